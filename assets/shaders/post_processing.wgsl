@@ -0,0 +1,142 @@
+// Post processing stack of the game camera, see `src/post_process.rs`.
+
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct PostProcessSettings {
+    lut_intensity: f32,
+    pixel_size: f32,
+    color_levels: f32,
+    dither_strength: f32,
+    vignette_color: vec4<f32>,
+    vignette_intensity: f32,
+    vignette_smoothness: f32,
+    outline_color: vec4<f32>,
+    outline_thickness: f32,
+    outline_depth_threshold: f32,
+}
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var screen_sampler: sampler;
+@group(0) @binding(2) var<uniform> settings: PostProcessSettings;
+@group(0) @binding(3) var lut_texture: texture_2d<f32>;
+@group(0) @binding(4) var lut_sampler: sampler;
+#ifdef DEPTH_OUTLINE
+#ifdef MULTISAMPLED
+@group(0) @binding(5) var depth_texture: texture_depth_multisampled_2d;
+#else
+@group(0) @binding(5) var depth_texture: texture_depth_2d;
+#endif
+#endif
+
+fn linear_to_srgb(color: vec3<f32>) -> vec3<f32> {
+    let low = color * 12.92;
+    let high = 1.055 * pow(color, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, color <= vec3<f32>(0.0031308));
+}
+
+fn srgb_to_linear(color: vec3<f32>) -> vec3<f32> {
+    let low = color / 12.92;
+    let high = pow((color + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, color <= vec3<f32>(0.04045));
+}
+
+// Looks the color up in a strip LUT of `size` slices of `size` x `size` pixels,
+// the blue channel selecting the slice.
+fn apply_lut(color: vec3<f32>) -> vec3<f32> {
+    let size = f32(textureDimensions(lut_texture).y);
+    let scaled = clamp(linear_to_srgb(color), vec3<f32>(0.0), vec3<f32>(1.0)) * (size - 1.0);
+    let slice = floor(scaled.b);
+    let next_slice = min(slice + 1.0, size - 1.0);
+    let y = (scaled.g + 0.5) / size;
+    let uv = vec2<f32>((slice * size + scaled.r + 0.5) / (size * size), y);
+    let next_uv = vec2<f32>((next_slice * size + scaled.r + 0.5) / (size * size), y);
+    return mix(
+        textureSampleLevel(lut_texture, lut_sampler, uv, 0.0).rgb,
+        textureSampleLevel(lut_texture, lut_sampler, next_uv, 0.0).rgb,
+        scaled.b - slice,
+    );
+}
+
+// Ordered dithering threshold between -0.5 and 0.5 from a 4x4 Bayer matrix.
+fn bayer_threshold(pixel: vec2<u32>) -> f32 {
+    var matrix = array<f32, 16>(
+        0.0, 8.0, 2.0, 10.0,
+        12.0, 4.0, 14.0, 6.0,
+        3.0, 11.0, 1.0, 9.0,
+        15.0, 7.0, 13.0, 5.0,
+    );
+    return matrix[(pixel.y % 4u) * 4u + pixel.x % 4u] / 16.0 - 0.5;
+}
+
+#ifdef DEPTH_OUTLINE
+fn load_depth(pixel: vec2<i32>) -> f32 {
+    let size = vec2<i32>(textureDimensions(depth_texture));
+    let clamped = clamp(pixel, vec2<i32>(0), size - 1);
+    // the last argument is the sample index when multisampled and the mip level otherwise
+    return textureLoad(depth_texture, clamped, 0);
+}
+
+// How much of the outline color to apply, found by comparing the depth of the
+// neighbouring pixels. With a reversed infinite perspective projection the
+// ratio of two NDC depths is the inverse ratio of their view depths.
+fn outline(pixel: vec2<i32>) -> f32 {
+    let thickness = i32(settings.outline_thickness);
+    if thickness <= 0 {
+        return 0.0;
+    }
+
+    var offsets = array<vec2<i32>, 4>(
+        vec2<i32>(thickness, 0),
+        vec2<i32>(-thickness, 0),
+        vec2<i32>(0, thickness),
+        vec2<i32>(0, -thickness),
+    );
+
+    let depth = load_depth(pixel);
+    var edge = 0.0;
+    for (var i = 0; i < 4; i++) {
+        let neighbour_depth = load_depth(pixel + offsets[i]);
+        let nearest = max(depth, neighbour_depth);
+        let farthest = min(depth, neighbour_depth);
+        if nearest > 0.0 && nearest / max(farthest, 1e-6) - 1.0 > settings.outline_depth_threshold {
+            edge = 1.0;
+        }
+    }
+
+    return edge * settings.outline_color.a;
+}
+#endif
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let size = vec2<f32>(textureDimensions(screen_texture));
+
+    // snap to the center of the pixelation cell
+    let pixel_size = max(settings.pixel_size, 1.0);
+    let cell = floor(in.uv * size / pixel_size);
+    let pixel = (cell + 0.5) * pixel_size;
+    var color = textureSampleLevel(screen_texture, screen_sampler, pixel / size, 0.0);
+
+#ifdef DEPTH_OUTLINE
+    let outline_mask = outline(vec2<i32>(pixel));
+    color = vec4<f32>(mix(color.rgb, settings.outline_color.rgb, outline_mask), color.a);
+#endif
+
+    if settings.lut_intensity > 0.0 {
+        color = vec4<f32>(mix(color.rgb, apply_lut(color.rgb), settings.lut_intensity), color.a);
+    }
+
+    if settings.color_levels > 1.0 {
+        let levels = settings.color_levels - 1.0;
+        let dither = bayer_threshold(vec2<u32>(cell)) * settings.dither_strength;
+        let srgb = linear_to_srgb(color.rgb);
+        let quantized = floor(srgb * levels + 0.5 + dither) / levels;
+        color = vec4<f32>(srgb_to_linear(clamp(quantized, vec3<f32>(0.0), vec3<f32>(1.0))), color.a);
+    }
+
+    let distance = length(in.uv - 0.5) * 1.4142135;
+    let vignette = smoothstep(1.0 - settings.vignette_smoothness, 1.0, distance) * settings.vignette_intensity;
+    color = vec4<f32>(mix(color.rgb, settings.vignette_color.rgb, clamp(vignette, 0.0, 1.0)), color.a);
+
+    return color;
+}
@@ -7,26 +7,150 @@
 #import bevy_pbr::{
     prepass_io::{VertexOutput, FragmentOutput},
     pbr_deferred_functions::deferred_output,
+    pbr_deferred_types::{pack_unorm4x8_, unpack_unorm4x8_},
 }
 #else
 #import bevy_pbr::{
     forward_io::{VertexOutput, FragmentOutput},
     pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
+    mesh_view_bindings::lights,
+    view_transformations::depth_ndc_to_view_z,
 }
+#ifdef DEPTH_PREPASS
+#import bevy_pbr::prepass_utils::prepass_depth
+#endif
+#ifdef NORMAL_PREPASS
+#import bevy_pbr::prepass_utils::prepass_normal
+#endif
 #endif
 
 struct MyExtendedMaterial {
     quantize_steps: u32,
+    shadow_color: vec4<f32>,
+    highlight_color: vec4<f32>,
+    rim_color: vec4<f32>,
+    rim_threshold: f32,
+    specular_color: vec4<f32>,
+    specular_threshold: f32,
+    glossiness: f32,
+    outline_color: vec4<f32>,
+    outline_thickness: f32,
+    outline_depth_threshold: f32,
+    outline_normal_threshold: f32,
 }
 
 @group(2) @binding(100)
 var<uniform> my_extended_material: MyExtendedMaterial;
 
+#ifdef TOON_RAMP
+@group(2) @binding(101)
+var ramp_texture: texture_2d<f32>;
+@group(2) @binding(102)
+var ramp_sampler: sampler;
+#endif
+
+#ifndef PREPASS_PIPELINE
+// Snaps the luminance of the lit color to `quantize_steps` bands and tints the
+// result with the band colors or the ramp texture.
+fn quantize(lit: vec3<f32>) -> vec3<f32> {
+    let steps = f32(max(my_extended_material.quantize_steps, 1u));
+    let luminance = dot(lit, vec3<f32>(0.2126, 0.7152, 0.0722));
+    if luminance <= 0.0 {
+        return lit;
+    }
+
+    let band = min(floor(luminance * steps), steps - 1.0);
+    let quantized = (band + 1.0) / steps;
+
+#ifdef TOON_RAMP
+    let tint = textureSample(ramp_texture, ramp_sampler, vec2<f32>((band + 0.5) / steps, 0.5)).rgb;
+#else
+    let tint = mix(
+        my_extended_material.shadow_color.rgb,
+        my_extended_material.highlight_color.rgb,
+        band / max(steps - 1.0, 1.0),
+    );
+#endif
+
+    return lit * (quantized / luminance) * tint;
+}
+
+// Hard edged specular highlight from the first directional light.
+fn specular_highlight(N: vec3<f32>, V: vec3<f32>) -> vec3<f32> {
+    if lights.n_directional_lights == 0u {
+        return vec3<f32>(0.0);
+    }
+
+    let L = lights.directional_lights[0].direction_to_light;
+    let H = normalize(L + V);
+    let intensity = pow(max(dot(N, H), 0.0), my_extended_material.glossiness);
+    let highlight = step(my_extended_material.specular_threshold, intensity) * step(0.0, dot(N, L));
+    return my_extended_material.specular_color.rgb * my_extended_material.specular_color.a * highlight;
+}
+
+// Hard edged rim light on the silhouette of the mesh.
+fn rim_light(N: vec3<f32>, V: vec3<f32>) -> vec3<f32> {
+    let rim = 1.0 - max(dot(N, V), 0.0);
+    let threshold = my_extended_material.rim_threshold;
+    let mask = smoothstep(threshold - 0.01, threshold + 0.01, rim);
+    return my_extended_material.rim_color.rgb * my_extended_material.rim_color.a * mask;
+}
+
+// How much of the outline color to apply, found by comparing the prepass depth
+// and normals of the neighbouring pixels. Requires the camera to have a
+// `DepthPrepass` and `NormalPrepass`.
+fn outline(frag_coord: vec4<f32>, sample_index: u32) -> f32 {
+    let thickness = my_extended_material.outline_thickness;
+    if thickness <= 0.0 {
+        return 0.0;
+    }
+
+    var offsets = array<vec2<f32>, 4>(
+        vec2<f32>(thickness, 0.0),
+        vec2<f32>(-thickness, 0.0),
+        vec2<f32>(0.0, thickness),
+        vec2<f32>(0.0, -thickness),
+    );
+
+    var edge = 0.0;
+#ifdef DEPTH_PREPASS
+    let depth = -depth_ndc_to_view_z(prepass_depth(frag_coord, sample_index));
+#endif
+#ifdef NORMAL_PREPASS
+    let normal = prepass_normal(frag_coord, sample_index);
+#endif
+    for (var i = 0; i < 4; i++) {
+        let neighbour = vec4<f32>(frag_coord.xy + offsets[i], frag_coord.zw);
+#ifdef DEPTH_PREPASS
+        let neighbour_depth = -depth_ndc_to_view_z(prepass_depth(neighbour, sample_index));
+        if abs(neighbour_depth - depth) / depth > my_extended_material.outline_depth_threshold {
+            edge = 1.0;
+        }
+#endif
+#ifdef NORMAL_PREPASS
+        let neighbour_normal = prepass_normal(neighbour, sample_index);
+        if 1.0 - dot(normal, neighbour_normal) > my_extended_material.outline_normal_threshold {
+            edge = 1.0;
+        }
+#endif
+    }
+
+    return edge * my_extended_material.outline_color.a;
+}
+#endif
+
 @fragment
 fn fragment(
     in: VertexOutput,
     @builtin(front_facing) is_front: bool,
+#ifdef MULTISAMPLED
+    @builtin(sample_index) sample_index: u32,
+#endif
 ) -> FragmentOutput {
+#ifndef MULTISAMPLED
+    let sample_index = 0u;
+#endif
+
     // generate a PbrInput struct from the StandardMaterial bindings
     var pbr_input = pbr_input_from_standard_material(in, is_front);
 
@@ -35,21 +159,32 @@ fn fragment(
 
 #ifdef PREPASS_PIPELINE
     // in deferred mode we can't modify anything after that, as lighting is run in a separate fullscreen shader.
-    let out = deferred_output(in, pbr_input);
+    var out = deferred_output(in, pbr_input);
+#ifndef WEBGL2
+    // store the band count in the spare channel of the gbuffer properties so that
+    // `toon_deferred_lighting.wgsl` can quantize the lit color.
+    var props = unpack_unorm4x8_(out.deferred.b);
+    props.a = f32(my_extended_material.quantize_steps) / 255.0;
+    out.deferred.b = pack_unorm4x8_(props);
+#endif
 #else
     var out: FragmentOutput;
     // apply lighting
     out.color = apply_pbr_lighting(pbr_input);
 
-    // we can optionally modify the lit color before post-processing is applied
-    //out.color = vec4<f32>(vec4<u32>(out.color * f32(my_extended_material.quantize_steps))) / f32(my_extended_material.quantize_steps);
+    // quantize the lit color into bands, then add the hard edged highlights on top
+    let toon = quantize(out.color.rgb)
+        + specular_highlight(pbr_input.N, pbr_input.V)
+        + rim_light(pbr_input.N, pbr_input.V);
+    out.color = vec4<f32>(toon, out.color.a);
 
     // apply in-shader post processing (fog, alpha-premultiply, and also tonemapping, debanding if the camera is non-hdr)
     // note this does not include fullscreen postprocessing effects like bloom.
     out.color = main_pass_post_lighting_processing(pbr_input, out.color);
 
-    // we can optionally modify the final result here
-    out.color = out.color;
+    // draw the outline over the final color so it isn't affected by tonemapping
+    let outline_mask = outline(in.position, sample_index);
+    out.color = vec4<f32>(mix(out.color.rgb, my_extended_material.outline_color.rgb, outline_mask), out.color.a);
 #endif
 
     return out;
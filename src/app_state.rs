@@ -0,0 +1,176 @@
+use avian3d::prelude::*;
+use bevy::{
+    asset::{LoadState, RecursiveDependencyLoadState},
+    prelude::*,
+};
+
+use crate::{game_management::Health, gltf_extras::PlayerSpawn, CharacterController, Grounded};
+
+pub struct AppStatePlugin;
+
+impl Plugin for AppStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<AppState>()
+            .add_computed_state::<Gameplay>()
+            .enable_state_scoped_entities::<AppState>()
+            .enable_state_scoped_entities::<Gameplay>()
+            .init_resource::<LoadingAssets>()
+            .add_systems(Startup, pause_physics)
+            .add_systems(Update, finish_boot.run_if(in_state(AppState::Boot)))
+            .add_systems(Update, wait_for_assets.run_if(in_state(AppState::Loading)))
+            .add_systems(Update, toggle_pause.run_if(in_state(Gameplay)))
+            .add_systems(Update, detect_game_over.run_if(in_state(AppState::InGame)))
+            .add_systems(OnEnter(AppState::InGame), unpause_physics)
+            .add_systems(OnExit(AppState::InGame), pause_physics)
+            .add_systems(OnExit(AppState::GameOver), reset_player);
+    }
+}
+
+/// The top level state of the game.
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum AppState {
+    #[default]
+    Boot,
+    MainMenu,
+    /// Waiting on the assets in [`LoadingAssets`].
+    Loading,
+    InGame,
+    Paused,
+    GameOver,
+}
+
+/// Handles that must be fully loaded before gameplay starts.
+#[derive(Resource, Default)]
+pub struct LoadingAssets(pub Vec<UntypedHandle>);
+
+impl LoadingAssets {
+    pub fn add<A: Asset>(&mut self, handle: Handle<A>) -> Handle<A> {
+        self.0.push(handle.clone().untyped());
+        handle
+    }
+}
+
+/// Active while a game is in progress, whether it is paused or not.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Gameplay;
+
+impl ComputedStates for Gameplay {
+    type SourceStates = AppState;
+
+    fn compute(sources: AppState) -> Option<Self> {
+        matches!(sources, AppState::InGame | AppState::Paused).then_some(Gameplay)
+    }
+}
+
+fn finish_boot(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::MainMenu);
+}
+
+/// Starts the game once every asset in [`LoadingAssets`] is loaded, or goes back to the
+/// main menu if one of them fails to load.
+fn wait_for_assets(
+    asset_server: Res<AssetServer>,
+    loading_assets: Res<LoadingAssets>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let failed: Vec<_> = loading_assets
+        .0
+        .iter()
+        .filter(|handle| {
+            matches!(
+                asset_server.get_load_state(handle.id()),
+                Some(LoadState::Failed(_))
+            ) || matches!(
+                asset_server.get_recursive_dependency_load_state(handle.id()),
+                Some(RecursiveDependencyLoadState::Failed)
+            )
+        })
+        .collect();
+    if !failed.is_empty() {
+        for handle in failed {
+            match handle.path() {
+                Some(path) => error!("Could not load {path}, returning to the main menu"),
+                None => error!("Could not load {handle:?}, returning to the main menu"),
+            }
+        }
+        next_state.set(AppState::MainMenu);
+        return;
+    }
+
+    let loaded = loading_assets
+        .0
+        .iter()
+        .all(|handle| asset_server.is_loaded_with_dependencies(handle.id()));
+
+    if loaded {
+        next_state.set(AppState::InGame);
+    }
+}
+
+/// Pauses and resumes the game with P or the gamepad start button.
+fn toggle_pause(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepad_input: Res<ButtonInput<GamepadButton>>,
+    gamepads: Res<Gamepads>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let start_pressed = gamepads.iter().any(|gamepad| {
+        gamepad_input.just_pressed(GamepadButton {
+            gamepad,
+            button_type: GamepadButtonType::Start,
+        })
+    });
+
+    if !keyboard_input.just_pressed(KeyCode::KeyP) && !start_pressed {
+        return;
+    }
+
+    match state.get() {
+        AppState::InGame => next_state.set(AppState::Paused),
+        AppState::Paused => next_state.set(AppState::InGame),
+        _ => {}
+    }
+}
+
+fn detect_game_over(
+    query: Query<&Health, With<CharacterController>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if query.iter().any(|health| health.current <= 0.0) {
+        next_state.set(AppState::GameOver);
+    }
+}
+
+/// Restores the player's health and moves them back to the level's spawn point.
+fn reset_player(
+    q_spawns: Query<&GlobalTransform, With<PlayerSpawn>>,
+    mut q_player: Query<
+        (
+            &mut Transform,
+            &mut LinearVelocity,
+            &mut Health,
+            &mut Grounded,
+        ),
+        With<CharacterController>,
+    >,
+) {
+    let spawn = q_spawns.iter().next().map(GlobalTransform::translation);
+
+    for (mut transform, mut linear_velocity, mut health, mut grounded) in &mut q_player {
+        if let Some(spawn) = spawn {
+            transform.translation = spawn;
+        }
+        linear_velocity.0 = Vec3::ZERO;
+        health.current = health.max;
+        *grounded = Grounded::default();
+    }
+}
+
+fn pause_physics(mut time: ResMut<Time<Physics>>) {
+    time.pause();
+}
+
+fn unpause_physics(mut time: ResMut<Time<Physics>>) {
+    time.unpause();
+}
@@ -0,0 +1,302 @@
+//! Colliders authored in the editor, saved in authored scenes as an [`AuthoredCollider`]
+//! from which the physics components are built.
+use avian3d::prelude::*;
+use bevy::prelude::*;
+
+use crate::game_management::GameLayer;
+
+/// Builds the rigid body and collider of entities with an [`AuthoredCollider`].
+pub struct AuthoredColliderPlugin;
+
+impl Plugin for AuthoredColliderPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<AuthoredCollider>().add_systems(
+            Update,
+            (remove_authored_colliders, build_authored_colliders),
+        );
+    }
+}
+
+/// The shape of an [`AuthoredCollider`].
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[reflect(Default)]
+pub enum AuthoredShape {
+    #[default]
+    Cuboid,
+    Sphere,
+    Capsule,
+    Cylinder,
+    /// Built from the meshes of the entity and its descendants.
+    ConvexHull,
+    /// Built from the meshes of the entity and its descendants. Only static bodies get
+    /// a trimesh, other bodies fall back to a convex hull.
+    Trimesh,
+}
+
+impl AuthoredShape {
+    /// The axes of [`AuthoredCollider::size`] the shape uses.
+    pub fn size_axes(self) -> &'static [usize] {
+        match self {
+            Self::Cuboid => &[0, 1, 2],
+            Self::Sphere => &[0],
+            Self::Capsule | Self::Cylinder => &[0, 1],
+            Self::ConvexHull | Self::Trimesh => &[],
+        }
+    }
+
+    /// The collider of a primitive shape of the given size, `None` for mesh shapes.
+    pub fn collider(self, size: Vec3) -> Option<Collider> {
+        let radius = size.x / 2.;
+        match self {
+            Self::Cuboid => Some(Collider::cuboid(size.x, size.y, size.z)),
+            Self::Sphere => Some(Collider::sphere(radius)),
+            Self::Capsule => Some(Collider::capsule(radius, (size.y - size.x).max(0.))),
+            Self::Cylinder => Some(Collider::cylinder(radius, size.y)),
+            Self::ConvexHull | Self::Trimesh => None,
+        }
+    }
+
+    fn constructor(self, rigid_body: RigidBody) -> Option<ColliderConstructor> {
+        match self {
+            Self::ConvexHull => Some(ColliderConstructor::ConvexHullFromMesh),
+            // Trimeshes are hollow and collide poorly when they move
+            Self::Trimesh if rigid_body.is_static() => Some(ColliderConstructor::TrimeshFromMesh),
+            Self::Trimesh => Some(ColliderConstructor::ConvexHullFromMesh),
+            _ => None,
+        }
+    }
+}
+
+/// A set of [`GameLayer`]s, edited as a checkbox per layer.
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[reflect(Default)]
+pub struct GameLayers {
+    pub default: bool,
+    pub player: bool,
+    pub enemy: bool,
+    pub ground: bool,
+    pub projectile: bool,
+}
+
+impl GameLayers {
+    pub const ALL: Self = Self {
+        default: true,
+        player: true,
+        enemy: true,
+        ground: true,
+        projectile: true,
+    };
+
+    pub fn contains(&self, layer: GameLayer) -> bool {
+        match layer {
+            GameLayer::Default => self.default,
+            GameLayer::Player => self.player,
+            GameLayer::Enemy => self.enemy,
+            GameLayer::Ground => self.ground,
+            GameLayer::Projectile => self.projectile,
+        }
+    }
+
+    pub fn layer_mask(&self) -> LayerMask {
+        GameLayer::ALL
+            .into_iter()
+            .filter(|layer| self.contains(*layer))
+            .fold(LayerMask::NONE, |mask, layer| mask | LayerMask::from(layer))
+    }
+}
+
+impl From<GameLayer> for GameLayers {
+    fn from(layer: GameLayer) -> Self {
+        Self {
+            default: layer == GameLayer::Default,
+            player: layer == GameLayer::Player,
+            enemy: layer == GameLayer::Enemy,
+            ground: layer == GameLayer::Ground,
+            projectile: layer == GameLayer::Projectile,
+        }
+    }
+}
+
+/// A collider authored in the editor, replacing the one a level generates for the
+/// meshes of the entity.
+#[derive(Component, Reflect, Clone, Debug, PartialEq)]
+#[reflect(Component, Default)]
+pub struct AuthoredCollider {
+    pub shape: AuthoredShape,
+    /// The extents of primitive shapes. Spheres, capsules and cylinders use x as
+    /// their diameter and capsules and cylinders y as their height.
+    pub size: Vec3,
+    /// The center of primitive shapes relative to the entity.
+    pub offset: Vec3,
+    pub rigid_body: RigidBody,
+    /// Whether the collider only detects bodies instead of blocking them.
+    pub sensor: bool,
+    pub memberships: GameLayers,
+    pub filters: GameLayers,
+}
+
+impl Default for AuthoredCollider {
+    fn default() -> Self {
+        Self {
+            shape: AuthoredShape::default(),
+            size: Vec3::ONE,
+            offset: Vec3::ZERO,
+            rigid_body: RigidBody::Static,
+            sensor: false,
+            memberships: GameLayer::Default.into(),
+            filters: GameLayers::ALL,
+        }
+    }
+}
+
+impl AuthoredCollider {
+    pub fn collision_layers(&self) -> CollisionLayers {
+        CollisionLayers::new(self.memberships.layer_mask(), self.filters.layer_mask())
+    }
+
+    /// The collider of a primitive shape, moved to the offset.
+    pub fn collider(&self) -> Option<Collider> {
+        let collider = self.shape.collider(self.size)?;
+        if self.offset == Vec3::ZERO {
+            Some(collider)
+        } else {
+            Some(Collider::compound(vec![(
+                self.offset,
+                Quat::IDENTITY,
+                collider,
+            )]))
+        }
+    }
+}
+
+/// Removes the colliders built for the entity and for the meshes of its descendants.
+fn clear_built_colliders(
+    commands: &mut Commands,
+    entity: Entity,
+    q_children: &Query<&Children>,
+    q_meshes: &Query<(), With<Handle<Mesh>>>,
+) {
+    commands.entity(entity).remove::<(
+        Collider,
+        ColliderConstructor,
+        ColliderConstructorHierarchy,
+        Sensor,
+    )>();
+    for descendant in q_children.iter_descendants(entity) {
+        if q_meshes.contains(descendant) {
+            commands
+                .entity(descendant)
+                .remove::<(Collider, ColliderConstructor, CollisionLayers, Sensor)>();
+        }
+    }
+}
+
+fn build_authored_colliders(
+    mut commands: Commands,
+    q_colliders: Query<(Entity, &AuthoredCollider), Changed<AuthoredCollider>>,
+    q_children: Query<&Children>,
+    q_meshes: Query<(), With<Handle<Mesh>>>,
+) {
+    for (entity, authored) in &q_colliders {
+        clear_built_colliders(&mut commands, entity, &q_children, &q_meshes);
+
+        let layers = authored.collision_layers();
+        let mut body = commands.entity(entity);
+        body.insert((authored.rigid_body, layers));
+        if authored.sensor {
+            body.insert(Sensor);
+        }
+        if let Some(collider) = authored.collider() {
+            body.insert(collider);
+        } else if let Some(constructor) = authored.shape.constructor(authored.rigid_body) {
+            body.insert(
+                ColliderConstructorHierarchy::new(Some(constructor)).with_default_layers(layers),
+            );
+        }
+    }
+}
+
+fn remove_authored_colliders(
+    mut commands: Commands,
+    mut removed: RemovedComponents<AuthoredCollider>,
+    q_children: Query<&Children>,
+    q_meshes: Query<(), With<Handle<Mesh>>>,
+) {
+    for entity in removed.read() {
+        let Some(mut body) = commands.get_entity(entity) else {
+            continue;
+        };
+        body.remove::<(RigidBody, CollisionLayers)>();
+        clear_built_colliders(&mut commands, entity, &q_children, &q_meshes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collision_layers_follow_the_checked_game_layers() {
+        let collider = AuthoredCollider {
+            memberships: GameLayer::Ground.into(),
+            filters: GameLayers {
+                player: true,
+                enemy: true,
+                ..default()
+            },
+            ..default()
+        };
+
+        let layers = collider.collision_layers();
+        assert_eq!(layers.memberships, LayerMask::from(GameLayer::Ground));
+        assert_eq!(
+            layers.filters,
+            LayerMask::from(GameLayer::Player) | LayerMask::from(GameLayer::Enemy)
+        );
+    }
+
+    #[test]
+    fn authored_colliders_build_their_physics_components() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AuthoredColliderPlugin));
+
+        let sensor = app
+            .world_mut()
+            .spawn(AuthoredCollider {
+                shape: AuthoredShape::Sphere,
+                size: Vec3::splat(2.),
+                sensor: true,
+                memberships: GameLayer::Ground.into(),
+                filters: GameLayer::Player.into(),
+                ..default()
+            })
+            .id();
+        let prop = app
+            .world_mut()
+            .spawn(AuthoredCollider {
+                shape: AuthoredShape::Trimesh,
+                rigid_body: RigidBody::Dynamic,
+                ..default()
+            })
+            .id();
+        app.update();
+
+        let world = app.world();
+        let ball = world.get::<Collider>(sensor).unwrap().shape().as_ball();
+        assert_eq!(ball.map(|ball| ball.radius), Some(1.));
+        assert!(world.get::<Sensor>(sensor).is_some());
+        assert_eq!(world.get::<RigidBody>(sensor), Some(&RigidBody::Static));
+        assert_eq!(
+            world.get::<CollisionLayers>(sensor),
+            Some(&CollisionLayers::new(GameLayer::Ground, GameLayer::Player))
+        );
+
+        assert!(world.get::<Collider>(prop).is_none());
+        assert!(world.get::<Sensor>(prop).is_none());
+        let hierarchy = world.get::<ColliderConstructorHierarchy>(prop).unwrap();
+        assert!(matches!(
+            hierarchy.default_constructor,
+            Some(ColliderConstructor::ConvexHullFromMesh)
+        ));
+    }
+}
@@ -0,0 +1,226 @@
+//! Scenes authored in the editor, saved as `.scn.ron` [`DynamicScene`] files.
+//!
+//! glTF scenes placed in an authored scene are saved as a [`GltfInstance`] holding
+//! their asset path rather than as the entities they spawn. Gameplay properties are
+//! saved as [`GltfExtras`], which the game converts like the custom properties of
+//! nodes authored in Blender.
+use bevy::{ecs::entity::EntityHashSet, gltf::GltfExtras, prelude::*};
+
+use crate::authored_collider::AuthoredCollider;
+
+/// The extension of authored scene files.
+pub const AUTHORED_SCENE_EXTENSION: &str = "scn.ron";
+
+/// Spawns the glTF scenes referenced by authored scenes.
+pub struct AuthoredScenePlugin;
+
+impl Plugin for AuthoredScenePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<GltfInstance>()
+            .add_systems(PreUpdate, spawn_gltf_instances);
+    }
+}
+
+/// An instance of the glTF scene at the given asset path, e.g. `models/Scene.glb#Scene0`.
+#[derive(Component, Reflect, Clone, Debug, Default, PartialEq, Eq)]
+#[reflect(Component, Default)]
+pub struct GltfInstance {
+    pub path: String,
+}
+
+/// Whether the asset path is an authored scene rather than a glTF scene.
+pub fn is_authored_scene(path: &str) -> bool {
+    path.ends_with(&format!(".{AUTHORED_SCENE_EXTENSION}"))
+}
+
+fn spawn_gltf_instances(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    q_instances: Query<(Entity, &GltfInstance), Changed<GltfInstance>>,
+) {
+    for (entity, instance) in &q_instances {
+        // A changed handle replaces the scene spawned by the previous one
+        commands
+            .entity(entity)
+            .insert(asset_server.load::<Scene>(&instance.path));
+    }
+}
+
+/// Extracts the descendants of `root` into a scene, leaving out the entities spawned
+/// by [`GltfInstance`]s. The children of `root` become the roots of the scene.
+pub fn extract_authored_scene(world: &World, root: Entity) -> DynamicScene {
+    let mut entities = Vec::new();
+    let mut stack: Vec<Entity> = world
+        .get::<Children>(root)
+        .map(|children| children.to_vec())
+        .unwrap_or_default();
+    while let Some(entity) = stack.pop() {
+        entities.push(entity);
+        if world.get::<GltfInstance>(entity).is_some() {
+            continue;
+        }
+        if let Some(children) = world.get::<Children>(entity) {
+            stack.extend(children.iter());
+        }
+    }
+
+    let mut scene = DynamicSceneBuilder::from_world(world)
+        .allow::<Name>()
+        .allow::<Transform>()
+        .allow::<GlobalTransform>()
+        .allow::<Visibility>()
+        .allow::<InheritedVisibility>()
+        .allow::<ViewVisibility>()
+        .allow::<Parent>()
+        .allow::<Children>()
+        .allow::<GltfExtras>()
+        .allow::<GltfInstance>()
+        .allow::<AuthoredCollider>()
+        .extract_entities(entities.into_iter())
+        .build();
+
+    // Scene roots are spawned under whatever loads the scene, and glTF instances
+    // spawn their own children
+    let roots: EntityHashSet = world
+        .get::<Children>(root)
+        .map(|children| children.iter().copied().collect())
+        .unwrap_or_default();
+    for entity in &mut scene.entities {
+        let instance = world.get::<GltfInstance>(entity.entity).is_some();
+        let root = roots.contains(&entity.entity);
+        entity.components.retain(|component| {
+            !((root && component.represents::<Parent>())
+                || (instance && component.represents::<Children>()))
+        });
+    }
+
+    scene
+}
+
+/// Serializes the descendants of `root` as an authored scene, see [`extract_authored_scene`].
+pub fn save_authored_scene(world: &World, root: Entity) -> Result<String, ron::Error> {
+    let scene = extract_authored_scene(world, root);
+    let registry = world.resource::<AppTypeRegistry>().read();
+    scene.serialize(&registry)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{ecs::entity::EntityHashMap, scene::serde::SceneDeserializer};
+    use serde::de::DeserializeSeed;
+
+    use super::*;
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, TransformPlugin, HierarchyPlugin))
+            .register_type::<Visibility>()
+            .register_type::<InheritedVisibility>()
+            .register_type::<ViewVisibility>()
+            .register_type::<GltfExtras>()
+            .register_type::<GltfInstance>();
+        app
+    }
+
+    fn load(world: &mut World, ron: &str) -> Entity {
+        let scene = {
+            let registry = world.resource::<AppTypeRegistry>().read();
+            let mut deserializer = ron::de::Deserializer::from_str(ron).unwrap();
+            SceneDeserializer {
+                type_registry: &registry,
+            }
+            .deserialize(&mut deserializer)
+            .unwrap()
+        };
+
+        let root = world.spawn(SpatialBundle::default()).id();
+        let mut entity_map = EntityHashMap::default();
+        scene.write_to_world(world, &mut entity_map).unwrap();
+        let scene_roots: Vec<_> = entity_map
+            .values()
+            .copied()
+            .filter(|entity| world.get::<Parent>(*entity).is_none())
+            .collect();
+        world.entity_mut(root).push_children(&scene_roots);
+        root
+    }
+
+    fn find(world: &mut World, name: &str) -> Option<Entity> {
+        world
+            .query::<(Entity, &Name)>()
+            .iter(world)
+            .find_map(|(entity, n)| (n.as_str() == name).then_some(entity))
+    }
+
+    #[test]
+    fn authored_scenes_round_trip_through_ron() {
+        let mut app = test_app();
+        let world = app.world_mut();
+        let root = world.spawn(SpatialBundle::default()).id();
+
+        let instance = world
+            .spawn((
+                Name::new("Arena"),
+                SpatialBundle::from_transform(Transform::from_xyz(0., -1., 0.)),
+                GltfInstance {
+                    path: "models/Scene.glb#Scene0".into(),
+                },
+            ))
+            .set_parent(root)
+            .id();
+        world
+            .spawn((Name::new("Arena mesh"), SpatialBundle::default()))
+            .set_parent(instance);
+
+        let group = world
+            .spawn((Name::new("Spawns"), SpatialBundle::default()))
+            .set_parent(root)
+            .id();
+        world
+            .spawn((
+                Name::new("Player spawn"),
+                SpatialBundle::from_transform(Transform::from_xyz(2., 0., 3.)),
+                GltfExtras {
+                    value: r#"{"PlayerSpawn":"()"}"#.into(),
+                },
+            ))
+            .set_parent(group);
+
+        let ron = save_authored_scene(world, root).unwrap();
+
+        let mut loaded_app = test_app();
+        let loaded = loaded_app.world_mut();
+        let loaded_root = load(loaded, &ron);
+        assert_eq!(loaded.get::<Children>(loaded_root).unwrap().len(), 2);
+
+        let instance = find(loaded, "Arena").unwrap();
+        assert_eq!(
+            loaded.get::<GltfInstance>(instance).unwrap().path,
+            "models/Scene.glb#Scene0"
+        );
+        assert_eq!(
+            loaded.get::<Transform>(instance).unwrap().translation,
+            Vec3::new(0., -1., 0.)
+        );
+        assert!(loaded.get::<Children>(instance).is_none());
+        assert!(find(loaded, "Arena mesh").is_none());
+
+        let group = find(loaded, "Spawns").unwrap();
+        let spawn = find(loaded, "Player spawn").unwrap();
+        assert_eq!(loaded.get::<Parent>(spawn).map(Parent::get), Some(group));
+        assert_eq!(
+            loaded.get::<GltfExtras>(spawn).unwrap().value,
+            r#"{"PlayerSpawn":"()"}"#
+        );
+        assert_eq!(
+            loaded.get::<Transform>(spawn).unwrap().translation,
+            Vec3::new(2., 0., 3.)
+        );
+    }
+
+    #[test]
+    fn only_authored_scene_paths_are_recognized() {
+        assert!(is_authored_scene("levels/arena.scn.ron"));
+        assert!(!is_authored_scene("models/Scene.glb#Scene0"));
+    }
+}
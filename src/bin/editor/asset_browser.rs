@@ -0,0 +1,591 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use artificer_3d::authored_scene::{is_authored_scene, GltfInstance};
+use bevy::{
+    gltf::Gltf,
+    prelude::*,
+    tasks::{block_on, futures_lite::future, IoTaskPool, Task},
+    window::PrimaryWindow,
+};
+use sickle_ui::{prelude::*, ui_commands::SetCursorExt};
+
+use crate::{
+    history::{History, SpawnCommand},
+    picking::SceneViewCursor,
+    play::PlayMode,
+    scene_file::ASSET_FOLDER,
+    selection::Selection,
+};
+
+/// How long after a scan the assets directory is scanned again for added, removed or
+/// changed files.
+const REFRESH_INTERVAL_SECONDS: f32 = 1.;
+
+/// How far in pixels the cursor moves from where a glTF scene was pressed before it is
+/// dragged.
+const DRAG_THRESHOLD: f32 = 4.;
+
+/// How far in front of the scene view camera a scene dropped above the horizon is placed.
+const DROP_DISTANCE: f32 = 10.;
+
+/// Lists the assets directory, and instances glTF scenes dragged into the scene view.
+pub struct AssetBrowserPlugin;
+
+impl Plugin for AssetBrowserPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AssetBrowser>().add_systems(
+            Update,
+            (
+                refresh_asset_tree,
+                rebuild_asset_browser,
+                press_asset_browser_node,
+                start_asset_drag,
+                drop_asset_in_scene_view.run_if(in_state(PlayMode::Editing)),
+                update_asset_browser_node_style,
+            )
+                .chain()
+                .after(WidgetLibraryUpdate),
+        );
+    }
+}
+
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct AssetBrowserPanel;
+
+/// The type of a listed asset, picking its icon and what pressing it does.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssetKind {
+    Folder,
+    Gltf,
+    GltfScene,
+    GltfMesh,
+    GltfMaterial,
+    Texture,
+    Shader,
+    AuthoredScene,
+    Ron,
+    Other,
+}
+
+impl AssetKind {
+    fn of(path: &Path) -> Self {
+        if path.is_dir() {
+            return AssetKind::Folder;
+        }
+        if is_authored_scene(&path.to_string_lossy()) {
+            return AssetKind::AuthoredScene;
+        }
+
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("glb" | "gltf") => AssetKind::Gltf,
+            Some("png" | "jpg" | "jpeg" | "ktx2" | "hdr") => AssetKind::Texture,
+            Some("wgsl") => AssetKind::Shader,
+            Some("ron") => AssetKind::Ron,
+            _ => AssetKind::Other,
+        }
+    }
+
+    /// The letter and color of the icon of the asset type.
+    fn icon(self) -> (&'static str, Color) {
+        match self {
+            AssetKind::Folder => ("D", Color::srgb(0.8, 0.65, 0.3)),
+            AssetKind::Gltf => ("G", Color::srgb(0.35, 0.6, 0.9)),
+            AssetKind::GltfScene => ("S", Color::srgb(0.3, 0.75, 0.55)),
+            AssetKind::GltfMesh => ("M", Color::srgb(0.55, 0.55, 0.85)),
+            AssetKind::GltfMaterial => ("m", Color::srgb(0.85, 0.45, 0.6)),
+            AssetKind::Texture => ("T", Color::srgb(0.6, 0.45, 0.8)),
+            AssetKind::Shader => ("W", Color::srgb(0.85, 0.5, 0.3)),
+            AssetKind::AuthoredScene => ("L", Color::srgb(0.3, 0.75, 0.55)),
+            AssetKind::Ron => ("R", Color::srgb(0.55, 0.55, 0.55)),
+            AssetKind::Other => ("?", Color::srgb(0.4, 0.4, 0.4)),
+        }
+    }
+
+    fn expandable(self) -> bool {
+        matches!(self, AssetKind::Folder | AssetKind::Gltf)
+    }
+}
+
+/// A file or folder of the assets directory.
+#[derive(Clone, Debug, PartialEq)]
+struct AssetEntry {
+    /// The asset path, relative to the assets directory.
+    path: String,
+    name: String,
+    kind: AssetKind,
+    children: Vec<AssetEntry>,
+}
+
+/// Lists the folder, folders first and then by name.
+fn scan_assets(folder: &Path, asset_path: &str) -> Vec<AssetEntry> {
+    let Ok(read_dir) = fs::read_dir(folder) else {
+        return Vec::new();
+    };
+
+    let mut entries: Vec<AssetEntry> = read_dir
+        .filter_map(Result::ok)
+        .map(|dir_entry| {
+            let file_path = dir_entry.path();
+            let name = dir_entry.file_name().to_string_lossy().to_string();
+            let path = if asset_path.is_empty() {
+                name.clone()
+            } else {
+                format!("{asset_path}/{name}")
+            };
+            let kind = AssetKind::of(&file_path);
+            let children = match kind {
+                AssetKind::Folder => scan_assets(&file_path, &path),
+                _ => Vec::new(),
+            };
+            AssetEntry {
+                path,
+                name,
+                kind,
+                children,
+            }
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        (a.kind != AssetKind::Folder, &a.name).cmp(&(b.kind != AssetKind::Folder, &b.name))
+    });
+    entries
+}
+
+#[derive(Resource)]
+struct AssetBrowser {
+    entries: Vec<AssetEntry>,
+    expanded: HashSet<String>,
+    /// The glTF files expanded at least once, to list their sub-assets.
+    gltfs: HashMap<String, Handle<Gltf>>,
+    refresh: Timer,
+    /// The scan of the assets directory running on the IO task pool.
+    scan: Option<Task<Vec<AssetEntry>>>,
+    /// Whether the listed assets changed since the panels were built.
+    dirty: bool,
+    /// The path of the pressed glTF scene and where the cursor was when it was pressed.
+    pressed: Option<(String, Vec2)>,
+    /// The path of the glTF scene being dragged towards the scene view.
+    dragged: Option<String>,
+}
+
+impl Default for AssetBrowser {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            expanded: HashSet::new(),
+            gltfs: HashMap::new(),
+            refresh: Timer::from_seconds(REFRESH_INTERVAL_SECONDS, TimerMode::Repeating),
+            scan: Some(spawn_scan()),
+            dirty: true,
+            pressed: None,
+            dragged: None,
+        }
+    }
+}
+
+/// Scans the assets directory without blocking the frame.
+fn spawn_scan() -> Task<Vec<AssetEntry>> {
+    IoTaskPool::get().spawn(async { scan_assets(Path::new(ASSET_FOLDER), "") })
+}
+
+/// A row of the asset browser.
+#[derive(Component, Debug)]
+pub struct AssetBrowserNode {
+    pub path: String,
+    pub kind: AssetKind,
+}
+
+/// The name of the glTF sub-asset, if it has one.
+fn sub_asset_name<'a, A: Asset>(
+    handle: &Handle<A>,
+    named: &'a HashMap<Box<str>, Handle<A>>,
+) -> Option<&'a str> {
+    named
+        .iter()
+        .find_map(|(name, named_handle)| (named_handle == handle).then_some(name.as_ref()))
+}
+
+/// The rows listing the sub-assets of a loaded glTF file.
+fn gltf_rows(path: &str, gltf: &Gltf) -> Vec<(String, String, AssetKind)> {
+    let mut rows = Vec::new();
+    let mut add = |label: String, name: Option<&str>, kind| {
+        let text = match name {
+            Some(name) => format!("#{label} {name}"),
+            None => format!("#{label}"),
+        };
+        rows.push((format!("{path}#{label}"), text, kind));
+    };
+
+    for (index, scene) in gltf.scenes.iter().enumerate() {
+        let name = sub_asset_name(scene, &gltf.named_scenes);
+        add(format!("Scene{index}"), name, AssetKind::GltfScene);
+    }
+    for (index, mesh) in gltf.meshes.iter().enumerate() {
+        let name = sub_asset_name(mesh, &gltf.named_meshes);
+        add(format!("Mesh{index}"), name, AssetKind::GltfMesh);
+    }
+    for (index, material) in gltf.materials.iter().enumerate() {
+        let name = sub_asset_name(material, &gltf.named_materials);
+        add(format!("Material{index}"), name, AssetKind::GltfMaterial);
+    }
+    rows
+}
+
+/// Scans the assets directory again once the previous scan is done, and notices
+/// reloaded glTF files.
+fn refresh_asset_tree(
+    time: Res<Time>,
+    mut browser: ResMut<AssetBrowser>,
+    mut gltf_events: EventReader<AssetEvent<Gltf>>,
+) {
+    let gltf_changed = gltf_events
+        .read()
+        .filter(|event| {
+            matches!(
+                event,
+                AssetEvent::LoadedWithDependencies { .. } | AssetEvent::Modified { .. }
+            )
+        })
+        .count()
+        > 0;
+    if gltf_changed {
+        browser.dirty = true;
+    }
+
+    let Some(task) = browser.scan.as_mut() else {
+        if browser.refresh.tick(time.delta()).just_finished() {
+            browser.scan = Some(spawn_scan());
+        }
+        return;
+    };
+    let Some(entries) = block_on(future::poll_once(task)) else {
+        return;
+    };
+
+    browser.scan = None;
+    if browser.entries != entries {
+        browser.entries = entries;
+        browser.dirty = true;
+    }
+}
+
+fn rebuild_asset_browser(
+    mut commands: Commands,
+    mut browser: ResMut<AssetBrowser>,
+    asset_server: Res<AssetServer>,
+    gltfs: Res<Assets<Gltf>>,
+    q_panels: Query<Entity, With<AssetBrowserPanel>>,
+    q_added_panels: Query<(), Added<AssetBrowserPanel>>,
+) {
+    if !browser.dirty && q_added_panels.is_empty() {
+        return;
+    }
+    browser.dirty = false;
+
+    // The rows as path, label, kind and depth, in the order they are listed
+    let mut rows = Vec::new();
+    let mut stack: Vec<(&AssetEntry, usize)> = browser
+        .entries
+        .iter()
+        .rev()
+        .map(|entry| (entry, 0))
+        .collect();
+    while let Some((entry, depth)) = stack.pop() {
+        rows.push((entry.path.clone(), entry.name.clone(), entry.kind, depth));
+        if !browser.expanded.contains(&entry.path) {
+            continue;
+        }
+
+        stack.extend(entry.children.iter().rev().map(|child| (child, depth + 1)));
+        let gltf = browser
+            .gltfs
+            .get(&entry.path)
+            .and_then(|handle| gltfs.get(handle));
+        if let Some(gltf) = gltf {
+            rows.extend(
+                gltf_rows(&entry.path, gltf)
+                    .into_iter()
+                    .map(|(path, label, kind)| (path, label, kind, depth + 1)),
+            );
+        }
+    }
+
+    for container in &q_panels {
+        commands.entity(container).despawn_descendants();
+        commands.ui_builder(container).column(|column| {
+            column.style().width(Val::Percent(100.));
+
+            for (path, label, kind, depth) in &rows {
+                let expander = match (kind.expandable(), browser.expanded.contains(path)) {
+                    (true, true) => "v ",
+                    (true, false) => "> ",
+                    (false, _) => "  ",
+                };
+
+                column
+                    .container(
+                        (
+                            ButtonBundle {
+                                style: Style {
+                                    width: Val::Percent(100.),
+                                    padding: UiRect::left(Val::Px(4. + 12. * *depth as f32)),
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: Color::NONE.into(),
+                                ..default()
+                            },
+                            AssetBrowserNode {
+                                path: path.clone(),
+                                kind: *kind,
+                            },
+                        ),
+                        |row| {
+                            row.label(LabelConfig {
+                                label: expander.into(),
+                                ..default()
+                            });
+                            if *kind == AssetKind::Texture {
+                                row.spawn(ImageBundle {
+                                    style: icon_style(),
+                                    image: UiImage::new(asset_server.load(path.clone())),
+                                    ..default()
+                                });
+                            } else {
+                                let (letter, color) = kind.icon();
+                                row.container(
+                                    NodeBundle {
+                                        style: icon_style(),
+                                        background_color: color.into(),
+                                        ..default()
+                                    },
+                                    |icon| {
+                                        icon.spawn(TextBundle::from_section(
+                                            letter,
+                                            TextStyle {
+                                                font_size: 11.,
+                                                ..default()
+                                            },
+                                        ));
+                                    },
+                                );
+                            }
+                            row.label(LabelConfig {
+                                label: label.clone(),
+                                ..default()
+                            });
+                        },
+                    )
+                    .insert(Name::new("Asset Browser Node"));
+            }
+        });
+    }
+}
+
+/// The style of the type icons and texture thumbnails.
+fn icon_style() -> Style {
+    Style {
+        width: Val::Px(16.),
+        height: Val::Px(16.),
+        margin: UiRect::right(Val::Px(4.)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    }
+}
+
+/// Expands and collapses folders and glTF files, and remembers where glTF scenes are
+/// pressed so they can be dragged.
+fn press_asset_browser_node(
+    mut browser: ResMut<AssetBrowser>,
+    asset_server: Res<AssetServer>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    q_nodes: Query<(&Interaction, &AssetBrowserNode), Changed<Interaction>>,
+) {
+    for (interaction, node) in &q_nodes {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        if node.kind.expandable() {
+            if !browser.expanded.remove(&node.path) {
+                browser.expanded.insert(node.path.clone());
+            }
+            if node.kind == AssetKind::Gltf && !browser.gltfs.contains_key(&node.path) {
+                let gltf = asset_server.load(node.path.clone());
+                browser.gltfs.insert(node.path.clone(), gltf);
+            }
+            browser.dirty = true;
+        }
+
+        let scene = match node.kind {
+            AssetKind::Gltf => Some(format!("{}#Scene0", node.path)),
+            AssetKind::GltfScene => Some(node.path.clone()),
+            _ => None,
+        };
+        let cursor = q_window.get_single().ok().and_then(Window::cursor_position);
+        if let (Some(scene), Some(cursor)) = (scene, cursor) {
+            browser.pressed = Some((scene, cursor));
+        }
+    }
+}
+
+/// Starts dragging the pressed glTF scene once the cursor moves away from where it was
+/// pressed, so pressing a glTF file only expands it.
+fn start_asset_drag(
+    mut commands: Commands,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut browser: ResMut<AssetBrowser>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+) {
+    let Some(pressed_at) = browser.pressed.as_ref().map(|(_, pressed_at)| *pressed_at) else {
+        return;
+    };
+    if !mouse_input.pressed(MouseButton::Left) {
+        browser.pressed = None;
+        return;
+    }
+    let Some(cursor) = q_window.get_single().ok().and_then(Window::cursor_position) else {
+        return;
+    };
+    if cursor.distance(pressed_at) < DRAG_THRESHOLD {
+        return;
+    }
+
+    browser.dragged = browser.pressed.take().map(|(scene, _)| scene);
+    commands.set_cursor(CursorIcon::Grabbing);
+}
+
+/// Instances the dragged glTF scene where it is released over the scene view, on the
+/// ground plane or in front of the camera.
+fn drop_asset_in_scene_view(
+    mut commands: Commands,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut browser: ResMut<AssetBrowser>,
+    mut history: ResMut<History>,
+    mut selection: ResMut<Selection>,
+    scene_view_cursor: SceneViewCursor,
+    q_global_transforms: Query<&GlobalTransform>,
+) {
+    if browser.dragged.is_none() || !mouse_input.just_released(MouseButton::Left) {
+        return;
+    }
+    let Some(path) = browser.dragged.take() else {
+        return;
+    };
+    commands.set_cursor(CursorIcon::Default);
+
+    let Some((asset_root, ray)) = scene_view_cursor.ray() else {
+        return;
+    };
+    let ground_distance = -ray.origin.y / ray.direction.y;
+    let distance = if ground_distance.is_finite() && ground_distance > 0. {
+        ground_distance
+    } else {
+        DROP_DISTANCE
+    };
+    let point = ray.get_point(distance);
+    let translation = q_global_transforms.get(asset_root).map_or(point, |root| {
+        root.affine().inverse().transform_point3(point)
+    });
+
+    let name = path
+        .rsplit('/')
+        .next()
+        .and_then(|file| file.split('.').next())
+        .unwrap_or(&path)
+        .to_string();
+    let instance = commands
+        .spawn((
+            Name::new(name),
+            SpatialBundle::from_transform(Transform::from_translation(translation)),
+            GltfInstance { path },
+        ))
+        .set_parent(asset_root)
+        .id();
+    history.record(SpawnCommand::new(vec![instance]));
+    selection.set(instance);
+}
+
+fn update_asset_browser_node_style(
+    browser: Res<AssetBrowser>,
+    mut q_nodes: Query<(&AssetBrowserNode, &Interaction, &mut BackgroundColor)>,
+) {
+    for (node, interaction, mut background_color) in &mut q_nodes {
+        let color = if browser.dragged.as_ref() == Some(&node.path) {
+            Color::srgba(0.25, 0.45, 0.8, 0.6)
+        } else if *interaction == Interaction::Hovered {
+            Color::srgba(1., 1., 1., 0.08)
+        } else {
+            Color::NONE
+        };
+
+        if background_color.0 != color {
+            background_color.0 = color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asset_kinds_follow_the_extension() {
+        assert_eq!(AssetKind::of(Path::new("Scene.GLB")), AssetKind::Gltf);
+        assert_eq!(AssetKind::of(Path::new("models/a.gltf")), AssetKind::Gltf);
+        assert_eq!(AssetKind::of(Path::new("grass.png")), AssetKind::Texture);
+        assert_eq!(AssetKind::of(Path::new("toon.wgsl")), AssetKind::Shader);
+        assert_eq!(
+            AssetKind::of(Path::new("levels/arena.scn.ron")),
+            AssetKind::AuthoredScene
+        );
+        assert_eq!(AssetKind::of(Path::new("day.lighting.ron")), AssetKind::Ron);
+        assert_eq!(AssetKind::of(Path::new("README")), AssetKind::Other);
+    }
+
+    #[test]
+    fn scanned_folders_come_first_and_are_sorted_by_name() {
+        let folder = std::env::temp_dir().join("artificer_asset_browser_scan");
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(folder.join("models")).unwrap();
+        fs::create_dir_all(folder.join("levels")).unwrap();
+        fs::write(folder.join("models/Scene.glb"), "").unwrap();
+        fs::write(folder.join("levels/arena.scn.ron"), "").unwrap();
+        fs::write(folder.join("a.png"), "").unwrap();
+
+        let entries = scan_assets(&folder, "");
+        fs::remove_dir_all(&folder).unwrap();
+
+        let listed: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.path.as_str(), entry.kind))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                ("levels", AssetKind::Folder),
+                ("models", AssetKind::Folder),
+                ("a.png", AssetKind::Texture),
+            ]
+        );
+        assert_eq!(
+            entries[1].children,
+            [AssetEntry {
+                path: "models/Scene.glb".into(),
+                name: "Scene.glb".into(),
+                kind: AssetKind::Gltf,
+                children: Vec::new(),
+            }]
+        );
+        assert_eq!(entries[0].children[0].kind, AssetKind::AuthoredScene);
+    }
+}
@@ -0,0 +1,461 @@
+use artificer_3d::authored_collider::{AuthoredCollider, AuthoredShape};
+use avian3d::prelude::*;
+use bevy::{ecs::entity::EntityHashMap, prelude::*, render::primitives::Aabb};
+use sickle_ui::prelude::*;
+
+use crate::{
+    gizmo::{ray_axis_closest, TransformGizmo},
+    history::{map_entity, CommandBatch, EditorCommand, History, HistoryWorldExt},
+    hotkeys::{GizmoHotkey, GizmoHotkeys},
+    picking::{pick_in_scene_view, SceneViewCursor},
+    play::PlayMode,
+    selection::Selection,
+    settings::EditorSettings,
+};
+
+/// The handle size relative to its distance from the camera, to keep it the same size on screen.
+const HANDLE_SCREEN_SIZE: f32 = 0.012;
+/// The smallest extent handles can shrink a collider to.
+const MIN_COLLIDER_SIZE: f32 = 0.01;
+
+/// The Physics menu adding [`AuthoredCollider`]s to the selection, handles resizing the
+/// collider of the primary selection and the debug rendering of every collider.
+pub struct ColliderToolsPlugin;
+
+impl Plugin for ColliderToolsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(PhysicsDebugPlugin::default())
+            .init_resource::<ColliderHandles>()
+            .add_systems(
+                PreUpdate,
+                run_physics_menu_items.run_if(in_state(PlayMode::Editing)),
+            )
+            .add_systems(
+                Update,
+                (
+                    (toggle_collider_rendering, show_collider_rendering).chain(),
+                    (
+                        grab_collider_handle,
+                        drag_collider_handle,
+                        draw_collider_handles,
+                    )
+                        .chain()
+                        .after(pick_in_scene_view)
+                        .run_if(in_state(PlayMode::Editing)),
+                ),
+            );
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PhysicsMenuItem {
+    /// Adds a collider of the shape to the selection, replacing the shape of those
+    /// that already have one.
+    AddCollider(AuthoredShape),
+    RemoveCollider,
+}
+
+/// The toggle menu item showing the colliders of the scene.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct ShowCollidersToggle;
+
+/// The dimension handles of the collider of the primary selection.
+#[derive(Resource, Debug, Default)]
+pub struct ColliderHandles {
+    hovered: Option<ColliderHandle>,
+    drag: Option<HandleDrag>,
+}
+
+impl ColliderHandles {
+    /// Whether the cursor is over a handle or dragging one, so clicks belong to the handles.
+    pub fn is_active(&self) -> bool {
+        self.hovered.is_some() || self.drag.is_some()
+    }
+}
+
+/// A handle on a face of the collider, moving it along the axis of the size it edits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ColliderHandle {
+    axis_index: usize,
+    /// 1 or -1, for the handle on the positive or negative side of the axis.
+    sign: f32,
+}
+
+#[derive(Debug)]
+struct HandleDrag {
+    entity: Entity,
+    handle: ColliderHandle,
+    start: AuthoredCollider,
+}
+
+/// A collider added, changed or removed, undone by restoring the previous one.
+pub struct ColliderCommand {
+    pub entity: Entity,
+    pub before: Option<AuthoredCollider>,
+    pub after: Option<AuthoredCollider>,
+}
+
+fn set_collider(world: &mut World, entity: Entity, collider: &Option<AuthoredCollider>) {
+    let Some(mut entity) = world.get_entity_mut(entity) else {
+        return;
+    };
+    match collider {
+        Some(collider) => {
+            entity.insert(collider.clone());
+        }
+        None => {
+            entity.remove::<AuthoredCollider>();
+        }
+    }
+}
+
+impl EditorCommand for ColliderCommand {
+    fn apply(&mut self, world: &mut World, _: &mut EntityHashMap<Entity>) {
+        set_collider(world, self.entity, &self.after);
+    }
+
+    fn revert(&mut self, world: &mut World, _: &mut EntityHashMap<Entity>) {
+        set_collider(world, self.entity, &self.before);
+    }
+
+    fn map_entities(&mut self, respawned: &EntityHashMap<Entity>) {
+        map_entity(&mut self.entity, respawned);
+    }
+}
+
+/// The center and size of the meshes of the entity and its descendants, in the space
+/// of the entity.
+fn local_bounds(world: &World, entity: Entity) -> Option<(Vec3, Vec3)> {
+    let world_to_local = world.get::<GlobalTransform>(entity)?.affine().inverse();
+    let mut min = Vec3::INFINITY;
+    let mut max = Vec3::NEG_INFINITY;
+
+    let mut stack = vec![entity];
+    while let Some(entity) = stack.pop() {
+        if let (Some(aabb), Some(transform)) = (
+            world.get::<Aabb>(entity),
+            world.get::<GlobalTransform>(entity),
+        ) {
+            let to_local = world_to_local * transform.affine();
+            let (center, half) = (Vec3::from(aabb.center), Vec3::from(aabb.half_extents));
+            for corner in 0..8 {
+                let sign = Vec3::new(
+                    if corner & 1 == 0 { -1. } else { 1. },
+                    if corner & 2 == 0 { -1. } else { 1. },
+                    if corner & 4 == 0 { -1. } else { 1. },
+                );
+                let point = to_local.transform_point3(center + half * sign);
+                min = min.min(point);
+                max = max.max(point);
+            }
+        }
+        if let Some(children) = world.get::<Children>(entity) {
+            stack.extend(children.iter());
+        }
+    }
+
+    (min.x <= max.x).then(|| {
+        (
+            (min + max) / 2.,
+            (max - min).max(Vec3::splat(MIN_COLLIDER_SIZE)),
+        )
+    })
+}
+
+/// A collider of the shape fitting the meshes of the entity, keeping the other settings
+/// of its current collider.
+fn fitted_collider(world: &World, entity: Entity, shape: AuthoredShape) -> AuthoredCollider {
+    let mut collider = world
+        .get::<AuthoredCollider>(entity)
+        .cloned()
+        .unwrap_or_default();
+    collider.shape = shape;
+
+    if let Some((center, size)) = local_bounds(world, entity) {
+        collider.offset = center;
+        collider.size = match shape {
+            AuthoredShape::Sphere => Vec3::splat(size.max_element()),
+            AuthoredShape::Capsule | AuthoredShape::Cylinder => {
+                let diameter = size.x.max(size.z);
+                Vec3::new(diameter, size.y, diameter)
+            }
+            _ => size,
+        };
+    }
+    collider
+}
+
+fn add_colliders(world: &mut World, entities: Vec<Entity>, shape: AuthoredShape) {
+    let commands: Vec<Box<dyn EditorCommand>> = entities
+        .into_iter()
+        .filter(|entity| world.get_entity(*entity).is_some())
+        .map(|entity| {
+            Box::new(ColliderCommand {
+                entity,
+                before: world.get::<AuthoredCollider>(entity).cloned(),
+                after: Some(fitted_collider(world, entity, shape)),
+            }) as Box<dyn EditorCommand>
+        })
+        .collect();
+    if !commands.is_empty() {
+        world.execute(CommandBatch(commands));
+    }
+}
+
+fn remove_colliders(world: &mut World, entities: Vec<Entity>) {
+    let commands: Vec<Box<dyn EditorCommand>> = entities
+        .into_iter()
+        .filter_map(|entity| {
+            let before = world.get::<AuthoredCollider>(entity)?.clone();
+            Some(Box::new(ColliderCommand {
+                entity,
+                before: Some(before),
+                after: None,
+            }) as Box<dyn EditorCommand>)
+        })
+        .collect();
+    if !commands.is_empty() {
+        world.execute(CommandBatch(commands));
+    }
+}
+
+fn run_physics_menu_items(
+    mut commands: Commands,
+    q_menu_items: Query<(&MenuItem, &PhysicsMenuItem), Changed<MenuItem>>,
+    selection: Res<Selection>,
+) {
+    for (item, physics_item) in &q_menu_items {
+        if !item.interacted() || selection.is_empty() {
+            continue;
+        }
+
+        let entities = selection.entities().to_vec();
+        match *physics_item {
+            PhysicsMenuItem::AddCollider(shape) => {
+                commands.add(move |world: &mut World| add_colliders(world, entities, shape))
+            }
+            PhysicsMenuItem::RemoveCollider => {
+                commands.add(move |world: &mut World| remove_colliders(world, entities))
+            }
+        }
+    }
+}
+
+fn toggle_collider_rendering(
+    mut settings: ResMut<EditorSettings>,
+    q_toggles: Query<&ToggleMenuItem, (With<ShowCollidersToggle>, Changed<ToggleMenuItem>)>,
+) {
+    for toggle in &q_toggles {
+        if settings.show_colliders != toggle.checked {
+            settings.show_colliders = toggle.checked;
+        }
+    }
+}
+
+fn show_collider_rendering(
+    settings: Res<EditorSettings>,
+    mut config_store: ResMut<GizmoConfigStore>,
+    mut shown: Local<Option<bool>>,
+) {
+    if *shown == Some(settings.show_colliders) {
+        return;
+    }
+    *shown = Some(settings.show_colliders);
+
+    let (config, _) = config_store.config_mut::<PhysicsGizmos>();
+    config.enabled = settings.show_colliders;
+}
+
+/// The world position and outward direction of the handle, and the collider's center.
+fn handle_frame(
+    collider: &AuthoredCollider,
+    transform: &GlobalTransform,
+    handle: ColliderHandle,
+) -> (Vec3, Vec3, Vec3) {
+    let axis = Vec3::AXES[handle.axis_index] * handle.sign;
+    let local = collider.offset + axis * collider.size[handle.axis_index] / 2.;
+    let direction = transform
+        .affine()
+        .transform_vector3(axis)
+        .normalize_or_zero();
+    (
+        transform.transform_point(local),
+        direction,
+        transform.transform_point(collider.offset),
+    )
+}
+
+fn collider_handles(collider: &AuthoredCollider) -> impl Iterator<Item = ColliderHandle> + '_ {
+    collider.shape.size_axes().iter().flat_map(|axis_index| {
+        [1., -1.].map(|sign| ColliderHandle {
+            axis_index: *axis_index,
+            sign,
+        })
+    })
+}
+
+/// Highlights the handle under the cursor and starts dragging it on click.
+fn grab_collider_handle(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut handles: ResMut<ColliderHandles>,
+    gizmo: Res<TransformGizmo>,
+    selection: Res<Selection>,
+    scene_view_cursor: SceneViewCursor,
+    q_colliders: Query<(&AuthoredCollider, &GlobalTransform)>,
+) {
+    if handles.drag.is_some() {
+        return;
+    }
+
+    let primary = selection.primary();
+    let hovered = primary
+        .and_then(|entity| q_colliders.get(entity).ok())
+        .zip(scene_view_cursor.first_view())
+        .zip(scene_view_cursor.ray())
+        .filter(|_| !gizmo.is_active())
+        .and_then(|(((collider, transform), (_, camera)), (_, ray))| {
+            collider_handles(collider)
+                .filter_map(|handle| {
+                    let (position, ..) = handle_frame(collider, transform, handle);
+                    let radius = camera.translation().distance(position) * HANDLE_SCREEN_SIZE;
+                    let along = (position - ray.origin).dot(*ray.direction);
+                    let distance = ray.get_point(along.max(0.)).distance(position);
+                    (distance < radius * 2.).then_some((handle, distance))
+                })
+                .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                .map(|(handle, _)| handle)
+        });
+    handles.hovered = hovered;
+
+    let (Some(handle), Some(entity)) = (hovered, primary) else {
+        return;
+    };
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Ok((collider, _)) = q_colliders.get(entity) else {
+        return;
+    };
+
+    handles.drag = Some(HandleDrag {
+        entity,
+        handle,
+        start: collider.clone(),
+    });
+}
+
+/// Resizes the collider along the dragged handle, keeping the opposite face in place.
+/// Confirmed drags are recorded in the [`History`], cancelled ones restore the collider.
+#[allow(clippy::too_many_arguments)]
+fn drag_collider_handle(
+    mut history: ResMut<History>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    hotkeys: Res<GizmoHotkeys>,
+    gizmo: Res<TransformGizmo>,
+    mut handles: ResMut<ColliderHandles>,
+    scene_view_cursor: SceneViewCursor,
+    mut q_colliders: Query<(&mut AuthoredCollider, &GlobalTransform)>,
+) {
+    let Some(drag) = handles.drag.as_ref() else {
+        return;
+    };
+    let Ok((mut collider, transform)) = q_colliders.get_mut(drag.entity) else {
+        handles.drag = None;
+        return;
+    };
+
+    let cancelled = keyboard_input.just_pressed(KeyCode::Escape)
+        || mouse_input.just_pressed(MouseButton::Right);
+    if cancelled {
+        *collider = drag.start.clone();
+        handles.drag = None;
+        return;
+    }
+
+    // Measured from the opposite face, which stays in place
+    let opposite = ColliderHandle {
+        sign: -drag.handle.sign,
+        ..drag.handle
+    };
+    let (opposite_position, ..) = handle_frame(&drag.start, transform, opposite);
+    let (_, direction, _) = handle_frame(&drag.start, transform, drag.handle);
+    let scale = transform
+        .affine()
+        .transform_vector3(Vec3::AXES[drag.handle.axis_index])
+        .length();
+
+    let extent = scene_view_cursor
+        .ray()
+        .and_then(|(_, ray)| ray_axis_closest(ray, opposite_position, direction))
+        .map(|(parameter, _)| parameter / scale.max(f32::EPSILON));
+    if let Some(extent) = extent {
+        let extent = if hotkeys.pressed(GizmoHotkey::Snap, &keyboard_input) {
+            (extent / gizmo.snapping.grid).round() * gizmo.snapping.grid
+        } else {
+            extent
+        }
+        .max(MIN_COLLIDER_SIZE);
+
+        let index = drag.handle.axis_index;
+        let start_size = drag.start.size[index];
+        let mut resized = drag.start.clone();
+        resized.size[index] = extent;
+        resized.offset[index] += drag.handle.sign * (extent - start_size) / 2.;
+        // Spheres stay round
+        if resized.shape == AuthoredShape::Sphere {
+            resized.size = Vec3::splat(extent);
+        }
+        if *collider != resized {
+            *collider = resized;
+        }
+    }
+
+    let confirmed =
+        mouse_input.just_released(MouseButton::Left) || keyboard_input.just_pressed(KeyCode::Enter);
+    if confirmed {
+        if *collider != drag.start {
+            history.record(ColliderCommand {
+                entity: drag.entity,
+                before: Some(drag.start.clone()),
+                after: Some(collider.clone()),
+            });
+        }
+        handles.drag = None;
+    }
+}
+
+fn draw_collider_handles(
+    mut gizmos: Gizmos,
+    handles: Res<ColliderHandles>,
+    selection: Res<Selection>,
+    scene_view_cursor: SceneViewCursor,
+    q_colliders: Query<(&AuthoredCollider, &GlobalTransform)>,
+) {
+    let Some((collider, transform)) = selection
+        .primary()
+        .and_then(|entity| q_colliders.get(entity).ok())
+    else {
+        return;
+    };
+    let Some((_, camera)) = scene_view_cursor.first_view() else {
+        return;
+    };
+
+    let active = handles
+        .drag
+        .as_ref()
+        .map(|drag| drag.handle)
+        .or(handles.hovered);
+    for handle in collider_handles(collider) {
+        let (position, _, center) = handle_frame(collider, transform, handle);
+        let color = if active == Some(handle) {
+            Color::srgb(1., 0.9, 0.2)
+        } else {
+            Color::srgb(0.3, 0.9, 0.9)
+        };
+        let radius = camera.translation().distance(position) * HANDLE_SCREEN_SIZE;
+        gizmos.line(center, position, color.with_alpha(0.4));
+        gizmos.sphere(position, Quat::IDENTITY, radius, color);
+    }
+}
@@ -0,0 +1,618 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::{
+    ecs::entity::EntityHashMap,
+    input::keyboard::{Key, KeyboardInput},
+    prelude::*,
+};
+
+use crate::{
+    history::{
+        extract_hierarchies, spawn_hierarchies, CommandBatch, DespawnCommand, History,
+        HistoryWorldExt, SpawnCommand, TransformChange, TransformCommand,
+    },
+    hotkeys::{GizmoHotkey, GizmoHotkeys},
+    picking::{pick_in_scene_view, SceneViewCursor},
+    play::PlayMode,
+    selection::Selection,
+};
+
+/// The gizmo size relative to its distance from the camera, to keep it the same size on screen.
+const GIZMO_SCREEN_SIZE: f32 = 0.15;
+/// How close to a handle, relative to the gizmo size, the cursor has to be to grab it.
+const HANDLE_PICK_RADIUS: f32 = 0.08;
+
+/// Translate, rotate and scale gizmos for the [`Selection`] in the scene view.
+pub struct TransformGizmoPlugin;
+
+impl Plugin for TransformGizmoPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TransformGizmo>().add_systems(
+            Update,
+            (
+                handle_gizmo_hotkeys,
+                grab_gizmo_handle,
+                drag_transform_gizmo,
+                draw_transform_gizmo,
+            )
+                .chain()
+                .after(pick_in_scene_view)
+                .run_if(in_state(PlayMode::Editing)),
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GizmoMode {
+    #[default]
+    Translate,
+    Rotate,
+    Scale,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GizmoSpace {
+    #[default]
+    World,
+    /// Along the axes of the primary selection.
+    Local,
+}
+
+/// The steps drags snap to while the [`GizmoHotkey::Snap`] keys are held.
+#[derive(Clone, Copy, Debug)]
+pub struct GizmoSnapping {
+    pub grid: f32,
+    /// In radians.
+    pub angle: f32,
+    pub scale: f32,
+}
+
+impl Default for GizmoSnapping {
+    fn default() -> Self {
+        Self {
+            grid: 0.5,
+            angle: 15f32.to_radians(),
+            scale: 0.1,
+        }
+    }
+}
+
+/// The state of the transform gizmo drawn over the selection.
+#[derive(Resource, Debug, Default)]
+pub struct TransformGizmo {
+    pub mode: GizmoMode,
+    pub space: GizmoSpace,
+    pub snapping: GizmoSnapping,
+    hovered: Option<usize>,
+    drag: Option<GizmoDrag>,
+}
+
+impl TransformGizmo {
+    /// Whether the cursor is over a handle or dragging one, so clicks belong to the gizmo.
+    pub fn is_active(&self) -> bool {
+        self.hovered.is_some() || self.drag.is_some()
+    }
+}
+
+/// A handle being dragged, with the transforms to restore if the drag is cancelled.
+#[derive(Debug)]
+struct GizmoDrag {
+    frame: GizmoFrame,
+    axis_index: usize,
+    targets: Vec<DragTarget>,
+    /// Whether the targets are clones, despawned if the drag is cancelled.
+    cloned: bool,
+    start_parameter: f32,
+    last_parameter: f32,
+    /// The unwrapped angle rotated so far, as handle angles wrap around.
+    rotated: f32,
+    /// Digits typed during the drag, used as the exact amount instead of the cursor.
+    typed: String,
+}
+
+#[derive(Debug)]
+struct DragTarget {
+    entity: Entity,
+    start: Transform,
+    start_global: GlobalTransform,
+    parent_global: Option<GlobalTransform>,
+}
+
+/// The pivot and axes of the gizmo for the current selection.
+#[derive(Clone, Copy, Debug)]
+struct GizmoFrame {
+    pivot: Vec3,
+    rotation: Quat,
+    size: f32,
+}
+
+impl GizmoFrame {
+    fn axis(&self, index: usize) -> Vec3 {
+        self.rotation * Vec3::AXES[index]
+    }
+
+    /// How far along the handle's axis, or around it when rotating, the ray points,
+    /// and how far the ray passes from the handle.
+    fn handle_parameter(&self, mode: GizmoMode, index: usize, ray: Ray3d) -> Option<(f32, f32)> {
+        let axis = self.axis(index);
+        match mode {
+            GizmoMode::Translate | GizmoMode::Scale => {
+                let (parameter, distance) = ray_axis_closest(ray, self.pivot, axis)?;
+                let along = if (0. ..=self.size).contains(&parameter) {
+                    distance
+                } else {
+                    f32::INFINITY
+                };
+                Some((parameter, along))
+            }
+            GizmoMode::Rotate => {
+                let point = ray_plane_intersection(ray, self.pivot, axis)?;
+                let offset = point - self.pivot;
+                let u = axis.any_orthonormal_vector();
+                let v = axis.cross(u);
+                let angle = offset.dot(v).atan2(offset.dot(u));
+                Some((angle, (offset.length() - self.size).abs()))
+            }
+        }
+    }
+}
+
+/// The parameter along the axis closest to the ray, and the distance between them.
+pub fn ray_axis_closest(ray: Ray3d, origin: Vec3, axis: Vec3) -> Option<(f32, f32)> {
+    let direction = *ray.direction;
+    let offset = ray.origin - origin;
+    let b = direction.dot(axis);
+    let denominator = 1. - b * b;
+    if denominator < 1e-4 {
+        return None;
+    }
+
+    let d = direction.dot(offset);
+    let e = axis.dot(offset);
+    let along_ray = ((b * e - d) / denominator).max(0.);
+    let along_axis = e + along_ray * b;
+    let distance = (ray.get_point(along_ray) - (origin + axis * along_axis)).length();
+    Some((along_axis, distance))
+}
+
+fn ray_plane_intersection(ray: Ray3d, origin: Vec3, normal: Vec3) -> Option<Vec3> {
+    let denominator = ray.direction.dot(normal);
+    if denominator.abs() < 1e-4 {
+        return None;
+    }
+
+    let distance = (origin - ray.origin).dot(normal) / denominator;
+    (distance >= 0.).then(|| ray.get_point(distance))
+}
+
+fn snap(value: f32, step: f32) -> f32 {
+    if step > 0. {
+        (value / step).round() * step
+    } else {
+        value
+    }
+}
+
+/// Selected entities that are moved by the gizmo, leaving out those moved along with
+/// a selected ancestor.
+fn gizmo_targets(selection: &Selection, q_parents: &Query<&Parent>) -> Vec<Entity> {
+    selection
+        .entities()
+        .iter()
+        .copied()
+        .filter(|entity| {
+            !q_parents
+                .iter_ancestors(*entity)
+                .any(|ancestor| selection.contains(ancestor))
+        })
+        .collect()
+}
+
+fn gizmo_frame(
+    gizmo: &TransformGizmo,
+    selection: &Selection,
+    camera_transform: &GlobalTransform,
+    q_transforms: &Query<&GlobalTransform>,
+) -> Option<GizmoFrame> {
+    let transforms: Vec<_> = selection
+        .entities()
+        .iter()
+        .filter_map(|entity| q_transforms.get(*entity).ok())
+        .collect();
+    if transforms.is_empty() {
+        return None;
+    }
+
+    let pivot = transforms.iter().map(|t| t.translation()).sum::<Vec3>() / transforms.len() as f32;
+    let rotation = match gizmo.space {
+        GizmoSpace::World => Quat::IDENTITY,
+        GizmoSpace::Local => selection
+            .primary()
+            .and_then(|primary| q_transforms.get(primary).ok())
+            .map_or(Quat::IDENTITY, |t| t.compute_transform().rotation),
+    };
+    let size = camera_transform.translation().distance(pivot) * GIZMO_SCREEN_SIZE;
+
+    Some(GizmoFrame {
+        pivot,
+        rotation,
+        size,
+    })
+}
+
+/// Switches the gizmo mode and space, and deletes the selection.
+fn handle_gizmo_hotkeys(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    hotkeys: Res<GizmoHotkeys>,
+    mut gizmo: ResMut<TransformGizmo>,
+    mut selection: ResMut<Selection>,
+    q_parents: Query<&Parent>,
+) {
+    // Keys typed during a drag are numeric entry
+    if gizmo.drag.is_some() {
+        return;
+    }
+    // Leaves Ctrl shortcuts such as Ctrl+S to the menus
+    if keyboard_input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+
+    if hotkeys.just_pressed(GizmoHotkey::Translate, &keyboard_input) {
+        gizmo.mode = GizmoMode::Translate;
+    } else if hotkeys.just_pressed(GizmoHotkey::Rotate, &keyboard_input) {
+        gizmo.mode = GizmoMode::Rotate;
+    } else if hotkeys.just_pressed(GizmoHotkey::Scale, &keyboard_input) {
+        gizmo.mode = GizmoMode::Scale;
+    }
+
+    if hotkeys.just_pressed(GizmoHotkey::ToggleSpace, &keyboard_input) {
+        gizmo.space = match gizmo.space {
+            GizmoSpace::World => GizmoSpace::Local,
+            GizmoSpace::Local => GizmoSpace::World,
+        };
+    }
+
+    if hotkeys.just_pressed(GizmoHotkey::Delete, &keyboard_input) && !selection.is_empty() {
+        let entities = gizmo_targets(&selection, &q_parents);
+        commands.add(move |world: &mut World| world.execute(DespawnCommand::new(entities)));
+        selection.clear();
+    }
+}
+
+/// Highlights the handle under the cursor and starts dragging it on click.
+#[allow(clippy::too_many_arguments)]
+fn grab_gizmo_handle(
+    mut commands: Commands,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    hotkeys: Res<GizmoHotkeys>,
+    mut gizmo: ResMut<TransformGizmo>,
+    selection: Res<Selection>,
+    scene_view_cursor: SceneViewCursor,
+    q_transforms: Query<(&Transform, &GlobalTransform)>,
+    q_global_transforms: Query<&GlobalTransform>,
+    q_parents: Query<&Parent>,
+) {
+    if gizmo.drag.is_some() {
+        return;
+    }
+
+    let frame = scene_view_cursor
+        .first_view()
+        .and_then(|(_, camera_transform)| {
+            gizmo_frame(&gizmo, &selection, camera_transform, &q_global_transforms)
+        });
+    let hovered = frame
+        .zip(scene_view_cursor.ray())
+        .and_then(|(frame, (_, ray))| {
+            (0..3)
+                .filter_map(|index| {
+                    let (parameter, distance) = frame.handle_parameter(gizmo.mode, index, ray)?;
+                    (distance < frame.size * HANDLE_PICK_RADIUS)
+                        .then_some((index, parameter, distance))
+                })
+                .min_by(|(.., a), (.., b)| a.total_cmp(b))
+                .map(|(index, parameter, _)| (frame, index, parameter))
+        });
+    gizmo.hovered = hovered.map(|(_, index, _)| index);
+
+    let Some((frame, axis_index, parameter)) = hovered else {
+        return;
+    };
+    if !mouse_input.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let targets: Vec<_> = gizmo_targets(&selection, &q_parents)
+        .into_iter()
+        .filter_map(|entity| {
+            let (transform, global_transform) = q_transforms.get(entity).ok()?;
+            Some(DragTarget {
+                entity,
+                start: *transform,
+                start_global: *global_transform,
+                parent_global: q_parents
+                    .get(entity)
+                    .ok()
+                    .and_then(|parent| q_global_transforms.get(parent.get()).ok())
+                    .copied(),
+            })
+        })
+        .collect();
+
+    let cloned = hotkeys.pressed(GizmoHotkey::Clone, &keyboard_input);
+    if cloned {
+        let entities: Vec<_> = targets.iter().map(|target| target.entity).collect();
+        commands.add(move |world: &mut World| clone_drag_targets(world, entities));
+    }
+
+    gizmo.drag = Some(GizmoDrag {
+        frame,
+        axis_index,
+        targets,
+        cloned,
+        start_parameter: parameter,
+        last_parameter: parameter,
+        rotated: 0.,
+        typed: String::new(),
+    });
+}
+
+/// Moves the dragged selection, confirming on release or Enter and cancelling on
+/// Escape or right click. Confirmed drags are recorded in the [`History`].
+#[allow(clippy::too_many_arguments)]
+fn drag_transform_gizmo(
+    mut commands: Commands,
+    mut history: ResMut<History>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    hotkeys: Res<GizmoHotkeys>,
+    mut gizmo: ResMut<TransformGizmo>,
+    scene_view_cursor: SceneViewCursor,
+    mut q_transforms: Query<&mut Transform>,
+) {
+    let typed: String = keyboard_events
+        .read()
+        .filter(|event| event.state.is_pressed())
+        .filter_map(|event| match &event.logical_key {
+            Key::Character(text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+
+    let gizmo = gizmo.as_mut();
+    let Some(drag) = gizmo.drag.as_mut() else {
+        return;
+    };
+
+    let cancelled = keyboard_input.just_pressed(KeyCode::Escape)
+        || mouse_input.just_pressed(MouseButton::Right);
+    if cancelled {
+        for target in &drag.targets {
+            if drag.cloned {
+                commands.entity(target.entity).despawn_recursive();
+            } else if let Ok(mut transform) = q_transforms.get_mut(target.entity) {
+                *transform = target.start;
+            }
+        }
+        gizmo.drag = None;
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Backspace) {
+        drag.typed.pop();
+    }
+    drag.typed.extend(
+        typed
+            .chars()
+            .filter(|c| c.is_ascii_digit() || *c == '.' || *c == '-'),
+    );
+
+    let parameter = scene_view_cursor.ray().and_then(|(_, ray)| {
+        drag.frame
+            .handle_parameter(gizmo.mode, drag.axis_index, ray)
+            .map(|(parameter, _)| parameter)
+    });
+    if let Some(parameter) = parameter {
+        if gizmo.mode == GizmoMode::Rotate {
+            let step = parameter - drag.last_parameter;
+            drag.rotated += (step + PI).rem_euclid(TAU) - PI;
+        }
+        drag.last_parameter = parameter;
+    }
+
+    let snapping = hotkeys.pressed(GizmoHotkey::Snap, &keyboard_input);
+    let typed_amount = drag.typed.parse::<f32>().ok();
+    let amount = match gizmo.mode {
+        GizmoMode::Translate => typed_amount.unwrap_or_else(|| {
+            let moved = drag.last_parameter - drag.start_parameter;
+            if snapping {
+                snap(moved, gizmo.snapping.grid)
+            } else {
+                moved
+            }
+        }),
+        GizmoMode::Rotate => typed_amount.map(f32::to_radians).unwrap_or_else(|| {
+            if snapping {
+                snap(drag.rotated, gizmo.snapping.angle)
+            } else {
+                drag.rotated
+            }
+        }),
+        GizmoMode::Scale => typed_amount.unwrap_or_else(|| {
+            let factor = if drag.start_parameter.abs() > f32::EPSILON {
+                drag.last_parameter / drag.start_parameter
+            } else {
+                1.
+            };
+            if snapping {
+                snap(factor, gizmo.snapping.scale)
+            } else {
+                factor
+            }
+        }),
+    };
+
+    let pivot = drag.frame.pivot;
+    let axis = drag.frame.axis(drag.axis_index);
+    let mut changes = Vec::with_capacity(drag.targets.len());
+    for target in &drag.targets {
+        let Ok(mut transform) = q_transforms.get_mut(target.entity) else {
+            continue;
+        };
+
+        let mut global = target.start_global.compute_transform();
+        match gizmo.mode {
+            GizmoMode::Translate => global.translation += axis * amount,
+            GizmoMode::Rotate => global.rotate_around(pivot, Quat::from_axis_angle(axis, amount)),
+            GizmoMode::Scale => {
+                // Scale along the entity's own axis closest to the handle
+                let local_axis = (global.rotation.inverse() * axis).abs();
+                let index = (0..3)
+                    .max_by(|a, b| local_axis[*a].total_cmp(&local_axis[*b]))
+                    .unwrap_or_default();
+                global.scale[index] *= amount;
+                let offset = global.translation - pivot;
+                global.translation += axis * offset.dot(axis) * (amount - 1.);
+            }
+        }
+
+        *transform = match &target.parent_global {
+            Some(parent_global) => GlobalTransform::from(global).reparented_to(parent_global),
+            None => global,
+        };
+        changes.push(TransformChange {
+            entity: target.entity,
+            before: target.start,
+            after: *transform,
+        });
+    }
+
+    let confirmed =
+        mouse_input.just_released(MouseButton::Left) || keyboard_input.just_pressed(KeyCode::Enter);
+    if confirmed {
+        record_drag(&mut history, changes, drag.cloned);
+        gizmo.drag = None;
+    }
+}
+
+/// Records a confirmed drag, together with the spawn of the dragged clones so undoing
+/// it removes them.
+pub fn record_drag(history: &mut History, changes: Vec<TransformChange>, cloned: bool) {
+    if changes.is_empty() {
+        return;
+    }
+
+    if cloned {
+        let clones = changes.iter().map(|change| change.entity).collect();
+        history.record(CommandBatch(vec![
+            Box::new(SpawnCommand::new(clones)),
+            Box::new(TransformCommand { changes }),
+        ]));
+    } else {
+        history.record(TransformCommand { changes });
+    }
+}
+
+/// Replaces the dragged entities with clones, leaving the originals where they were.
+fn clone_drag_targets(world: &mut World, entities: Vec<Entity>) {
+    let clones = match clone_entities(world, &entities) {
+        Ok(clones) => clones,
+        Err(error) => {
+            warn!("Couldn't clone the selection: {error}");
+            return;
+        }
+    };
+
+    let mut gizmo = world.resource_mut::<TransformGizmo>();
+    if let Some(drag) = gizmo.drag.as_mut() {
+        for target in &mut drag.targets {
+            if let Some(clone) = clones.get(&target.entity) {
+                target.entity = *clone;
+            }
+        }
+    }
+
+    let mut selection = world.resource_mut::<Selection>();
+    let selected: Vec<_> = selection
+        .entities()
+        .iter()
+        .map(|entity| clones.get(entity).copied().unwrap_or(*entity))
+        .collect();
+    selection.clear();
+    for entity in selected {
+        selection.select(entity, true);
+    }
+}
+
+/// Clones the entities and their descendants through their reflected components,
+/// keeping the clones under the same parents. Returns the clone of each entity.
+pub fn clone_entities(
+    world: &mut World,
+    entities: &[Entity],
+) -> Result<EntityHashMap<Entity>, bevy::scene::SceneSpawnError> {
+    let scene = extract_hierarchies(world, entities);
+    let roots: Vec<_> = entities
+        .iter()
+        .map(|entity| (*entity, world.get::<Parent>(*entity).map(Parent::get)))
+        .collect();
+
+    spawn_hierarchies(world, &scene, &roots)
+}
+
+fn draw_transform_gizmo(
+    mut gizmos: Gizmos,
+    gizmo: Res<TransformGizmo>,
+    selection: Res<Selection>,
+    scene_view_cursor: SceneViewCursor,
+    q_transforms: Query<&GlobalTransform>,
+) {
+    let frame = match &gizmo.drag {
+        Some(drag) => Some(drag.frame),
+        None => scene_view_cursor
+            .first_view()
+            .and_then(|(_, camera)| gizmo_frame(&gizmo, &selection, camera, &q_transforms)),
+    };
+    let Some(frame) = frame else {
+        return;
+    };
+
+    let active = gizmo
+        .drag
+        .as_ref()
+        .map(|drag| drag.axis_index)
+        .or(gizmo.hovered);
+    for index in 0..3 {
+        let axis = frame.axis(index);
+        let color = if active == Some(index) {
+            Color::srgb(1., 0.9, 0.2)
+        } else {
+            [
+                Color::srgb(0.9, 0.2, 0.2),
+                Color::srgb(0.2, 0.8, 0.2),
+                Color::srgb(0.2, 0.4, 0.9),
+            ][index]
+        };
+
+        let end = frame.pivot + axis * frame.size;
+        match gizmo.mode {
+            GizmoMode::Translate => {
+                gizmos.arrow(frame.pivot, end, color);
+            }
+            GizmoMode::Rotate => {
+                gizmos.circle(frame.pivot, Dir3::new_unchecked(axis), frame.size, color);
+            }
+            GizmoMode::Scale => {
+                gizmos.line(frame.pivot, end, color);
+                gizmos.cuboid(
+                    Transform::from_translation(end)
+                        .with_rotation(frame.rotation)
+                        .with_scale(Vec3::splat(frame.size * 0.1)),
+                    color,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+use sickle_ui::{
+    dev_panels::scene_view::{SceneView, SpawnSceneViewPreUpdate},
+    prelude::*,
+};
+
+use crate::{
+    hotkeys::{GizmoHotkey, GizmoHotkeys},
+    selection::Selection,
+};
+
+/// Lists the entities of the scene shown in the scene view and selects them on click.
+pub struct EditorHierarchyPlugin;
+
+impl Plugin for EditorHierarchyPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            (spawn_hierarchy_view, despawn_hierarchy_view).after(SpawnSceneViewPreUpdate),
+        )
+        .add_systems(
+            Update,
+            (
+                rebuild_hierarchy_view,
+                select_hierarchy_node,
+                update_hierarchy_node_style,
+            )
+                .chain()
+                .after(WidgetLibraryUpdate),
+        );
+    }
+}
+
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct HierarchyPanel;
+
+/// The root of the scene listed by a [`HierarchyPanel`].
+#[derive(Component, Debug)]
+struct HierarchyView {
+    root: Entity,
+    /// The number of listed entities, to notice despawned ones.
+    listed: Option<usize>,
+}
+
+/// A row of the hierarchy, selecting its scene entity when pressed.
+#[derive(Component, Debug)]
+pub struct HierarchyNode {
+    pub entity: Entity,
+}
+
+fn spawn_hierarchy_view(
+    q_added_scene_view: Query<&SceneView, Added<SceneView>>,
+    q_hierarchy_panel: Query<Entity, With<HierarchyPanel>>,
+    mut commands: Commands,
+) {
+    let Some(scene_view) = q_added_scene_view.iter().next() else {
+        return;
+    };
+    let Ok(container) = q_hierarchy_panel.get_single() else {
+        return;
+    };
+
+    commands.entity(container).insert(HierarchyView {
+        root: scene_view.asset_root(),
+        listed: None,
+    });
+}
+
+fn despawn_hierarchy_view(
+    q_hierarchy_panel: Query<Entity, With<HierarchyPanel>>,
+    q_removed_scene_view: RemovedComponents<SceneView>,
+    mut commands: Commands,
+) {
+    let Ok(container) = q_hierarchy_panel.get_single() else {
+        return;
+    };
+
+    if q_removed_scene_view.len() > 0 {
+        commands
+            .entity(container)
+            .remove::<HierarchyView>()
+            .despawn_descendants();
+    }
+}
+
+/// Lists the scene again whenever entities are added, removed or renamed in it.
+fn rebuild_hierarchy_view(
+    mut commands: Commands,
+    mut q_views: Query<(Entity, &mut HierarchyView)>,
+    q_changed: Query<Entity, Or<(Changed<Children>, Changed<Name>, Changed<Parent>)>>,
+    q_parents: Query<&Parent>,
+    q_children: Query<&Children>,
+    q_names: Query<&Name>,
+) {
+    for (container, mut view) in &mut q_views {
+        let count = q_children.iter_descendants(view.root).count() + 1;
+        let changed = view.listed != Some(count)
+            || q_changed.iter().any(|entity| {
+                entity == view.root || q_parents.iter_ancestors(entity).any(|a| a == view.root)
+            });
+        if !changed {
+            continue;
+        }
+        view.listed = Some(count);
+
+        commands.entity(container).despawn_descendants();
+        commands.ui_builder(container).column(|column| {
+            column.style().width(Val::Percent(100.));
+
+            let mut stack = vec![(view.root, 0)];
+            while let Some((entity, depth)) = stack.pop() {
+                let name = q_names
+                    .get(entity)
+                    .map_or_else(|_| format!("{entity}"), |name| name.to_string());
+
+                column
+                    .container(
+                        (
+                            ButtonBundle {
+                                style: Style {
+                                    width: Val::Percent(100.),
+                                    padding: UiRect::left(Val::Px(4. + 12. * depth as f32)),
+                                    ..default()
+                                },
+                                background_color: Color::NONE.into(),
+                                ..default()
+                            },
+                            HierarchyNode { entity },
+                        ),
+                        |row| {
+                            row.label(LabelConfig {
+                                label: name,
+                                ..default()
+                            });
+                        },
+                    )
+                    .insert(Name::new("Hierarchy Node"));
+
+                if let Ok(children) = q_children.get(entity) {
+                    stack.extend(children.iter().rev().map(|child| (*child, depth + 1)));
+                }
+            }
+        });
+    }
+}
+
+/// Selects the pressed row's entity, toggling it in the selection with the
+/// [`GizmoHotkey::Multiple`] keys.
+fn select_hierarchy_node(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    hotkeys: Res<GizmoHotkeys>,
+    mut selection: ResMut<Selection>,
+    q_nodes: Query<(&Interaction, &HierarchyNode), Changed<Interaction>>,
+) {
+    let extend = hotkeys.pressed(GizmoHotkey::Multiple, &keyboard_input);
+    for (interaction, node) in &q_nodes {
+        if *interaction == Interaction::Pressed {
+            selection.select(node.entity, extend);
+        }
+    }
+}
+
+fn update_hierarchy_node_style(
+    selection: Res<Selection>,
+    mut q_nodes: Query<(&HierarchyNode, &Interaction, &mut BackgroundColor)>,
+) {
+    for (node, interaction, mut background_color) in &mut q_nodes {
+        let color = if selection.contains(node.entity) {
+            Color::srgba(0.25, 0.45, 0.8, 0.6)
+        } else if *interaction == Interaction::Hovered {
+            Color::srgba(1., 1., 1., 0.08)
+        } else {
+            Color::NONE
+        };
+
+        if background_color.0 != color {
+            background_color.0 = color;
+        }
+    }
+}
@@ -0,0 +1,679 @@
+use std::collections::VecDeque;
+
+use bevy::{
+    ecs::entity::EntityHashMap, prelude::*, scene::SceneSpawnError,
+    transform::commands::BuildChildrenTransformExt,
+};
+use sickle_ui::prelude::*;
+
+use crate::{play::PlayMode, selection::Selection, settings::EditorSettings};
+
+/// Undo and redo of editor edits, and the Edit menu running them.
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        let max_size = app.world().resource::<EditorSettings>().history_size;
+        app.insert_resource(History::new(max_size)).add_systems(
+            PreUpdate,
+            run_edit_menu_items.run_if(in_state(PlayMode::Editing)),
+        );
+    }
+}
+
+/// An edit that can be undone and redone.
+///
+/// Undoing a despawn respawns entities under new ids, which are added to `respawned`
+/// so every command in the [`History`] can follow them in [`EditorCommand::map_entities`].
+pub trait EditorCommand: Send + Sync + 'static {
+    fn apply(&mut self, world: &mut World, respawned: &mut EntityHashMap<Entity>);
+
+    fn revert(&mut self, world: &mut World, respawned: &mut EntityHashMap<Entity>);
+
+    fn map_entities(&mut self, respawned: &EntityHashMap<Entity>);
+}
+
+/// Replaces the entity with its new id if it was respawned.
+pub fn map_entity(entity: &mut Entity, respawned: &EntityHashMap<Entity>) {
+    if let Some(new_entity) = respawned.get(entity) {
+        *entity = *new_entity;
+    }
+}
+
+/// The edits that can be undone, and those undone that can be redone.
+#[derive(Resource)]
+pub struct History {
+    done: VecDeque<Box<dyn EditorCommand>>,
+    undone: Vec<Box<dyn EditorCommand>>,
+    max_size: usize,
+}
+
+impl History {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            done: VecDeque::new(),
+            undone: Vec::new(),
+            max_size,
+        }
+    }
+
+    /// Adds an edit that was already applied, e.g. by dragging a gizmo, dropping the
+    /// oldest edit when the history is full.
+    pub fn record(&mut self, command: impl EditorCommand) {
+        self.push_done(Box::new(command));
+        self.undone.clear();
+    }
+
+    /// Forgets every edit, e.g. when another scene is opened.
+    pub fn clear(&mut self) {
+        self.done.clear();
+        self.undone.clear();
+    }
+
+    fn push_done(&mut self, command: Box<dyn EditorCommand>) {
+        self.done.push_back(command);
+        while self.done.len() > self.max_size {
+            self.done.pop_front();
+        }
+    }
+
+    /// Follows entities respawned outside of the history, e.g. when play mode stops.
+    pub fn map_entities(&mut self, respawned: &EntityHashMap<Entity>) {
+        if respawned.is_empty() {
+            return;
+        }
+
+        for command in self.done.iter_mut().chain(self.undone.iter_mut()) {
+            command.map_entities(respawned);
+        }
+    }
+}
+
+/// Applies, undoes and redoes [`EditorCommand`]s through the [`History`].
+pub trait HistoryWorldExt {
+    fn execute(&mut self, command: impl EditorCommand);
+
+    /// Reverts the last edit, returning whether there was one.
+    fn undo(&mut self) -> bool;
+
+    /// Applies the last undone edit again, returning whether there was one.
+    fn redo(&mut self) -> bool;
+}
+
+impl HistoryWorldExt for World {
+    fn execute(&mut self, mut command: impl EditorCommand) {
+        let mut respawned = EntityHashMap::default();
+        command.apply(self, &mut respawned);
+
+        let mut history = self.resource_mut::<History>();
+        history.record(command);
+        history.map_entities(&respawned);
+    }
+
+    fn undo(&mut self) -> bool {
+        let Some(mut command) = self.resource_mut::<History>().done.pop_back() else {
+            return false;
+        };
+
+        let mut respawned = EntityHashMap::default();
+        command.revert(self, &mut respawned);
+
+        let mut history = self.resource_mut::<History>();
+        history.undone.push(command);
+        history.map_entities(&respawned);
+        true
+    }
+
+    fn redo(&mut self) -> bool {
+        let Some(mut command) = self.resource_mut::<History>().undone.pop() else {
+            return false;
+        };
+
+        let mut respawned = EntityHashMap::default();
+        command.apply(self, &mut respawned);
+
+        let mut history = self.resource_mut::<History>();
+        history.push_done(command);
+        history.map_entities(&respawned);
+        true
+    }
+}
+
+/// Edits applied and reverted together as a single step.
+pub struct CommandBatch(pub Vec<Box<dyn EditorCommand>>);
+
+impl EditorCommand for CommandBatch {
+    fn apply(&mut self, world: &mut World, respawned: &mut EntityHashMap<Entity>) {
+        for command in &mut self.0 {
+            command.map_entities(respawned);
+            command.apply(world, respawned);
+        }
+    }
+
+    fn revert(&mut self, world: &mut World, respawned: &mut EntityHashMap<Entity>) {
+        for command in self.0.iter_mut().rev() {
+            command.map_entities(respawned);
+            command.revert(world, respawned);
+        }
+    }
+
+    fn map_entities(&mut self, respawned: &EntityHashMap<Entity>) {
+        for command in &mut self.0 {
+            command.map_entities(respawned);
+        }
+    }
+}
+
+/// Sets the transforms of entities, e.g. after dragging them with the gizmo.
+pub struct TransformCommand {
+    pub changes: Vec<TransformChange>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TransformChange {
+    pub entity: Entity,
+    pub before: Transform,
+    pub after: Transform,
+}
+
+impl TransformCommand {
+    fn set(&self, world: &mut World, transform: impl Fn(&TransformChange) -> Transform) {
+        for change in &self.changes {
+            if let Some(mut entity_transform) = world.get_mut::<Transform>(change.entity) {
+                *entity_transform = transform(change);
+            }
+        }
+    }
+}
+
+impl EditorCommand for TransformCommand {
+    fn apply(&mut self, world: &mut World, _: &mut EntityHashMap<Entity>) {
+        self.set(world, |change| change.after);
+    }
+
+    fn revert(&mut self, world: &mut World, _: &mut EntityHashMap<Entity>) {
+        self.set(world, |change| change.before);
+    }
+
+    fn map_entities(&mut self, respawned: &EntityHashMap<Entity>) {
+        for change in &mut self.changes {
+            map_entity(&mut change.entity, respawned);
+        }
+    }
+}
+
+/// Moves an entity under another parent, or to the root, keeping its global transform.
+pub struct ReparentCommand {
+    pub entity: Entity,
+    pub parent: Option<Entity>,
+    previous: Option<Entity>,
+}
+
+impl ReparentCommand {
+    pub fn new(entity: Entity, parent: Option<Entity>) -> Self {
+        Self {
+            entity,
+            parent,
+            previous: None,
+        }
+    }
+
+    fn set_parent(&self, world: &mut World, parent: Option<Entity>) {
+        let Some(mut entity) = world.get_entity_mut(self.entity) else {
+            return;
+        };
+
+        match parent {
+            Some(parent) => entity.set_parent_in_place(parent),
+            None => entity.remove_parent_in_place(),
+        };
+    }
+}
+
+impl EditorCommand for ReparentCommand {
+    fn apply(&mut self, world: &mut World, _: &mut EntityHashMap<Entity>) {
+        self.previous = world.get::<Parent>(self.entity).map(Parent::get);
+        self.set_parent(world, self.parent);
+    }
+
+    fn revert(&mut self, world: &mut World, _: &mut EntityHashMap<Entity>) {
+        self.set_parent(world, self.previous);
+    }
+
+    fn map_entities(&mut self, respawned: &EntityHashMap<Entity>) {
+        map_entity(&mut self.entity, respawned);
+        for parent in [&mut self.parent, &mut self.previous].into_iter().flatten() {
+            map_entity(parent, respawned);
+        }
+    }
+}
+
+/// Despawns entities and their descendants, keeping them to respawn on revert.
+pub struct DespawnCommand {
+    entities: Vec<Entity>,
+    despawned: Option<DespawnedHierarchies>,
+}
+
+impl DespawnCommand {
+    pub fn new(entities: Vec<Entity>) -> Self {
+        Self {
+            entities,
+            despawned: None,
+        }
+    }
+}
+
+impl EditorCommand for DespawnCommand {
+    fn apply(&mut self, world: &mut World, _: &mut EntityHashMap<Entity>) {
+        self.despawned = Some(DespawnedHierarchies::despawn(world, &self.entities));
+    }
+
+    fn revert(&mut self, world: &mut World, respawned: &mut EntityHashMap<Entity>) {
+        if let Some(despawned) = self.despawned.take() {
+            despawned.respawn(world, respawned);
+        }
+    }
+
+    fn map_entities(&mut self, respawned: &EntityHashMap<Entity>) {
+        for entity in &mut self.entities {
+            map_entity(entity, respawned);
+        }
+        if let Some(despawned) = &mut self.despawned {
+            despawned.map_parents(respawned);
+        }
+    }
+}
+
+/// Records entities spawned by an edit, e.g. clones, despawning them on revert.
+pub struct SpawnCommand {
+    entities: Vec<Entity>,
+    despawned: Option<DespawnedHierarchies>,
+}
+
+impl SpawnCommand {
+    pub fn new(entities: Vec<Entity>) -> Self {
+        Self {
+            entities,
+            despawned: None,
+        }
+    }
+}
+
+impl EditorCommand for SpawnCommand {
+    fn apply(&mut self, world: &mut World, respawned: &mut EntityHashMap<Entity>) {
+        if let Some(despawned) = self.despawned.take() {
+            despawned.respawn(world, respawned);
+        }
+    }
+
+    fn revert(&mut self, world: &mut World, _: &mut EntityHashMap<Entity>) {
+        self.despawned = Some(DespawnedHierarchies::despawn(world, &self.entities));
+    }
+
+    fn map_entities(&mut self, respawned: &EntityHashMap<Entity>) {
+        for entity in &mut self.entities {
+            map_entity(entity, respawned);
+        }
+        if let Some(despawned) = &mut self.despawned {
+            despawned.map_parents(respawned);
+        }
+    }
+}
+
+/// Despawned entities and their descendants, with the parents to put them back under.
+struct DespawnedHierarchies {
+    scene: DynamicScene,
+    roots: Vec<(Entity, Option<Entity>)>,
+}
+
+impl DespawnedHierarchies {
+    fn despawn(world: &mut World, entities: &[Entity]) -> Self {
+        let scene = extract_hierarchies(world, entities);
+        let roots = entities
+            .iter()
+            .map(|entity| (*entity, world.get::<Parent>(*entity).map(Parent::get)))
+            .collect();
+
+        for entity in entities {
+            if let Some(entity) = world.get_entity_mut(*entity) {
+                entity.despawn_recursive();
+            }
+        }
+
+        Self { scene, roots }
+    }
+
+    fn respawn(self, world: &mut World, respawned: &mut EntityHashMap<Entity>) {
+        match spawn_hierarchies(world, &self.scene, &self.roots) {
+            Ok(entity_map) => respawned.extend(entity_map),
+            Err(error) => warn!("Couldn't respawn despawned entities: {error}"),
+        }
+    }
+
+    fn map_parents(&mut self, respawned: &EntityHashMap<Entity>) {
+        for parent in self
+            .roots
+            .iter_mut()
+            .filter_map(|(_, parent)| parent.as_mut())
+        {
+            map_entity(parent, respawned);
+        }
+    }
+}
+
+/// A scene of the entities and their descendants, with the components that are
+/// registered for reflection.
+pub fn extract_hierarchies(world: &World, entities: &[Entity]) -> DynamicScene {
+    let mut extracted = Vec::new();
+    let mut stack = entities.to_vec();
+    while let Some(entity) = stack.pop() {
+        extracted.push(entity);
+        if let Some(children) = world.get::<Children>(entity) {
+            stack.extend(children.iter());
+        }
+    }
+
+    DynamicSceneBuilder::from_world(world)
+        .extract_entities(extracted.into_iter())
+        .build()
+}
+
+/// Spawns the hierarchies extracted from the roots under their given parents, returning
+/// the new entity of each extracted entity.
+pub fn spawn_hierarchies(
+    world: &mut World,
+    scene: &DynamicScene,
+    roots: &[(Entity, Option<Entity>)],
+) -> Result<EntityHashMap<Entity>, SceneSpawnError> {
+    // Keep parents outside the hierarchies instead of mapping them to new entities
+    let parents: EntityHashMap<Entity> = roots
+        .iter()
+        .filter_map(|(_, parent)| *parent)
+        .map(|parent| (parent, parent))
+        .collect();
+    let mut entity_map = parents.clone();
+    scene.write_to_world(world, &mut entity_map)?;
+    entity_map.retain(|entity, _| !parents.contains_key(entity));
+
+    for (root, parent) in roots {
+        let (Some(new_root), Some(parent)) = (entity_map.get(root), parent) else {
+            continue;
+        };
+        if let Some(mut new_root) = world.get_entity_mut(*new_root) {
+            new_root.remove::<Parent>();
+        }
+        if let Some(mut parent) = world.get_entity_mut(*parent) {
+            parent.add_child(entity_map[root]);
+        }
+    }
+
+    Ok(entity_map)
+}
+
+/// An edit run when picked in the Edit menu.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditMenuItem {
+    Undo,
+    Redo,
+    /// Moves the selected entities under the primary selection.
+    ParentToPrimary,
+    /// Moves the selected entities to the root of the scene.
+    ClearParent,
+}
+
+fn run_edit_menu_items(
+    q_menu_items: Query<(&MenuItem, &EditMenuItem), Changed<MenuItem>>,
+    selection: Res<Selection>,
+    q_parents: Query<&Parent>,
+    mut commands: Commands,
+) {
+    for (item, edit_item) in &q_menu_items {
+        if !item.interacted() {
+            continue;
+        }
+
+        let reparent = |parent: Option<Entity>| {
+            let commands: Vec<Box<dyn EditorCommand>> = selection
+                .entities()
+                .iter()
+                .filter(|entity| Some(**entity) != parent)
+                // Entities can't be moved under their own descendants
+                .filter(|entity| {
+                    parent.map_or(true, |parent| {
+                        !q_parents.iter_ancestors(parent).any(|a| a == **entity)
+                    })
+                })
+                .map(|entity| {
+                    Box::new(ReparentCommand::new(*entity, parent)) as Box<dyn EditorCommand>
+                })
+                .collect();
+            let batch = CommandBatch(commands);
+            move |world: &mut World| world.execute(batch)
+        };
+
+        match edit_item {
+            EditMenuItem::Undo => commands.add(|world: &mut World| {
+                world.undo();
+            }),
+            EditMenuItem::Redo => commands.add(|world: &mut World| {
+                world.redo();
+            }),
+            EditMenuItem::ParentToPrimary => {
+                if let Some(primary) = selection.primary() {
+                    commands.add(reparent(Some(primary)));
+                }
+            }
+            EditMenuItem::ClearParent => commands.add(reparent(None)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gizmo::{clone_entities, record_drag};
+
+    fn test_world(max_size: usize) -> World {
+        let mut world = World::new();
+        let registry = AppTypeRegistry::default();
+        {
+            let mut registry = registry.write();
+            registry.register::<Name>();
+            registry.register::<Transform>();
+            registry.register::<GlobalTransform>();
+            registry.register::<Parent>();
+            registry.register::<Children>();
+        }
+        world.insert_resource(registry);
+        world.insert_resource(History::new(max_size));
+        world
+    }
+
+    fn move_to(world: &World, entity: Entity, translation: Vec3) -> TransformCommand {
+        TransformCommand {
+            changes: vec![TransformChange {
+                entity,
+                before: *world.get::<Transform>(entity).unwrap(),
+                after: Transform::from_translation(translation),
+            }],
+        }
+    }
+
+    fn translation(world: &World, entity: Entity) -> Vec3 {
+        world.get::<Transform>(entity).unwrap().translation
+    }
+
+    #[test]
+    fn undo_and_redo_a_sequence_of_edits() {
+        let mut world = test_world(200);
+        let parent = world
+            .spawn(TransformBundle::from_transform(Transform::from_xyz(
+                1., 0., 0.,
+            )))
+            .id();
+        let child = world.spawn(TransformBundle::default()).id();
+
+        let command = move_to(&world, child, Vec3::Y);
+        world.execute(command);
+        world.execute(ReparentCommand::new(child, Some(parent)));
+        assert_eq!(world.get::<Parent>(child).map(Parent::get), Some(parent));
+
+        assert!(world.undo());
+        assert!(world.get::<Parent>(child).is_none());
+        assert!(world.undo());
+        assert_eq!(translation(&world, child), Vec3::ZERO);
+        assert!(!world.undo());
+
+        assert!(world.redo());
+        assert_eq!(translation(&world, child), Vec3::Y);
+        assert!(world.redo());
+        assert_eq!(world.get::<Parent>(child).map(Parent::get), Some(parent));
+        assert!(world.get::<Children>(parent).unwrap().contains(&child));
+        assert!(!world.redo());
+    }
+
+    #[test]
+    fn recording_an_edit_discards_undone_edits() {
+        let mut world = test_world(200);
+        let entity = world.spawn(TransformBundle::default()).id();
+
+        let command = move_to(&world, entity, Vec3::X);
+        world.execute(command);
+        world.undo();
+        let command = move_to(&world, entity, Vec3::Z);
+        world.execute(command);
+
+        assert!(!world.redo());
+        assert!(world.undo());
+        assert_eq!(translation(&world, entity), Vec3::ZERO);
+    }
+
+    #[test]
+    fn the_history_drops_the_oldest_edits_when_full() {
+        let mut world = test_world(2);
+        let entity = world.spawn(TransformBundle::default()).id();
+
+        for translation in [Vec3::X, Vec3::Y, Vec3::Z] {
+            let command = move_to(&world, entity, translation);
+            world.execute(command);
+        }
+
+        assert!(world.undo());
+        assert!(world.undo());
+        assert!(!world.undo());
+        assert_eq!(translation(&world, entity), Vec3::X);
+    }
+
+    #[test]
+    fn edits_follow_entities_respawned_by_undoing_a_despawn() {
+        let mut world = test_world(200);
+        let parent = world.spawn(TransformBundle::default()).id();
+        let entity = world
+            .spawn((Name::new("Crate"), TransformBundle::default()))
+            .set_parent(parent)
+            .id();
+        let child = world
+            .spawn((Name::new("Lid"), TransformBundle::default()))
+            .set_parent(entity)
+            .id();
+
+        let command = move_to(&world, entity, Vec3::X);
+        world.execute(command);
+        world.execute(DespawnCommand::new(vec![entity]));
+        assert!(world.get_entity(entity).is_none());
+        assert!(world.get_entity(child).is_none());
+
+        // The despawned entity comes back under a new id with its child and parent
+        assert!(world.undo());
+        let mut q_names = world.query::<(Entity, &Name)>();
+        let respawned = q_names
+            .iter(&world)
+            .find_map(|(entity, name)| (name.as_str() == "Crate").then_some(entity))
+            .unwrap();
+        assert_eq!(
+            world.get::<Parent>(respawned).map(Parent::get),
+            Some(parent)
+        );
+        assert!(world.get::<Children>(parent).unwrap().contains(&respawned));
+        assert_eq!(world.get::<Children>(respawned).unwrap().len(), 1);
+        assert_eq!(translation(&world, respawned), Vec3::X);
+
+        // Earlier edits apply to the respawned entity
+        assert!(world.undo());
+        assert_eq!(translation(&world, respawned), Vec3::ZERO);
+
+        assert!(world.redo());
+        assert!(world.redo());
+        assert!(world.get_entity(respawned).is_none());
+    }
+
+    #[test]
+    fn undoing_a_spawn_despawns_the_spawned_entities() {
+        let mut world = test_world(200);
+        let entity = world
+            .spawn((Name::new("Clone"), TransformBundle::default()))
+            .id();
+
+        world
+            .resource_mut::<History>()
+            .record(SpawnCommand::new(vec![entity]));
+        assert!(world.undo());
+        assert!(world.get_entity(entity).is_none());
+
+        assert!(world.redo());
+        let mut q_names = world.query::<&Name>();
+        assert_eq!(q_names.iter(&world).count(), 1);
+    }
+
+    #[test]
+    fn undoing_a_gizmo_drag_restores_the_transforms() {
+        let mut world = test_world(200);
+        let entity = world.spawn(TransformBundle::default()).id();
+
+        // As the gizmo moves the entity while dragging, then records the drag on confirm
+        let change = TransformChange {
+            entity,
+            before: Transform::IDENTITY,
+            after: Transform::from_translation(Vec3::X),
+        };
+        *world.get_mut::<Transform>(entity).unwrap() = change.after;
+        record_drag(&mut world.resource_mut::<History>(), vec![change], false);
+
+        assert!(world.undo());
+        assert_eq!(translation(&world, entity), Vec3::ZERO);
+        assert!(world.redo());
+        assert_eq!(translation(&world, entity), Vec3::X);
+    }
+
+    #[test]
+    fn undoing_a_clone_drag_despawns_the_clones() {
+        let mut world = test_world(200);
+        let original = world
+            .spawn((Name::new("Crate"), TransformBundle::default()))
+            .id();
+
+        let clone = clone_entities(&mut world, &[original]).unwrap()[&original];
+        let change = TransformChange {
+            entity: clone,
+            before: Transform::IDENTITY,
+            after: Transform::from_translation(Vec3::X),
+        };
+        *world.get_mut::<Transform>(clone).unwrap() = change.after;
+        record_drag(&mut world.resource_mut::<History>(), vec![change], true);
+
+        assert!(world.undo());
+        assert!(world.get_entity(clone).is_none());
+        assert_eq!(translation(&world, original), Vec3::ZERO);
+
+        // The clone is respawned where it was dragged to
+        assert!(world.redo());
+        let mut q_names = world.query::<(Entity, &Name)>();
+        let clones: Vec<Entity> = q_names
+            .iter(&world)
+            .filter_map(|(entity, _)| (entity != original).then_some(entity))
+            .collect();
+        assert_eq!(clones.len(), 1);
+        assert_eq!(translation(&world, clones[0]), Vec3::X);
+        assert!(!world.redo());
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use bevy::{
+    prelude::*,
+    reflect::{DynamicEnum, DynamicVariant},
+};
+use serde::{de, de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use thiserror::Error;
+
+use crate::settings::EditorSettings;
+
+/// Inserts the gizmo hotkeys from the [`EditorSettings`].
+pub struct HotkeysPlugin;
+
+impl Plugin for HotkeysPlugin {
+    fn build(&self, app: &mut App) {
+        let hotkeys = app.world().resource::<EditorSettings>().gizmo_hotkeys();
+        app.insert_resource(hotkeys);
+    }
+}
+
+/// The editor actions that can be bound to keys.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GizmoHotkey {
+    Translate,
+    Rotate,
+    Scale,
+    Delete,
+    /// Clones the selection when held while starting to drag a gizmo.
+    Clone,
+    /// Adds to the selection instead of replacing it when held.
+    Multiple,
+    /// Switches the gizmo between world and local space.
+    ToggleSpace,
+    /// Snaps gizmo drags to the grid and angle steps when held.
+    Snap,
+}
+
+/// The keys bound to each [`GizmoHotkey`], any of which triggers it.
+#[derive(Resource, Clone, Debug)]
+pub struct GizmoHotkeys {
+    bindings: HashMap<GizmoHotkey, Vec<KeyCode>>,
+}
+
+impl Default for GizmoHotkeys {
+    fn default() -> Self {
+        Self {
+            bindings: HashMap::from([
+                (GizmoHotkey::Translate, vec![KeyCode::KeyG]),
+                (GizmoHotkey::Rotate, vec![KeyCode::KeyR]),
+                (GizmoHotkey::Scale, vec![KeyCode::KeyS]),
+                (GizmoHotkey::Delete, vec![KeyCode::KeyX]),
+                (GizmoHotkey::Clone, vec![KeyCode::AltLeft]),
+                (
+                    GizmoHotkey::Multiple,
+                    vec![KeyCode::ShiftLeft, KeyCode::ShiftRight],
+                ),
+                (GizmoHotkey::ToggleSpace, vec![KeyCode::KeyQ]),
+                (
+                    GizmoHotkey::Snap,
+                    vec![KeyCode::ControlLeft, KeyCode::ControlRight],
+                ),
+            ]),
+        }
+    }
+}
+
+impl GizmoHotkeys {
+    pub fn keys(&self, hotkey: GizmoHotkey) -> &[KeyCode] {
+        self.bindings.get(&hotkey).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn bind(&mut self, hotkey: GizmoHotkey, keys: Vec<KeyCode>) {
+        self.bindings.insert(hotkey, keys);
+    }
+
+    pub fn pressed(&self, hotkey: GizmoHotkey, input: &ButtonInput<KeyCode>) -> bool {
+        input.any_pressed(self.keys(hotkey).iter().copied())
+    }
+
+    pub fn just_pressed(&self, hotkey: GizmoHotkey, input: &ButtonInput<KeyCode>) -> bool {
+        input.any_just_pressed(self.keys(hotkey).iter().copied())
+    }
+
+    /// The names of the keys bound to each hotkey, as saved in the settings.
+    pub fn key_names(&self) -> BTreeMap<GizmoHotkey, Vec<String>> {
+        self.bindings
+            .iter()
+            .map(|(hotkey, keys)| {
+                let names = keys.iter().map(|key| format!("{key:?}")).collect();
+                (*hotkey, names)
+            })
+            .collect()
+    }
+
+    /// Binds the keys with the given names. Actions missing from `names` or bound to
+    /// an unknown key keep their default keys.
+    pub fn from_key_names(names: &BTreeMap<GizmoHotkey, Vec<String>>) -> Self {
+        let mut hotkeys = Self::default();
+        for (hotkey, names) in names {
+            let keys: Option<Vec<KeyCode>> = names.iter().map(|name| key_code(name)).collect();
+            match keys {
+                Some(keys) => hotkeys.bind(*hotkey, keys),
+                None => warn!("Using the default keys of {hotkey:?}, bound to an unknown key"),
+            }
+        }
+        hotkeys
+    }
+
+    /// Reads the `GizmoHotkey` set from a space_editor settings file. Actions it
+    /// doesn't bind, or binds to unknown keys, keep their default keys.
+    pub fn load_legacy(path: &str) -> Result<Self, LegacySettingsError> {
+        let Some(hotkey_set) = read_legacy_setting::<LegacyHotkeySet>(path, "GizmoHotkey")? else {
+            return Ok(Self::default());
+        };
+
+        let names = hotkey_set
+            .bindings
+            .into_iter()
+            .map(|(hotkey, names)| (hotkey, names.into_iter().map(|name| name.0).collect()))
+            .collect();
+        Ok(Self::from_key_names(&names))
+    }
+}
+
+/// Reads a setting from a space_editor settings file, which maps type names to RON
+/// strings of a map from the type name to the value.
+pub fn read_legacy_setting<T: DeserializeOwned>(
+    path: &str,
+    type_name: &str,
+) -> Result<Option<T>, LegacySettingsError> {
+    let settings: HashMap<String, String> = ron::from_str(&std::fs::read_to_string(path)?)?;
+    let Some(setting) = settings
+        .into_iter()
+        .find_map(|(name, value)| name.contains(type_name).then_some(value))
+    else {
+        return Ok(None);
+    };
+
+    let setting: HashMap<String, T> = ron::from_str(&setting)?;
+    Ok(setting.into_values().next())
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum LegacySettingsError {
+    #[error("Could not read the settings: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse the settings: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+#[derive(Deserialize)]
+struct LegacyHotkeySet {
+    bindings: HashMap<GizmoHotkey, Vec<KeyName>>,
+}
+
+/// A key written as a bare RON identifier, e.g. `KeyG`.
+struct KeyName(String);
+
+impl<'de> Deserialize<'de> for KeyName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeyNameVisitor;
+
+        impl<'de> de::Visitor<'de> for KeyNameVisitor {
+            type Value = KeyName;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a key code name")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<KeyName, E> {
+                Ok(KeyName(value.to_string()))
+            }
+        }
+
+        deserializer.deserialize_identifier(KeyNameVisitor)
+    }
+}
+
+/// The [`KeyCode`] with the given variant name, e.g. `KeyG` or `ShiftLeft`.
+pub fn key_code(name: &str) -> Option<KeyCode> {
+    KeyCode::from_reflect(&DynamicEnum::new(name, DynamicVariant::Unit))
+}
@@ -0,0 +1,677 @@
+use std::any::TypeId;
+
+use bevy::{
+    ecs::{component::ComponentId, entity::EntityHashMap},
+    prelude::*,
+    reflect::{
+        DynamicEnum, DynamicVariant, GetPath, ReflectMut, ReflectRef, TypeInfo, VariantInfo,
+    },
+};
+use sickle_ui::prelude::*;
+
+use crate::{
+    history::{map_entity, EditorCommand, History},
+    play::PlayMode,
+    selection::Selection,
+};
+
+/// How deep nested structs are expanded into fields.
+const MAX_FIELD_DEPTH: usize = 3;
+
+/// Shows the reflected components of the primary [`Selection`] as editable fields.
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<InspectorEdit>()
+            .init_resource::<InspectorRebuilds>()
+            .add_systems(
+                Update,
+                (
+                    rebuild_inspector,
+                    send_inspector_edits.run_if(in_state(PlayMode::Editing)),
+                    apply_inspector_edits.run_if(in_state(PlayMode::Editing)),
+                    sync_inspector_fields,
+                )
+                    .chain()
+                    .after(WidgetLibraryUpdate),
+            );
+    }
+}
+
+/// The container the inspector fields are listed in.
+#[derive(Component, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct InspectorPanel;
+
+/// Counts the requests to list the inspector fields again, e.g. when a value leaves
+/// the range of its slider.
+#[derive(Resource, Debug, Default)]
+struct InspectorRebuilds(u32);
+
+/// The value a field widget and the world last agreed on, so updating the widget
+/// from the world isn't sent back as an edit.
+#[derive(Component, Clone, Debug, PartialEq)]
+struct SyncedValue(FieldValue);
+
+impl SyncedValue {
+    /// Whether the value is the synced one, up to the rounding of the slider.
+    fn matches(&self, value: &FieldValue) -> bool {
+        match (&self.0, value) {
+            (FieldValue::Number(synced), FieldValue::Number(number)) => {
+                (synced - number).abs() <= 1e-5 * synced.abs().max(1.)
+            }
+            (synced, value) => synced == value,
+        }
+    }
+}
+
+/// The range of a field slider, which can't be changed once the slider is built.
+#[derive(Component, Clone, Copy, Debug)]
+struct SliderRange(f32, f32);
+
+impl SliderRange {
+    fn contains(&self, value: f32) -> bool {
+        (self.0..=self.1).contains(&value)
+    }
+}
+
+/// How a reflected field is shown and edited.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldKind {
+    /// Any primitive number, edited with a slider.
+    Number,
+    Bool,
+    /// An enum with only unit variants, edited with a dropdown of the variant names.
+    Variant(Vec<String>),
+    /// A channel of a [`Color`] in sRGBA, from 0 to 1.
+    ColorChannel(usize),
+    /// An Euler angle of a [`Quat`] in degrees, around the X, Y or Z axis.
+    EulerAngle(usize),
+}
+
+/// The value of a field of a given [`FieldKind`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldValue {
+    Number(f64),
+    Bool(bool),
+    Variant(String),
+}
+
+/// A field of a component of a scene entity.
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct InspectorField {
+    pub entity: Entity,
+    pub component: TypeId,
+    /// The reflection path of the field in the component, empty for the component itself.
+    pub path: String,
+    pub kind: FieldKind,
+}
+
+impl InspectorField {
+    /// Reads the field from the world.
+    pub fn read(&self, world: &World) -> Option<FieldValue> {
+        let registry = world.resource::<AppTypeRegistry>().read();
+        let reflect_component = registry.get_type_data::<ReflectComponent>(self.component)?;
+        let component = reflect_component.reflect(world.get_entity(self.entity)?)?;
+        read_field(component.reflect_path(self.path.as_str()).ok()?, &self.kind)
+    }
+
+    /// Writes the field into the world, returning whether it changed.
+    pub fn write(&self, world: &mut World, value: &FieldValue) -> bool {
+        if self.read(world).as_ref() == Some(value) {
+            return false;
+        }
+
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let registry = registry.read();
+        let Some(reflect_component) = registry.get_type_data::<ReflectComponent>(self.component)
+        else {
+            return false;
+        };
+        let Some(mut entity) = world.get_entity_mut(self.entity) else {
+            return false;
+        };
+        let Some(mut component) = reflect_component.reflect_mut(&mut entity) else {
+            return false;
+        };
+        let Ok(field) = component.reflect_path_mut(self.path.as_str()) else {
+            return false;
+        };
+
+        write_field(field, &self.kind, value)
+    }
+}
+
+/// An event sent when a field is edited in the inspector.
+#[derive(Event, Clone, Debug)]
+pub struct InspectorEdit {
+    pub field: InspectorField,
+    pub value: FieldValue,
+}
+
+/// A field edited in the inspector, undone by writing its previous value back.
+pub struct FieldEditCommand {
+    pub field: InspectorField,
+    pub before: FieldValue,
+    pub after: FieldValue,
+}
+
+impl EditorCommand for FieldEditCommand {
+    fn apply(&mut self, world: &mut World, _: &mut EntityHashMap<Entity>) {
+        self.field.write(world, &self.after);
+    }
+
+    fn revert(&mut self, world: &mut World, _: &mut EntityHashMap<Entity>) {
+        self.field.write(world, &self.before);
+    }
+
+    fn map_entities(&mut self, respawned: &EntityHashMap<Entity>) {
+        map_entity(&mut self.field.entity, respawned);
+    }
+}
+
+/// A field found in a component, before its widget is spawned.
+struct FieldDescriptor {
+    label: String,
+    path: String,
+    kind: FieldKind,
+    value: FieldValue,
+}
+
+fn as_number(value: &dyn Reflect) -> Option<f64> {
+    let any = value.as_any();
+    if let Some(value) = any.downcast_ref::<f32>() {
+        Some(*value as f64)
+    } else if let Some(value) = any.downcast_ref::<f64>() {
+        Some(*value)
+    } else if let Some(value) = any.downcast_ref::<i8>() {
+        Some(*value as f64)
+    } else if let Some(value) = any.downcast_ref::<i16>() {
+        Some(*value as f64)
+    } else if let Some(value) = any.downcast_ref::<i32>() {
+        Some(*value as f64)
+    } else if let Some(value) = any.downcast_ref::<i64>() {
+        Some(*value as f64)
+    } else if let Some(value) = any.downcast_ref::<u8>() {
+        Some(*value as f64)
+    } else if let Some(value) = any.downcast_ref::<u16>() {
+        Some(*value as f64)
+    } else if let Some(value) = any.downcast_ref::<u32>() {
+        Some(*value as f64)
+    } else if let Some(value) = any.downcast_ref::<u64>() {
+        Some(*value as f64)
+    } else {
+        any.downcast_ref::<usize>().map(|value| *value as f64)
+    }
+}
+
+fn set_number(value: &mut dyn Reflect, number: f64) -> bool {
+    let any = value.as_any_mut();
+    if let Some(value) = any.downcast_mut::<f32>() {
+        *value = number as f32;
+    } else if let Some(value) = any.downcast_mut::<f64>() {
+        *value = number;
+    } else if let Some(value) = any.downcast_mut::<i8>() {
+        *value = number.round() as i8;
+    } else if let Some(value) = any.downcast_mut::<i16>() {
+        *value = number.round() as i16;
+    } else if let Some(value) = any.downcast_mut::<i32>() {
+        *value = number.round() as i32;
+    } else if let Some(value) = any.downcast_mut::<i64>() {
+        *value = number.round() as i64;
+    } else if let Some(value) = any.downcast_mut::<u8>() {
+        *value = number.round().max(0.) as u8;
+    } else if let Some(value) = any.downcast_mut::<u16>() {
+        *value = number.round().max(0.) as u16;
+    } else if let Some(value) = any.downcast_mut::<u32>() {
+        *value = number.round().max(0.) as u32;
+    } else if let Some(value) = any.downcast_mut::<u64>() {
+        *value = number.round().max(0.) as u64;
+    } else if let Some(value) = any.downcast_mut::<usize>() {
+        *value = number.round().max(0.) as usize;
+    } else {
+        return false;
+    }
+    true
+}
+
+/// The names of the variants of an enum, if they are all unit variants.
+fn unit_variant_names(value: &dyn Reflect) -> Option<Vec<String>> {
+    let Some(TypeInfo::Enum(info)) = value.get_represented_type_info() else {
+        return None;
+    };
+
+    info.iter()
+        .map(|variant| match variant {
+            VariantInfo::Unit(unit) => Some(unit.name().to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+fn read_field(value: &dyn Reflect, kind: &FieldKind) -> Option<FieldValue> {
+    match kind {
+        FieldKind::Number => as_number(value).map(FieldValue::Number),
+        FieldKind::Bool => value
+            .as_any()
+            .downcast_ref::<bool>()
+            .map(|value| FieldValue::Bool(*value)),
+        FieldKind::Variant(_) => match value.reflect_ref() {
+            ReflectRef::Enum(value) => Some(FieldValue::Variant(value.variant_name().into())),
+            _ => None,
+        },
+        FieldKind::ColorChannel(channel) => {
+            let color = value.as_any().downcast_ref::<Color>()?.to_srgba();
+            let channels = [color.red, color.green, color.blue, color.alpha];
+            Some(FieldValue::Number(channels[*channel] as f64))
+        }
+        FieldKind::EulerAngle(axis) => {
+            let rotation = value.as_any().downcast_ref::<Quat>()?;
+            let (x, y, z) = rotation.to_euler(EulerRot::XYZ);
+            Some(FieldValue::Number([x, y, z][*axis].to_degrees() as f64))
+        }
+    }
+}
+
+fn write_field(value: &mut dyn Reflect, kind: &FieldKind, new_value: &FieldValue) -> bool {
+    match (kind, new_value) {
+        (FieldKind::Number, FieldValue::Number(number)) => set_number(value, *number),
+        (FieldKind::Bool, FieldValue::Bool(new_value)) => {
+            let Some(value) = value.as_any_mut().downcast_mut::<bool>() else {
+                return false;
+            };
+            *value = *new_value;
+            true
+        }
+        (FieldKind::Variant(_), FieldValue::Variant(name)) => {
+            let ReflectMut::Enum(value) = value.reflect_mut() else {
+                return false;
+            };
+            value
+                .try_apply(&DynamicEnum::new(name.clone(), DynamicVariant::Unit))
+                .is_ok()
+        }
+        (FieldKind::ColorChannel(channel), FieldValue::Number(number)) => {
+            let Some(color) = value.as_any_mut().downcast_mut::<Color>() else {
+                return false;
+            };
+            let mut srgba = color.to_srgba();
+            match channel {
+                0 => srgba.red = *number as f32,
+                1 => srgba.green = *number as f32,
+                2 => srgba.blue = *number as f32,
+                _ => srgba.alpha = *number as f32,
+            }
+            *color = srgba.into();
+            true
+        }
+        (FieldKind::EulerAngle(axis), FieldValue::Number(degrees)) => {
+            let Some(rotation) = value.as_any_mut().downcast_mut::<Quat>() else {
+                return false;
+            };
+            let mut angles: [f32; 3] = rotation.to_euler(EulerRot::XYZ).into();
+            angles[*axis] = (*degrees as f32).to_radians();
+            *rotation = Quat::from_euler(EulerRot::XYZ, angles[0], angles[1], angles[2]);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Finds the editable fields of a reflected value, expanding structs and tuple structs.
+fn collect_fields(
+    value: &dyn Reflect,
+    path: String,
+    label: String,
+    depth: usize,
+    fields: &mut Vec<FieldDescriptor>,
+) {
+    let mut push = |label: String, kind: FieldKind| {
+        if let Some(value) = read_field(value, &kind) {
+            fields.push(FieldDescriptor {
+                label,
+                path: path.clone(),
+                kind,
+                value,
+            });
+        }
+    };
+
+    if value.as_any().is::<Color>() {
+        for (channel, name) in ["r", "g", "b", "a"].into_iter().enumerate() {
+            push(format!("{label} {name}"), FieldKind::ColorChannel(channel));
+        }
+        return;
+    }
+    if value.as_any().is::<Quat>() {
+        for (axis, name) in ["x", "y", "z"].into_iter().enumerate() {
+            push(format!("{label} {name}°"), FieldKind::EulerAngle(axis));
+        }
+        return;
+    }
+    if as_number(value).is_some() {
+        push(label, FieldKind::Number);
+        return;
+    }
+    if value.as_any().is::<bool>() {
+        push(label, FieldKind::Bool);
+        return;
+    }
+    if depth >= MAX_FIELD_DEPTH {
+        return;
+    }
+
+    match value.reflect_ref() {
+        ReflectRef::Struct(value) => {
+            for index in 0..value.field_len() {
+                let (Some(name), Some(field)) = (value.name_at(index), value.field_at(index))
+                else {
+                    continue;
+                };
+                let label = if label.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{label}.{name}")
+                };
+                collect_fields(field, format!("{path}.{name}"), label, depth + 1, fields);
+            }
+        }
+        ReflectRef::TupleStruct(value) => {
+            for index in 0..value.field_len() {
+                let Some(field) = value.field(index) else {
+                    continue;
+                };
+                // Newtypes are shown under the name of their component
+                let label = match (label.is_empty(), value.field_len()) {
+                    (_, 1) => label.clone(),
+                    (true, _) => index.to_string(),
+                    (false, _) => format!("{label}.{index}"),
+                };
+                collect_fields(field, format!("{path}.{index}"), label, depth + 1, fields);
+            }
+        }
+        ReflectRef::Enum(_) => {
+            if let Some(names) = unit_variant_names(value) {
+                push(label, FieldKind::Variant(names));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A slider range around the current value, which can't go below 0 for unsigned numbers.
+fn slider_range(value: f64, kind: &FieldKind) -> (f32, f32) {
+    match kind {
+        // HDR colors can go above 1
+        FieldKind::ColorChannel(_) => ((value as f32).min(0.), (value as f32).max(1.)),
+        FieldKind::EulerAngle(_) => (-180., 180.),
+        _ => {
+            let value = value as f32;
+            let span = value.abs().max(1.) * 2.;
+            (value - span, value + span)
+        }
+    }
+}
+
+/// Lists the fields of the primary selection again when it or its components change.
+fn rebuild_inspector(
+    world: &World,
+    mut commands: Commands,
+    selection: Res<Selection>,
+    q_panels: Query<Entity, With<InspectorPanel>>,
+    rebuilds: Res<InspectorRebuilds>,
+    mut shown: Local<(Vec<Entity>, Option<Entity>, Vec<ComponentId>, u32)>,
+) {
+    let entity = selection
+        .primary()
+        .filter(|entity| world.get_entity(*entity).is_some());
+    let components: Vec<ComponentId> = entity
+        .map(|entity| {
+            world
+                .inspect_entity(entity)
+                .into_iter()
+                .map(|info| info.id())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let current = (q_panels.iter().collect(), entity, components, rebuilds.0);
+    if *shown == current {
+        return;
+    }
+    *shown = current;
+
+    let registry = world.resource::<AppTypeRegistry>().read();
+    let mut sections = vec![];
+    if let Some(entity) = entity {
+        for info in world.inspect_entity(entity) {
+            let Some(type_id) = info.type_id() else {
+                continue;
+            };
+            let Some(registration) = registry.get(type_id) else {
+                continue;
+            };
+            let Some(reflect_component) = registration.data::<ReflectComponent>() else {
+                continue;
+            };
+            let Some(component) = reflect_component.reflect(world.entity(entity)) else {
+                continue;
+            };
+
+            let mut fields = vec![];
+            collect_fields(component, String::new(), String::new(), 0, &mut fields);
+            sections.push((
+                registration.type_info().type_path_table().short_path(),
+                type_id,
+                fields,
+            ));
+        }
+    }
+
+    for panel in &q_panels {
+        commands.entity(panel).despawn_descendants();
+        commands.ui_builder(panel).column(|column| {
+            column
+                .style()
+                .width(Val::Percent(100.))
+                .padding(UiRect::all(Val::Px(4.)));
+
+            let Some(entity) = entity else {
+                column.label(LabelConfig {
+                    label: "Nothing selected".into(),
+                    ..default()
+                });
+                return;
+            };
+
+            for (name, component, fields) in &sections {
+                column.label(LabelConfig {
+                    label: name.to_string(),
+                    ..default()
+                });
+
+                for field in fields {
+                    let inspector_field = InspectorField {
+                        entity,
+                        component: *component,
+                        path: field.path.clone(),
+                        kind: field.kind.clone(),
+                    };
+                    let label = if field.label.is_empty() {
+                        name.to_string()
+                    } else {
+                        field.label.clone()
+                    };
+
+                    match (&field.kind, &field.value) {
+                        (FieldKind::Bool, FieldValue::Bool(checked)) => {
+                            column
+                                .checkbox(label, *checked)
+                                .insert((inspector_field, SyncedValue(field.value.clone())));
+                        }
+                        (FieldKind::Variant(names), FieldValue::Variant(variant)) => {
+                            column.row(|row| {
+                                row.label(LabelConfig { label, ..default() });
+                                row.dropdown(
+                                    names.clone(),
+                                    names.iter().position(|name| name == variant),
+                                )
+                                .insert((inspector_field, SyncedValue(field.value.clone())));
+                            });
+                        }
+                        (kind, FieldValue::Number(value)) => {
+                            let (min, max) = slider_range(*value, kind);
+                            column
+                                .slider(SliderConfig::horizontal(
+                                    label,
+                                    min,
+                                    max,
+                                    *value as f32,
+                                    true,
+                                ))
+                                .insert((
+                                    inspector_field,
+                                    SyncedValue(FieldValue::Number(*value as f32 as f64)),
+                                    SliderRange(min, max),
+                                ));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Sends an [`InspectorEdit`] for each field widget changed by the user.
+fn send_inspector_edits(
+    mut edit_events: EventWriter<InspectorEdit>,
+    mut q_sliders: Query<(&InspectorField, &Slider, &mut SyncedValue), Changed<Slider>>,
+    mut q_checkboxes: Query<(&InspectorField, &Checkbox, &mut SyncedValue), Changed<Checkbox>>,
+    mut q_dropdowns: Query<(&InspectorField, &Dropdown, &mut SyncedValue), Changed<Dropdown>>,
+) {
+    let mut send = |field: &InspectorField, value: FieldValue, synced: &mut SyncedValue| {
+        // Widgets updated by `sync_inspector_fields` already show the value of the world
+        if !synced.matches(&value) {
+            synced.0 = value.clone();
+            edit_events.send(InspectorEdit {
+                field: field.clone(),
+                value,
+            });
+        }
+    };
+
+    for (field, slider, mut synced) in &mut q_sliders {
+        send(
+            field,
+            FieldValue::Number(slider.value() as f64),
+            &mut *synced,
+        );
+    }
+
+    for (field, checkbox, mut synced) in &mut q_checkboxes {
+        send(field, FieldValue::Bool(checkbox.checked), &mut *synced);
+    }
+
+    for (field, dropdown, mut synced) in &mut q_dropdowns {
+        let FieldKind::Variant(names) = &field.kind else {
+            continue;
+        };
+        let Some(name) = dropdown.value().and_then(|index| names.get(index)) else {
+            continue;
+        };
+        send(field, FieldValue::Variant(name.clone()), &mut *synced);
+    }
+}
+
+/// Writes edited fields, recording a continuous edit of a field, e.g. dragging a slider,
+/// as a single [`FieldEditCommand`] once the mouse is released.
+fn apply_inspector_edits(world: &mut World, mut pending: Local<Option<FieldEditCommand>>) {
+    let edits: Vec<InspectorEdit> = world
+        .resource_mut::<Events<InspectorEdit>>()
+        .drain()
+        .collect();
+
+    for edit in edits {
+        let Some(before) = edit.field.read(world) else {
+            continue;
+        };
+        if !edit.field.write(world, &edit.value) {
+            continue;
+        }
+
+        match pending.as_mut() {
+            Some(command) if command.field == edit.field => command.after = edit.value,
+            _ => {
+                let command = FieldEditCommand {
+                    field: edit.field,
+                    before,
+                    after: edit.value,
+                };
+                if let Some(previous) = pending.replace(command) {
+                    world.resource_mut::<History>().record(previous);
+                }
+            }
+        }
+    }
+
+    let dragging = world
+        .resource::<ButtonInput<MouseButton>>()
+        .pressed(MouseButton::Left);
+    if !dragging {
+        if let Some(command) = pending.take() {
+            world.resource_mut::<History>().record(command);
+        }
+    }
+}
+
+/// Updates the field widgets with values changed elsewhere, e.g. by moving an entity,
+/// listing the fields again when a value leaves the range of its slider.
+fn sync_inspector_fields(world: &mut World) {
+    let mut q_fields = world.query::<(Entity, &InspectorField, &SyncedValue)>();
+    let values: Vec<(Entity, FieldKind, FieldValue)> = q_fields
+        .iter(world)
+        .filter_map(|(widget, field, synced)| {
+            let value = match field.read(world)? {
+                FieldValue::Number(number) => FieldValue::Number(number as f32 as f64),
+                value => value,
+            };
+            (!synced.matches(&value)).then(|| (widget, field.kind.clone(), value))
+        })
+        .collect();
+
+    for (widget, kind, value) in values {
+        match (&kind, &value) {
+            (_, FieldValue::Number(number)) => {
+                let number = *number as f32;
+                let in_range = world
+                    .get::<SliderRange>(widget)
+                    .is_some_and(|range| range.contains(number));
+                if !in_range {
+                    world.resource_mut::<InspectorRebuilds>().0 += 1;
+                    return;
+                }
+                if let Some(mut slider) = world.get_mut::<Slider>(widget) {
+                    slider.set_value(number);
+                }
+            }
+            (_, FieldValue::Bool(checked)) => {
+                if let Some(mut checkbox) = world.get_mut::<Checkbox>(widget) {
+                    checkbox.checked = *checked;
+                }
+            }
+            (FieldKind::Variant(names), FieldValue::Variant(name)) => {
+                let index = names.iter().position(|n| n == name);
+                if let Some(mut dropdown) = world.get_mut::<Dropdown>(widget) {
+                    dropdown.set_value(index);
+                }
+            }
+            _ => continue,
+        }
+
+        if let Some(mut synced) = world.get_mut::<SyncedValue>(widget) {
+            synced.0 = value;
+        }
+    }
+}
@@ -0,0 +1,782 @@
+use std::{fs, io};
+
+use artificer_3d::authored_scene::extract_authored_scene;
+use bevy::{app::AppExit, prelude::*, scene::SceneInstance};
+use serde::{Deserialize, Serialize};
+use sickle_ui::{
+    dev_panels::scene_view::{SceneView, UiSceneViewExt},
+    prelude::*,
+};
+use thiserror::Error;
+
+use crate::{
+    asset_browser::AssetBrowserPanel,
+    hierarchy::HierarchyPanel,
+    history::History,
+    inspector::InspectorPanel,
+    play::{spawn_snapshot, PlayMode},
+    scene_file::{scene_root, CurrentSceneFile},
+    selection::Selection,
+    ShowcaseContainer, UiCamera, UiOutlinedBlockExt, UiTextureAtlasInteractionExt,
+};
+
+/// The file the docking layout is saved to between launches.
+pub const LAYOUT_PATH: &str = "editor_layout.ron";
+
+/// The version of the layouts written by this editor.
+pub const LAYOUT_VERSION: u32 = 1;
+
+/// Restores the docking layout on startup, saves it on exit, and switches between
+/// layout presets from the Window menu.
+pub struct DockingLayoutPlugin;
+
+impl Plugin for DockingLayoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DockingLayout::load_or_default(LAYOUT_PATH))
+            .add_systems(Startup, spawn_floating_panels.after(UiStartupSet))
+            .add_systems(
+                PreUpdate,
+                run_window_menu_items.run_if(in_state(PlayMode::Editing)),
+            )
+            .add_systems(
+                Last,
+                (store_docking_layout, save_docking_layout)
+                    .chain()
+                    .run_if(on_event::<AppExit>()),
+            );
+    }
+}
+
+/// The built-in layouts offered in the Window menu.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LayoutPreset {
+    Default,
+    /// A large scene view between the hierarchy and the inspector.
+    SceneFocus,
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowMenuItem {
+    Preset(LayoutPreset),
+    SaveLayout,
+    RestoreSavedLayout,
+}
+
+/// The docking zones, their tabs and the floating panels of the editor.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DockingLayout {
+    pub version: u32,
+    /// The zones laid out left to right in the editor.
+    pub zones: Vec<LayoutNode>,
+    pub floating: Vec<FloatingLayout>,
+}
+
+/// A docking zone split, whose children alternate between rows and columns, or a
+/// docking zone with tabs. Sizes are percentages of the parent.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum LayoutNode {
+    Split {
+        size: f32,
+        children: Vec<LayoutNode>,
+    },
+    Zone {
+        size: f32,
+        remove_empty: bool,
+        tabs: Vec<PanelKind>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FloatingLayout {
+    pub title: String,
+    pub panel: Option<PanelKind>,
+    pub position: (f32, f32),
+    pub size: (f32, f32),
+}
+
+/// The panels that can be placed in a layout.
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum PanelKind {
+    Hierarchy,
+    AssetBrowser,
+    SceneView,
+    Inspector,
+    Systems,
+    Placeholder,
+    Sliders,
+    Note { title: String, text: String },
+}
+
+impl PanelKind {
+    fn note(title: &str, text: &str) -> Self {
+        Self::Note {
+            title: title.into(),
+            text: text.into(),
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        match self {
+            PanelKind::Hierarchy => "Hierarchy",
+            PanelKind::AssetBrowser => "Asset Browser",
+            PanelKind::SceneView => "Scene View",
+            PanelKind::Inspector => "Inspector",
+            PanelKind::Systems => "Systems",
+            PanelKind::Placeholder => "Placeholder",
+            PanelKind::Sliders => "Sliders",
+            PanelKind::Note { title, .. } => title,
+        }
+    }
+}
+
+/// Keeps the `remove_empty` setting of the docking zones built from a layout.
+#[derive(Component, Clone, Copy, Debug)]
+struct LayoutZone {
+    remove_empty: bool,
+}
+
+/// A floating panel built from a layout.
+#[derive(Component, Clone, Debug)]
+struct LayoutFloatingPanel {
+    title: String,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum LayoutError {
+    #[error("Could not access the layout file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Could not parse the layout: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("Could not serialize the layout: {0}")]
+    Serialize(#[from] ron::Error),
+}
+
+impl Default for DockingLayout {
+    fn default() -> Self {
+        Self::preset(LayoutPreset::Default)
+    }
+}
+
+impl DockingLayout {
+    pub fn preset(preset: LayoutPreset) -> Self {
+        let zone = |size, remove_empty, tabs| LayoutNode::Zone {
+            size,
+            remove_empty,
+            tabs,
+        };
+        let split = |size, children| LayoutNode::Split { size, children };
+
+        let zones = match preset {
+            LayoutPreset::Default => vec![
+                split(
+                    75.,
+                    vec![
+                        split(
+                            75.,
+                            vec![
+                                zone(
+                                    25.,
+                                    true,
+                                    vec![PanelKind::Hierarchy, PanelKind::note("Tab 3", "Panel 3")],
+                                ),
+                                zone(
+                                    75.,
+                                    false,
+                                    vec![
+                                        PanelKind::SceneView,
+                                        PanelKind::note("Tab 2", "Panel 2"),
+                                        PanelKind::note("Tab 3", "Panel 3"),
+                                    ],
+                                ),
+                            ],
+                        ),
+                        zone(
+                            25.,
+                            true,
+                            vec![
+                                PanelKind::AssetBrowser,
+                                PanelKind::Systems,
+                                PanelKind::note("Tab 6", "Panel 6"),
+                            ],
+                        ),
+                    ],
+                ),
+                split(
+                    25.,
+                    vec![zone(
+                        25.,
+                        true,
+                        vec![
+                            PanelKind::Inspector,
+                            PanelKind::Placeholder,
+                            PanelKind::Sliders,
+                        ],
+                    )],
+                ),
+            ],
+            LayoutPreset::SceneFocus => vec![
+                split(
+                    20.,
+                    vec![zone(
+                        100.,
+                        true,
+                        vec![PanelKind::Hierarchy, PanelKind::AssetBrowser],
+                    )],
+                ),
+                split(60., vec![zone(100., false, vec![PanelKind::SceneView])]),
+                split(20., vec![zone(100., true, vec![PanelKind::Inspector])]),
+            ],
+        };
+
+        let floating = match preset {
+            LayoutPreset::Default => vec![FloatingLayout {
+                title: "Root floating panel".into(),
+                panel: None,
+                position: (100., 100.),
+                size: (200., 300.),
+            }],
+            LayoutPreset::SceneFocus => Vec::new(),
+        };
+
+        Self {
+            version: LAYOUT_VERSION,
+            zones,
+            floating,
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Self, LayoutError> {
+        let layout: Self = ron::from_str(&fs::read_to_string(path)?)?;
+        if layout.version > LAYOUT_VERSION {
+            warn!(
+                "{path} was written by a newer editor (version {})",
+                layout.version
+            );
+        }
+        Ok(layout)
+    }
+
+    /// Reads the layout, falling back to the default preset if there is none or it
+    /// can't be read.
+    pub fn load_or_default(path: &str) -> Self {
+        match Self::load(path) {
+            Ok(layout) => layout,
+            Err(LayoutError::Io(error)) if error.kind() == io::ErrorKind::NotFound => {
+                Self::default()
+            }
+            Err(error) => {
+                warn!("Using the default docking layout, couldn't read {path}: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), LayoutError> {
+        let layout = Self {
+            version: LAYOUT_VERSION,
+            ..self.clone()
+        };
+        let text = ron::ser::to_string_pretty(&layout, default())?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+/// Builds the docking zones of the layout in `root`.
+pub fn spawn_docking_layout(commands: &mut Commands, root: Entity, layout: &DockingLayout) {
+    commands
+        .ui_builder(root)
+        .row(|row| {
+            for node in &layout.zones {
+                spawn_layout_node(row, node);
+            }
+        })
+        .style()
+        .height(Val::Percent(100.));
+}
+
+fn spawn_layout_node(parent: &mut UiBuilder<Entity>, node: &LayoutNode) {
+    match node {
+        LayoutNode::Split { size, children } => {
+            parent.docking_zone_split(
+                SizedZoneConfig {
+                    size: *size,
+                    ..default()
+                },
+                |split| {
+                    for child in children {
+                        spawn_layout_node(split, child);
+                    }
+                },
+            );
+        }
+        LayoutNode::Zone {
+            size,
+            remove_empty,
+            tabs,
+        } => {
+            parent
+                .docking_zone(
+                    SizedZoneConfig {
+                        size: *size,
+                        ..default()
+                    },
+                    *remove_empty,
+                    |tab_container| {
+                        for kind in tabs {
+                            tab_container.add_tab(kind.title().into(), |panel| {
+                                spawn_panel(panel, kind);
+                            });
+                        }
+                    },
+                )
+                .insert(LayoutZone {
+                    remove_empty: *remove_empty,
+                });
+        }
+    }
+}
+
+fn spawn_panel(panel: &mut UiBuilder<Entity>, kind: &PanelKind) {
+    panel.insert(kind.clone());
+
+    match kind {
+        PanelKind::Hierarchy => {
+            panel.insert(HierarchyPanel);
+        }
+        PanelKind::AssetBrowser => {
+            panel.insert(AssetBrowserPanel);
+        }
+        PanelKind::SceneView => {
+            panel.scene_view("models/Scene.glb#Scene0");
+        }
+        PanelKind::Inspector => {
+            panel.insert(InspectorPanel);
+        }
+        PanelKind::Systems => {
+            panel.label(LabelConfig {
+                label: "Systems".into(),
+                ..default()
+            });
+        }
+        PanelKind::Placeholder => {
+            panel.style().padding(UiRect::all(Val::Px(10.)));
+
+            panel.row(|row| {
+                row.checkbox(None, false);
+                row.radio_group(vec!["Light", "Dark"], 1, false);
+            });
+
+            panel.row(|row| {
+                row.style().justify_content(JustifyContent::SpaceBetween);
+                row.dropdown(
+                    vec![
+                        "Standard",
+                        "Medium Contrast",
+                        "High Contrast - High Contrast",
+                    ],
+                    None,
+                );
+
+                row.dropdown(
+                    vec![
+                        "Standard",
+                        "Medium Contrast",
+                        "High Contrast - High Contrast",
+                    ],
+                    None,
+                );
+            });
+
+            panel.outlined_block();
+            panel.atlas_example();
+
+            panel.row(|row| {
+                row.style().justify_content(JustifyContent::SpaceBetween);
+                row.dropdown(
+                    vec![
+                        "Standard",
+                        "Medium Contrast",
+                        "High Contrast - High Contrast",
+                    ],
+                    None,
+                );
+                row.checkbox(None, false);
+                row.dropdown(
+                    vec![
+                        "Standard",
+                        "Medium Contrast",
+                        "High Contrast - High Contrast",
+                    ],
+                    None,
+                );
+            });
+        }
+        PanelKind::Sliders => {
+            panel
+                .row(|row| {
+                    row.slider(SliderConfig::vertical(
+                        String::from("Slider"),
+                        0.,
+                        5.,
+                        2.,
+                        true,
+                    ));
+
+                    row.slider(SliderConfig::vertical(None, 0., 5., 2., true));
+
+                    row.slider(SliderConfig::vertical(
+                        String::from("Slider"),
+                        0.,
+                        5.,
+                        2.,
+                        false,
+                    ));
+
+                    row.slider(SliderConfig::vertical(None, 0., 5., 2., false));
+                })
+                .style()
+                .height(Val::Percent(50.));
+
+            panel
+                .column(|row| {
+                    row.slider(SliderConfig::horizontal(
+                        String::from("Slider"),
+                        0.,
+                        5.,
+                        2.,
+                        true,
+                    ));
+                    row.slider(SliderConfig::horizontal(None, 0., 5., 2., true));
+                    row.slider(SliderConfig::horizontal(
+                        String::from("Slider"),
+                        0.,
+                        5.,
+                        2.,
+                        false,
+                    ));
+                    row.slider(SliderConfig::horizontal(None, 0., 5., 2., false));
+                })
+                .style()
+                .justify_content(JustifyContent::End)
+                .height(Val::Percent(50.))
+        }
+        PanelKind::Note { text, .. } => {
+            panel.label(LabelConfig {
+                label: text.clone(),
+                ..default()
+            });
+        }
+    }
+}
+
+fn spawn_floating_panel(commands: &mut Commands, ui_camera: Entity, floating: &FloatingLayout) {
+    commands
+        .ui_builder(UiRoot)
+        .floating_panel(
+            FloatingPanelConfig {
+                title: Some(floating.title.clone()),
+                ..default()
+            },
+            FloatingPanelLayout {
+                size: Vec2::new(floating.size.0, floating.size.1),
+                position: Vec2::new(floating.position.0, floating.position.1).into(),
+                droppable: true,
+            },
+            |container| {
+                if let Some(kind) = &floating.panel {
+                    spawn_panel(container, kind);
+                }
+            },
+        )
+        .insert((
+            TargetCamera(ui_camera),
+            LayoutFloatingPanel {
+                title: floating.title.clone(),
+            },
+        ));
+}
+
+fn spawn_floating_panels(
+    mut commands: Commands,
+    layout: Res<DockingLayout>,
+    q_ui_camera: Query<Entity, With<UiCamera>>,
+) {
+    let Ok(ui_camera) = q_ui_camera.get_single() else {
+        return;
+    };
+
+    for floating in &layout.floating {
+        spawn_floating_panel(&mut commands, ui_camera, floating);
+    }
+}
+
+/// The size of the node as a percentage of its parent along the parent's direction.
+fn size_percent(world: &World, entity: Entity, parent: Entity) -> f32 {
+    let (Some(node), Some(parent_node), Some(parent_style)) = (
+        world.get::<Node>(entity),
+        world.get::<Node>(parent),
+        world.get::<Style>(parent),
+    ) else {
+        return 100.;
+    };
+
+    let (size, parent_size) = match parent_style.flex_direction {
+        FlexDirection::Row | FlexDirection::RowReverse => (node.size().x, parent_node.size().x),
+        FlexDirection::Column | FlexDirection::ColumnReverse => {
+            (node.size().y, parent_node.size().y)
+        }
+    };
+    if parent_size > 0. {
+        size / parent_size * 100.
+    } else {
+        100.
+    }
+}
+
+/// The panels under the entity, in tab order.
+fn capture_panels(world: &World, entity: Entity, panels: &mut Vec<PanelKind>) {
+    if let Some(kind) = world.get::<PanelKind>(entity) {
+        panels.push(kind.clone());
+        return;
+    }
+    if let Some(children) = world.get::<Children>(entity) {
+        for child in children {
+            capture_panels(world, *child, panels);
+        }
+    }
+}
+
+fn capture_sized_zones(world: &World, parent: Entity) -> Vec<LayoutNode> {
+    let Some(children) = world.get::<Children>(parent) else {
+        return Vec::new();
+    };
+
+    children
+        .iter()
+        .filter_map(|child| capture_layout_node(world, *child, parent))
+        .collect()
+}
+
+fn capture_layout_node(world: &World, entity: Entity, parent: Entity) -> Option<LayoutNode> {
+    world.get::<SizedZone>(entity)?;
+    let size = size_percent(world, entity, parent);
+
+    if world.get::<DockingZone>(entity).is_some() {
+        let mut tabs = Vec::new();
+        capture_panels(world, entity, &mut tabs);
+        // Zones docked at runtime remove themselves once empty
+        let remove_empty = world
+            .get::<LayoutZone>(entity)
+            .map_or(true, |zone| zone.remove_empty);
+        if tabs.is_empty() && remove_empty {
+            return None;
+        }
+
+        return Some(LayoutNode::Zone {
+            size,
+            remove_empty,
+            tabs,
+        });
+    }
+
+    let children = capture_sized_zones(world, entity);
+    (!children.is_empty()).then_some(LayoutNode::Split { size, children })
+}
+
+/// Reads the docking layout of the editor as it was rearranged, or `None` when the
+/// docking zones aren't shown.
+fn capture_docking_layout(world: &mut World) -> Option<DockingLayout> {
+    let container = world
+        .query_filtered::<Entity, With<ShowcaseContainer>>()
+        .get_single(world)
+        .ok()?;
+    let row = *world.get::<Children>(container)?.first()?;
+    let zones = capture_sized_zones(world, row);
+    if zones.is_empty() {
+        return None;
+    }
+
+    let floating = world
+        .query::<(Entity, &LayoutFloatingPanel, &Node, &GlobalTransform)>()
+        .iter(world)
+        .map(|(entity, panel, node, transform)| {
+            let mut panels = Vec::new();
+            capture_panels(world, entity, &mut panels);
+            let top_left = transform.translation().truncate() - node.size() / 2.;
+            FloatingLayout {
+                title: panel.title.clone(),
+                panel: panels.into_iter().next(),
+                position: (top_left.x, top_left.y),
+                size: (node.size().x, node.size().y),
+            }
+        })
+        .collect();
+
+    Some(DockingLayout {
+        version: LAYOUT_VERSION,
+        zones,
+        floating,
+    })
+}
+
+/// Keeps the docking layout as rearranged, e.g. before the docking zones are cleared.
+pub fn store_docking_layout(world: &mut World) {
+    if let Some(layout) = capture_docking_layout(world) {
+        world.insert_resource(layout);
+    }
+}
+
+fn save_docking_layout(layout: Res<DockingLayout>) {
+    if let Err(error) = layout.save(LAYOUT_PATH) {
+        error!("Could not save the docking layout to {LAYOUT_PATH}: {error}");
+    }
+}
+
+/// Replaces the docking zones and floating panels with those of the layout, moving
+/// the edited scene to the respawned scene view.
+fn apply_docking_layout(world: &mut World, layout: DockingLayout) {
+    let edited = scene_root(world).map(|root| (root, extract_authored_scene(world, root)));
+
+    let floating_panels: Vec<Entity> = world
+        .query_filtered::<Entity, With<LayoutFloatingPanel>>()
+        .iter(world)
+        .collect();
+    let container = world
+        .query_filtered::<Entity, With<ShowcaseContainer>>()
+        .get_single(world)
+        .ok();
+    let ui_camera = world
+        .query_filtered::<Entity, With<UiCamera>>()
+        .get_single(world)
+        .ok();
+
+    let mut commands = world.commands();
+    for panel in floating_panels {
+        commands.entity(panel).despawn_recursive();
+    }
+    if let Some(container) = container {
+        commands.entity(container).despawn_descendants();
+        spawn_docking_layout(&mut commands, container, &layout);
+    }
+    if let Some(ui_camera) = ui_camera {
+        for floating in &layout.floating {
+            spawn_floating_panel(&mut commands, ui_camera, floating);
+        }
+    }
+    world.flush();
+    world.insert_resource(layout);
+
+    if let Some((old_root, scene)) = edited {
+        move_edited_scene(world, old_root, &scene);
+    }
+}
+
+/// Respawns the edited scene under the root of the respawned scene view instead of
+/// the glTF scene it starts with.
+fn move_edited_scene(world: &mut World, old_root: Entity, scene: &DynamicScene) {
+    if let Some(old_root) = world.get_entity_mut(old_root) {
+        old_root.despawn_recursive();
+    }
+    world.resource_mut::<Selection>().clear();
+
+    let new_root = world
+        .query::<&SceneView>()
+        .iter(world)
+        .map(SceneView::asset_root)
+        .find(|root| *root != old_root);
+    let Some(new_root) = new_root.filter(|root| world.get_entity(*root).is_some()) else {
+        // Without a scene view the edits are gone, so nothing may be saved over the file
+        world.resource_mut::<History>().clear();
+        world.resource_mut::<CurrentSceneFile>().path = None;
+        return;
+    };
+
+    world
+        .entity_mut(new_root)
+        .despawn_descendants()
+        .remove::<(Handle<Scene>, SceneInstance)>();
+    let respawned = spawn_snapshot(world, new_root, scene);
+    // The history refers to the entities of the previous scene view
+    world.resource_mut::<History>().map_entities(&respawned);
+}
+
+fn run_window_menu_items(
+    mut commands: Commands,
+    q_menu_items: Query<(&MenuItem, &WindowMenuItem), Changed<MenuItem>>,
+) {
+    for (item, window_item) in &q_menu_items {
+        if !item.interacted() {
+            continue;
+        }
+
+        match *window_item {
+            WindowMenuItem::Preset(preset) => {
+                commands.add(move |world: &mut World| {
+                    apply_docking_layout(world, DockingLayout::preset(preset));
+                });
+            }
+            WindowMenuItem::SaveLayout => {
+                commands.add(|world: &mut World| {
+                    store_docking_layout(world);
+                    if let Err(error) = world.resource::<DockingLayout>().save(LAYOUT_PATH) {
+                        error!("Could not save the docking layout to {LAYOUT_PATH}: {error}");
+                    }
+                });
+            }
+            WindowMenuItem::RestoreSavedLayout => {
+                commands.add(|world: &mut World| {
+                    apply_docking_layout(world, DockingLayout::load_or_default(LAYOUT_PATH));
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(name)
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn layouts_round_trip_through_ron() {
+        let mut layout = DockingLayout::preset(LayoutPreset::SceneFocus);
+        layout.floating.push(FloatingLayout {
+            title: "Notes".into(),
+            panel: Some(PanelKind::note("Notes", "Remember the exits")),
+            position: (40., 60.),
+            size: (300., 200.),
+        });
+
+        let path = temp_path("artificer_layout_round_trip.ron");
+        layout.save(&path).unwrap();
+        let loaded = DockingLayout::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, layout);
+    }
+
+    #[test]
+    fn missing_or_broken_layouts_fall_back_to_the_default_preset() {
+        let path = temp_path("artificer_layout_missing.ron");
+        let _ = fs::remove_file(&path);
+        assert_eq!(
+            DockingLayout::load_or_default(&path),
+            DockingLayout::default()
+        );
+
+        let path = temp_path("artificer_layout_broken.ron");
+        fs::write(&path, "(version: 1, zones: [").unwrap();
+        let layout = DockingLayout::load_or_default(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(layout, DockingLayout::default());
+    }
+}
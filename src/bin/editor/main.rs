@@ -1,25 +1,57 @@
 //! An example using the widget library to create a simple 3D scene view with a hierarchy browser for the scene asset.
+use artificer_3d::{authored_collider::AuthoredShape, GameplayPlugin};
 use bevy::prelude::*;
 
+use asset_browser::AssetBrowserPlugin;
+use colliders::{ColliderToolsPlugin, PhysicsMenuItem, ShowCollidersToggle};
 use ease::Ease;
+use gizmo::TransformGizmoPlugin;
+use hierarchy::EditorHierarchyPlugin;
+use history::{EditMenuItem, HistoryPlugin};
+use hotkeys::HotkeysPlugin;
+use inspector::InspectorPlugin;
+use layout::{
+    spawn_docking_layout, store_docking_layout, DockingLayout, DockingLayoutPlugin, LayoutPreset,
+    WindowMenuItem,
+};
+use picking::PickingPlugin;
+use play::{spawn_play_controls, PlayPlugin};
+use scene_file::{FileMenuItem, SceneFilePlugin};
+use selection::SelectionPlugin;
+use settings::{EditorSettings, EditorSettingsPlugin};
 use sickle_ui::{
-    dev_panels::{
-        hierarchy::{HierarchyTreeViewPlugin, UiHierarchyExt},
-        scene_view::{SceneView, SceneViewPlugin, SpawnSceneViewPreUpdate, UiSceneViewExt},
-    },
+    dev_panels::scene_view::SceneViewPlugin,
     prelude::*,
     ui_commands::{SetCursorExt, UpdateStatesExt},
     SickleUiPlugin,
 };
 
+mod asset_browser;
+mod colliders;
+mod gizmo;
+mod hierarchy;
+mod history;
+mod hotkeys;
+mod inspector;
+mod layout;
+mod picking;
+mod play;
+mod scene_file;
+mod selection;
+mod settings;
+
 fn main() {
+    // The window is created with the size and position it had when the editor closed
+    let settings = EditorSettings::load_user();
+    let window = Window {
+        title: "Sickle UI -  Simple Editor".into(),
+        ..settings.window.window()
+    };
+
     App::new()
+        .insert_resource(settings)
         .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Sickle UI -  Simple Editor".into(),
-                resolution: (1280., 720.).into(),
-                ..default()
-            }),
+            primary_window: Some(window),
             ..default()
         }))
         .add_plugins(SickleUiPlugin)
@@ -28,18 +60,30 @@ fn main() {
         .add_plugins(TextureAtlasInteractionPlugin)
         .init_resource::<CurrentPage>()
         .init_state::<Page>()
-        .add_plugins(HierarchyTreeViewPlugin)
         .add_plugins(SceneViewPlugin)
+        .add_plugins(GameplayPlugin)
+        .add_plugins(PlayPlugin)
+        .add_plugins(EditorSettingsPlugin)
+        .add_plugins(HotkeysPlugin)
+        .add_plugins(HistoryPlugin)
+        .add_plugins(SelectionPlugin)
+        .add_plugins(PickingPlugin)
+        .add_plugins(TransformGizmoPlugin)
+        .add_plugins(EditorHierarchyPlugin)
+        .add_plugins(InspectorPlugin)
+        .add_plugins(AssetBrowserPlugin)
+        .add_plugins(ColliderToolsPlugin)
+        .add_plugins(SceneFilePlugin)
+        .add_plugins(DockingLayoutPlugin)
         .add_systems(Startup, setup.in_set(UiStartupSet))
         .add_systems(OnEnter(Page::Layout), layout_showcase)
-        .add_systems(OnExit(Page::Layout), clear_content_on_menu_change)
+        .add_systems(
+            OnExit(Page::Layout),
+            (store_docking_layout, clear_content_on_menu_change).chain(),
+        )
         .add_systems(OnEnter(Page::Playground), interaction_showcase)
         .add_systems(OnExit(Page::Playground), clear_content_on_menu_change)
         .add_systems(PreUpdate, exit_app_on_menu_item)
-        .add_systems(
-            PreUpdate,
-            (spawn_hierarchy_view, despawn_hierarchy_view).after(SpawnSceneViewPreUpdate),
-        )
         .add_systems(
             Update,
             (
@@ -329,10 +373,6 @@ struct ExitAppButton;
 #[reflect(Component)]
 struct ShowcaseContainer;
 
-#[derive(Component, Debug, Default, Reflect)]
-#[reflect(Component)]
-struct HierarchyPanel;
-
 #[derive(Resource, Debug, Default, Reflect)]
 #[reflect(Resource)]
 struct CurrentPage(Page);
@@ -343,7 +383,7 @@ pub struct ThemeSwitch;
 #[derive(Component, Debug)]
 pub struct ThemeContrastSelect;
 
-fn setup(mut commands: Commands) {
+fn setup(mut commands: Commands, settings: Res<EditorSettings>) {
     // The main camera which will render UI
     let main_camera = commands
         .spawn((
@@ -398,22 +438,6 @@ fn setup(mut commands: Commands) {
         },
     );
 
-    commands
-        .ui_builder(UiRoot)
-        .floating_panel(
-            FloatingPanelConfig {
-                title: Some("Root floating panel".into()),
-                ..default()
-            },
-            FloatingPanelLayout {
-                size: Vec2::new(200., 300.),
-                position: Vec2::new(100., 100.).into(),
-                droppable: true,
-            },
-            |_| {},
-        )
-        .insert(TargetCamera(main_camera));
-
     // Use the UI builder of the root entity with styling applied via commands
     commands.ui_builder(root_entity).column(|column| {
         column
@@ -422,6 +446,47 @@ fn setup(mut commands: Commands) {
             .background_color(Color::srgb(0.15, 0.155, 0.16));
 
         column.menu_bar(|bar| {
+            bar.menu(
+                MenuConfig {
+                    name: "File".into(),
+                    alt_code: KeyCode::KeyF.into(),
+                    ..default()
+                },
+                |menu| {
+                    menu.menu_item(MenuItemConfig {
+                        name: "New".into(),
+                        shortcut: vec![KeyCode::ControlLeft, KeyCode::KeyN].into(),
+                        alt_code: KeyCode::KeyN.into(),
+                        ..default()
+                    })
+                    .insert(FileMenuItem::New);
+                    menu.menu_item(MenuItemConfig {
+                        name: "Open".into(),
+                        shortcut: vec![KeyCode::ControlLeft, KeyCode::KeyO].into(),
+                        alt_code: KeyCode::KeyO.into(),
+                        ..default()
+                    })
+                    .insert(FileMenuItem::Open);
+
+                    menu.separator();
+
+                    menu.menu_item(MenuItemConfig {
+                        name: "Save".into(),
+                        shortcut: vec![KeyCode::ControlLeft, KeyCode::KeyS].into(),
+                        alt_code: KeyCode::KeyS.into(),
+                        ..default()
+                    })
+                    .insert(FileMenuItem::Save);
+                    menu.menu_item(MenuItemConfig {
+                        name: "Save As".into(),
+                        shortcut: vec![KeyCode::ControlLeft, KeyCode::ShiftLeft, KeyCode::KeyS]
+                            .into(),
+                        alt_code: KeyCode::KeyA.into(),
+                        ..default()
+                    })
+                    .insert(FileMenuItem::SaveAs);
+                },
+            );
             bar.menu(
                 MenuConfig {
                     name: "Showcase".into(),
@@ -455,6 +520,137 @@ fn setup(mut commands: Commands) {
                     .insert(ExitAppButton);
                 },
             );
+            bar.menu(
+                MenuConfig {
+                    name: "Edit".into(),
+                    alt_code: KeyCode::KeyE.into(),
+                    ..default()
+                },
+                |menu| {
+                    menu.menu_item(MenuItemConfig {
+                        name: "Undo".into(),
+                        shortcut: vec![KeyCode::ControlLeft, KeyCode::KeyZ].into(),
+                        alt_code: KeyCode::KeyU.into(),
+                        ..default()
+                    })
+                    .insert(EditMenuItem::Undo);
+                    menu.menu_item(MenuItemConfig {
+                        name: "Redo".into(),
+                        shortcut: vec![KeyCode::ControlLeft, KeyCode::KeyY].into(),
+                        alt_code: KeyCode::KeyR.into(),
+                        ..default()
+                    })
+                    .insert(EditMenuItem::Redo);
+
+                    menu.separator();
+
+                    menu.menu_item(MenuItemConfig {
+                        name: "Parent to active".into(),
+                        shortcut: vec![KeyCode::ControlLeft, KeyCode::KeyP].into(),
+                        alt_code: KeyCode::KeyP.into(),
+                        ..default()
+                    })
+                    .insert(EditMenuItem::ParentToPrimary);
+                    menu.menu_item(MenuItemConfig {
+                        name: "Clear parent".into(),
+                        shortcut: vec![KeyCode::AltLeft, KeyCode::KeyP].into(),
+                        alt_code: KeyCode::KeyC.into(),
+                        ..default()
+                    })
+                    .insert(EditMenuItem::ClearParent);
+                },
+            );
+            bar.menu(
+                MenuConfig {
+                    name: "Physics".into(),
+                    alt_code: KeyCode::KeyP.into(),
+                    ..default()
+                },
+                |menu| {
+                    for (name, alt_code, shape) in [
+                        ("Add box collider", KeyCode::KeyB, AuthoredShape::Cuboid),
+                        ("Add sphere collider", KeyCode::KeyS, AuthoredShape::Sphere),
+                        (
+                            "Add capsule collider",
+                            KeyCode::KeyC,
+                            AuthoredShape::Capsule,
+                        ),
+                        (
+                            "Add cylinder collider",
+                            KeyCode::KeyY,
+                            AuthoredShape::Cylinder,
+                        ),
+                        (
+                            "Add convex hull collider",
+                            KeyCode::KeyH,
+                            AuthoredShape::ConvexHull,
+                        ),
+                        (
+                            "Add trimesh collider",
+                            KeyCode::KeyT,
+                            AuthoredShape::Trimesh,
+                        ),
+                    ] {
+                        menu.menu_item(MenuItemConfig {
+                            name: name.into(),
+                            alt_code: alt_code.into(),
+                            ..default()
+                        })
+                        .insert(PhysicsMenuItem::AddCollider(shape));
+                    }
+                    menu.menu_item(MenuItemConfig {
+                        name: "Remove collider".into(),
+                        alt_code: KeyCode::KeyR.into(),
+                        ..default()
+                    })
+                    .insert(PhysicsMenuItem::RemoveCollider);
+
+                    menu.separator();
+
+                    menu.toggle_menu_item(ToggleMenuItemConfig {
+                        name: "Show colliders".into(),
+                        initially_checked: settings.show_colliders,
+                        ..default()
+                    })
+                    .insert(ShowCollidersToggle);
+                },
+            );
+            bar.menu(
+                MenuConfig {
+                    name: "Window".into(),
+                    alt_code: KeyCode::KeyW.into(),
+                    ..default()
+                },
+                |menu| {
+                    menu.menu_item(MenuItemConfig {
+                        name: "Default layout".into(),
+                        alt_code: KeyCode::KeyD.into(),
+                        ..default()
+                    })
+                    .insert(WindowMenuItem::Preset(LayoutPreset::Default));
+                    menu.menu_item(MenuItemConfig {
+                        name: "Scene focus layout".into(),
+                        alt_code: KeyCode::KeyF.into(),
+                        ..default()
+                    })
+                    .insert(WindowMenuItem::Preset(LayoutPreset::SceneFocus));
+
+                    menu.separator();
+
+                    menu.menu_item(MenuItemConfig {
+                        name: "Save layout".into(),
+                        alt_code: KeyCode::KeyS.into(),
+                        ..default()
+                    })
+                    .insert(WindowMenuItem::SaveLayout);
+                    menu.menu_item(MenuItemConfig {
+                        name: "Restore saved layout".into(),
+                        alt_code: KeyCode::KeyR.into(),
+                        ..default()
+                    })
+                    .insert(WindowMenuItem::RestoreSavedLayout);
+                },
+            );
             bar.menu(
                 MenuConfig {
                     name: "Use case".into(),
@@ -681,6 +877,7 @@ fn setup(mut commands: Commands) {
             bar.separator();
 
             bar.extra_menu(|extra| {
+                spawn_play_controls(extra);
                 extra
                     .radio_group(vec!["Light", "Dark"], 1, false)
                     .insert(ThemeSwitch);
@@ -739,254 +936,13 @@ fn clear_content_on_menu_change(
     commands.set_cursor(CursorIcon::Default);
 }
 
-fn spawn_hierarchy_view(
-    q_added_scene_view: Query<&SceneView, Added<SceneView>>,
-    q_hierarchy_panel: Query<Entity, With<HierarchyPanel>>,
-
-    mut commands: Commands,
-) {
-    for scene_view in &q_added_scene_view {
-        let Ok(container) = q_hierarchy_panel.get_single() else {
-            return;
-        };
-
-        commands.entity(container).despawn_descendants();
-        commands
-            .ui_builder(container)
-            .hierarchy_for(scene_view.asset_root());
-        break;
-    }
-}
-
-fn despawn_hierarchy_view(
-    q_hierarchy_panel: Query<Entity, With<HierarchyPanel>>,
-    q_removed_scene_view: RemovedComponents<SceneView>,
+fn layout_showcase(
+    root_node: Query<Entity, With<ShowcaseContainer>>,
+    layout: Res<DockingLayout>,
     mut commands: Commands,
 ) {
-    let Ok(container) = q_hierarchy_panel.get_single() else {
-        return;
-    };
-
-    if q_removed_scene_view.len() > 0 {
-        commands.entity(container).despawn_descendants();
-    }
-}
-
-fn layout_showcase(root_node: Query<Entity, With<ShowcaseContainer>>, mut commands: Commands) {
     let root_entity = root_node.single();
-
-    commands
-        .ui_builder(root_entity)
-        .row(|row| {
-            row.docking_zone_split(
-                SizedZoneConfig {
-                    size: 75.,
-                    ..default()
-                },
-                |left_side| {
-                    left_side.docking_zone_split(
-                        SizedZoneConfig {
-                            size: 75.,
-                            ..default()
-                        },
-                        |left_side_top| {
-                            left_side_top.docking_zone(
-                                SizedZoneConfig {
-                                    size: 25.,
-                                    ..default()
-                                },
-                                true,
-                                |tab_container| {
-                                    tab_container.add_tab("Hierarchy".into(), |panel| {
-                                        panel.insert(HierarchyPanel);
-                                    });
-                                    tab_container.add_tab("Tab 3".into(), |panel| {
-                                        panel.label(LabelConfig {
-                                            label: "Panel 3".into(),
-                                            ..default()
-                                        });
-                                    });
-                                },
-                            );
-                            left_side_top.docking_zone(
-                                SizedZoneConfig {
-                                    size: 75.,
-                                    ..default()
-                                },
-                                false,
-                                |tab_container| {
-                                    tab_container.add_tab("Scene View".into(), |panel| {
-                                        panel.scene_view("models/Scene.glb#Scene0");
-                                    });
-                                    tab_container.add_tab("Tab 2".into(), |panel| {
-                                        panel.label(LabelConfig {
-                                            label: "Panel 2".into(),
-                                            ..default()
-                                        });
-                                    });
-                                    tab_container.add_tab("Tab 3".into(), |panel| {
-                                        panel.label(LabelConfig {
-                                            label: "Panel 3".into(),
-                                            ..default()
-                                        });
-                                    });
-                                },
-                            );
-                        },
-                    );
-
-                    left_side.docking_zone(
-                        SizedZoneConfig {
-                            size: 25.,
-                            ..default()
-                        },
-                        true,
-                        |tab_container| {
-                            tab_container.add_tab("Systems".into(), |panel| {
-                                panel.label(LabelConfig {
-                                    label: "Systems".into(),
-                                    ..default()
-                                });
-                            });
-                            tab_container.add_tab("Tab 6".into(), |panel| {
-                                panel.label(LabelConfig {
-                                    label: "Panel 6".into(),
-                                    ..default()
-                                });
-                            });
-                        },
-                    );
-                },
-            );
-
-            row.docking_zone_split(
-                SizedZoneConfig {
-                    size: 25.,
-                    ..default()
-                },
-                |right_side| {
-                    right_side.docking_zone(
-                        SizedZoneConfig {
-                            size: 25.,
-                            ..default()
-                        },
-                        true,
-                        |tab_container| {
-                            tab_container.add_tab("Placeholder".into(), |placeholder| {
-                                placeholder.style().padding(UiRect::all(Val::Px(10.)));
-
-                                placeholder.row(|row| {
-                                    row.checkbox(None, false);
-                                    row.radio_group(vec!["Light", "Dark"], 1, false);
-                                });
-
-                                placeholder.row(|row| {
-                                    row.style().justify_content(JustifyContent::SpaceBetween);
-                                    row.dropdown(
-                                        vec![
-                                            "Standard",
-                                            "Medium Contrast",
-                                            "High Contrast - High Contrast",
-                                        ],
-                                        None,
-                                    );
-
-                                    row.dropdown(
-                                        vec![
-                                            "Standard",
-                                            "Medium Contrast",
-                                            "High Contrast - High Contrast",
-                                        ],
-                                        None,
-                                    );
-                                });
-
-                                placeholder.outlined_block();
-                                placeholder.atlas_example();
-
-                                placeholder.row(|row| {
-                                    row.style().justify_content(JustifyContent::SpaceBetween);
-                                    row.dropdown(
-                                        vec![
-                                            "Standard",
-                                            "Medium Contrast",
-                                            "High Contrast - High Contrast",
-                                        ],
-                                        None,
-                                    );
-                                    row.checkbox(None, false);
-                                    row.dropdown(
-                                        vec![
-                                            "Standard",
-                                            "Medium Contrast",
-                                            "High Contrast - High Contrast",
-                                        ],
-                                        None,
-                                    );
-                                });
-                            });
-
-                            tab_container.add_tab("Sliders".into(), |slider_tab| {
-                                slider_tab
-                                    .row(|row| {
-                                        row.slider(SliderConfig::vertical(
-                                            String::from("Slider"),
-                                            0.,
-                                            5.,
-                                            2.,
-                                            true,
-                                        ));
-
-                                        row.slider(SliderConfig::vertical(None, 0., 5., 2., true));
-
-                                        row.slider(SliderConfig::vertical(
-                                            String::from("Slider"),
-                                            0.,
-                                            5.,
-                                            2.,
-                                            false,
-                                        ));
-
-                                        row.slider(SliderConfig::vertical(None, 0., 5., 2., false));
-                                    })
-                                    .style()
-                                    .height(Val::Percent(50.));
-
-                                slider_tab
-                                    .column(|row| {
-                                        row.slider(SliderConfig::horizontal(
-                                            String::from("Slider"),
-                                            0.,
-                                            5.,
-                                            2.,
-                                            true,
-                                        ));
-                                        row.slider(SliderConfig::horizontal(
-                                            None, 0., 5., 2., true,
-                                        ));
-                                        row.slider(SliderConfig::horizontal(
-                                            String::from("Slider"),
-                                            0.,
-                                            5.,
-                                            2.,
-                                            false,
-                                        ));
-                                        row.slider(SliderConfig::horizontal(
-                                            None, 0., 5., 2., false,
-                                        ));
-                                    })
-                                    .style()
-                                    .justify_content(JustifyContent::End)
-                                    .height(Val::Percent(50.))
-                                    .width(Val::Percent(100.));
-                            });
-                        },
-                    );
-                },
-            );
-        })
-        .style()
-        .height(Val::Percent(100.));
+    spawn_docking_layout(&mut commands, root_entity, &layout);
 }
 
 fn interaction_showcase(root_node: Query<Entity, With<ShowcaseContainer>>, mut commands: Commands) {
@@ -0,0 +1,379 @@
+use artificer_3d::authored_scene::GltfInstance;
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    render::{
+        camera::RenderTarget,
+        mesh::{PrimitiveTopology, VertexAttributeValues},
+        primitives::Aabb,
+    },
+    window::PrimaryWindow,
+};
+use sickle_ui::dev_panels::scene_view::SceneView;
+
+use crate::{
+    colliders::ColliderHandles,
+    gizmo::TransformGizmo,
+    hotkeys::{GizmoHotkey, GizmoHotkeys},
+    play::PlayMode,
+    selection::Selection,
+};
+
+/// How far the cursor can move between press and release for a click to select,
+/// so orbiting the scene view camera doesn't change the selection.
+const CLICK_DRAG_THRESHOLD: f32 = 4.;
+
+/// Selects meshes clicked in the scene view, or the glTF instance they belong to, and
+/// outlines the [`Selection`].
+pub struct PickingPlugin;
+
+impl Plugin for PickingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (pick_in_scene_view, draw_selection_outlines)
+                .chain()
+                .run_if(in_state(PlayMode::Editing)),
+        );
+    }
+}
+
+/// The cursor and cameras of the scene views, to cast rays into their scenes.
+#[derive(SystemParam)]
+pub struct SceneViewCursor<'w, 's> {
+    q_window: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    q_scene_views: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static SceneView,
+            &'static Node,
+            &'static GlobalTransform,
+        ),
+    >,
+    q_ui_images: Query<'w, 's, &'static UiImage>,
+    q_children: Query<'w, 's, &'static Children>,
+    q_cameras: Query<'w, 's, (Entity, &'static Camera, &'static GlobalTransform)>,
+}
+
+impl SceneViewCursor<'_, '_> {
+    pub fn position(&self) -> Option<Vec2> {
+        self.q_window.get_single().ok()?.cursor_position()
+    }
+
+    /// The camera rendering the scene view, which displays the image it renders to.
+    pub fn camera(&self, scene_view: Entity) -> Option<(&Camera, &GlobalTransform)> {
+        self.camera_entry(scene_view)
+            .map(|(_, camera, transform)| (camera, transform))
+    }
+
+    fn camera_entry(&self, scene_view: Entity) -> Option<(Entity, &Camera, &GlobalTransform)> {
+        let image = std::iter::once(scene_view)
+            .chain(self.q_children.iter_descendants(scene_view))
+            .find_map(|entity| self.q_ui_images.get(entity).ok())?;
+
+        self.q_cameras.iter().find(|(_, camera, _)| {
+            matches!(&camera.target, RenderTarget::Image(target) if *target == image.texture)
+        })
+    }
+
+    /// The camera entity of the first scene view.
+    pub fn first_view_camera(&self) -> Option<Entity> {
+        let (entity, ..) = self.q_scene_views.iter().next()?;
+        self.camera_entry(entity).map(|(camera, ..)| camera)
+    }
+
+    /// The asset root and camera transform of the first scene view.
+    pub fn first_view(&self) -> Option<(Entity, &GlobalTransform)> {
+        let (entity, scene_view, ..) = self.q_scene_views.iter().next()?;
+        let (_, camera_transform) = self.camera(entity)?;
+        Some((scene_view.asset_root(), camera_transform))
+    }
+
+    /// A ray from the cursor through the camera of the scene view under it, along
+    /// with the asset root of that scene view.
+    pub fn ray(&self) -> Option<(Entity, Ray3d)> {
+        let cursor = self.position()?;
+        let (entity, scene_view, node, transform) =
+            self.q_scene_views.iter().find(|(_, _, node, transform)| {
+                Rect::from_center_size(transform.translation().truncate(), node.size())
+                    .contains(cursor)
+            })?;
+
+        let rect = Rect::from_center_size(transform.translation().truncate(), node.size());
+        let (camera, camera_transform) = self.camera(entity)?;
+        let viewport_size = camera.logical_viewport_size()?;
+        let viewport_position = (cursor - rect.min) / rect.size() * viewport_size;
+        let ray = camera.viewport_to_world(camera_transform, viewport_position)?;
+        Some((scene_view.asset_root(), ray))
+    }
+}
+
+/// The distance along the ray to the nearest triangle of the mesh, or to its
+/// bounding box if the mesh isn't a triangle list.
+pub fn ray_mesh_distance(
+    ray: Ray3d,
+    transform: &GlobalTransform,
+    aabb: &Aabb,
+    mesh: Option<&Mesh>,
+) -> Option<f32> {
+    // Affine transforms keep the distance parameter of points along the ray
+    let world_to_local = transform.affine().inverse();
+    let origin = world_to_local.transform_point3(ray.origin);
+    let direction = world_to_local.transform_vector3(*ray.direction);
+
+    let box_distance = ray_aabb_distance(origin, direction, aabb)?;
+
+    let Some(mesh) =
+        mesh.filter(|mesh| mesh.primitive_topology() == PrimitiveTopology::TriangleList)
+    else {
+        return Some(box_distance);
+    };
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return Some(box_distance);
+    };
+
+    let vertex = |index: usize| positions.get(index).copied().map(Vec3::from);
+    let indices: Vec<usize> = match mesh.indices() {
+        Some(indices) => indices.iter().collect(),
+        None => (0..positions.len()).collect(),
+    };
+
+    indices
+        .chunks_exact(3)
+        .filter_map(|triangle| {
+            let [a, b, c] = [
+                vertex(triangle[0])?,
+                vertex(triangle[1])?,
+                vertex(triangle[2])?,
+            ];
+            ray_triangle_distance(origin, direction, a, b, c)
+        })
+        .min_by(f32::total_cmp)
+}
+
+fn ray_aabb_distance(origin: Vec3, direction: Vec3, aabb: &Aabb) -> Option<f32> {
+    let min = Vec3::from(aabb.min());
+    let max = Vec3::from(aabb.max());
+    let inverse = direction.recip();
+    let t1 = (min - origin) * inverse;
+    let t2 = (max - origin) * inverse;
+
+    let near = t1.min(t2).max_element();
+    let far = t1.max(t2).min_element();
+    (far >= near.max(0.)).then_some(near.max(0.))
+}
+
+/// Möller–Trumbore ray triangle intersection, hitting both faces.
+fn ray_triangle_distance(origin: Vec3, direction: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+    let edge1 = b - a;
+    let edge2 = c - a;
+    let p = direction.cross(edge2);
+    let determinant = edge1.dot(p);
+    if determinant.abs() < f32::EPSILON {
+        return None;
+    }
+
+    let inverse_determinant = 1. / determinant;
+    let t_vec = origin - a;
+    let u = t_vec.dot(p) * inverse_determinant;
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+
+    let q = t_vec.cross(edge1);
+    let v = direction.dot(q) * inverse_determinant;
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inverse_determinant;
+    (t >= 0.).then_some(t)
+}
+
+/// Selects the mesh under the cursor on click, toggling it in the selection with the
+/// [`GizmoHotkey::Multiple`] keys. Clicks on the transform gizmo and the collider
+/// handles are left to them.
+#[allow(clippy::too_many_arguments)]
+pub fn pick_in_scene_view(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    hotkeys: Res<GizmoHotkeys>,
+    gizmo: Res<TransformGizmo>,
+    collider_handles: Res<ColliderHandles>,
+    meshes: Res<Assets<Mesh>>,
+    mut selection: ResMut<Selection>,
+    mut press_position: Local<Option<Vec2>>,
+    scene_view_cursor: SceneViewCursor,
+    q_meshes: Query<(
+        Entity,
+        &Handle<Mesh>,
+        &GlobalTransform,
+        &Aabb,
+        &ViewVisibility,
+    )>,
+    q_parents: Query<&Parent>,
+    q_instances: Query<(), With<GltfInstance>>,
+) {
+    let Some(cursor) = scene_view_cursor.position() else {
+        return;
+    };
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        *press_position = Some(cursor);
+    }
+    if !mouse_input.just_released(MouseButton::Left) {
+        return;
+    }
+    let Some(pressed_at) = press_position.take() else {
+        return;
+    };
+    if pressed_at.distance(cursor) > CLICK_DRAG_THRESHOLD
+        || gizmo.is_active()
+        || collider_handles.is_active()
+    {
+        return;
+    }
+
+    let Some((asset_root, ray)) = scene_view_cursor.ray() else {
+        return;
+    };
+
+    let hit = q_meshes
+        .iter()
+        .filter(|(entity, .., visibility)| {
+            visibility.get() && q_parents.iter_ancestors(*entity).any(|a| a == asset_root)
+        })
+        .filter_map(|(entity, mesh, transform, aabb, _)| {
+            let distance = ray_mesh_distance(ray, transform, aabb, meshes.get(mesh))?;
+            Some((entity, distance))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    let extend = hotkeys.pressed(GizmoHotkey::Multiple, &keyboard_input);
+    match hit {
+        Some((entity, _)) => {
+            let entity = editable_entity(entity, asset_root, &q_parents, &q_instances);
+            selection.select(entity, extend);
+        }
+        None if !extend => selection.clear(),
+        None => {}
+    }
+}
+
+/// The entity edited when picking `entity`: the outermost [`GltfInstance`] it was
+/// spawned by, as the entities of glTF instances aren't saved, or else itself.
+pub fn editable_entity(
+    entity: Entity,
+    asset_root: Entity,
+    q_parents: &Query<&Parent>,
+    q_instances: &Query<(), With<GltfInstance>>,
+) -> Entity {
+    std::iter::once(entity)
+        .chain(q_parents.iter_ancestors(entity))
+        .take_while(|ancestor| *ancestor != asset_root)
+        .filter(|ancestor| q_instances.contains(*ancestor))
+        .last()
+        .unwrap_or(entity)
+}
+
+/// Draws the bounds of the selected meshes, the primary selection brighter.
+fn draw_selection_outlines(
+    mut gizmos: Gizmos,
+    selection: Res<Selection>,
+    q_children: Query<&Children>,
+    q_bounds: Query<(&GlobalTransform, &Aabb)>,
+) {
+    for selected in selection.entities() {
+        let color = if selection.primary() == Some(*selected) {
+            Color::srgb(1., 0.6, 0.1)
+        } else {
+            Color::srgb(0.9, 0.8, 0.3)
+        };
+
+        for entity in std::iter::once(*selected).chain(q_children.iter_descendants(*selected)) {
+            let Ok((transform, aabb)) = q_bounds.get(entity) else {
+                continue;
+            };
+
+            let bounds = Transform::from_translation(aabb.center.into())
+                .with_scale(Vec3::from(aabb.half_extents) * 2.);
+            gizmos.cuboid(transform.mul_transform(bounds), color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use artificer_3d::authored_scene::save_authored_scene;
+    use bevy::{ecs::system::SystemState, scene::serde::SceneDeserializer};
+    use serde::de::DeserializeSeed;
+
+    use super::*;
+
+    #[test]
+    fn edits_of_picked_gltf_meshes_are_saved_on_their_instance() {
+        let mut world = World::new();
+        let registry = AppTypeRegistry::default();
+        {
+            let mut registry = registry.write();
+            registry.register::<Name>();
+            registry.register::<Transform>();
+            registry.register::<GlobalTransform>();
+            registry.register::<Parent>();
+            registry.register::<Children>();
+            registry.register::<GltfInstance>();
+        }
+        world.insert_resource(registry);
+
+        let asset_root = world.spawn(SpatialBundle::default()).id();
+        let instance = world
+            .spawn((
+                Name::new("Arena"),
+                SpatialBundle::default(),
+                GltfInstance {
+                    path: "models/Scene.glb#Scene0".into(),
+                },
+            ))
+            .set_parent(asset_root)
+            .id();
+        let node = world
+            .spawn((Name::new("Cube"), SpatialBundle::default()))
+            .set_parent(instance)
+            .id();
+        let mesh = world
+            .spawn((Name::new("Cube.0"), SpatialBundle::default()))
+            .set_parent(node)
+            .id();
+
+        let mut state =
+            SystemState::<(Query<&Parent>, Query<(), With<GltfInstance>>)>::new(&mut world);
+        let (q_parents, q_instances) = state.get(&world);
+        let picked = editable_entity(mesh, asset_root, &q_parents, &q_instances);
+        assert_eq!(picked, instance);
+
+        // As if moved with the gizmo
+        world.get_mut::<Transform>(picked).unwrap().translation = Vec3::new(1., 2., 3.);
+        let ron = save_authored_scene(&world, asset_root).unwrap();
+
+        let scene = {
+            let registry = world.resource::<AppTypeRegistry>().read();
+            let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+            SceneDeserializer {
+                type_registry: &registry,
+            }
+            .deserialize(&mut deserializer)
+            .unwrap()
+        };
+        assert_eq!(scene.entities.len(), 1);
+        let transform = scene.entities[0]
+            .components
+            .iter()
+            .find_map(|component| Transform::from_reflect(component.as_ref()))
+            .unwrap();
+        assert_eq!(transform.translation, Vec3::new(1., 2., 3.));
+    }
+}
@@ -0,0 +1,300 @@
+use artificer_3d::{
+    app_state::{AppState, LoadingAssets},
+    authored_scene::{extract_authored_scene, GltfInstance},
+    game_management::Team,
+    level::Level,
+    player_bundle,
+    projectile::Projectile,
+    MainCamera,
+};
+use bevy::{
+    ecs::{entity::EntityHashMap, system::SystemState},
+    prelude::*,
+};
+use sickle_ui::prelude::*;
+
+use crate::{
+    history::History, picking::SceneViewCursor, scene_file::scene_root, selection::Selection,
+};
+
+/// Runs the gameplay in the scene view with the Play, Pause and Stop buttons of the
+/// menu bar, restoring the edited scene on stop.
+pub struct PlayPlugin;
+
+impl Plugin for PlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<PlayMode>()
+            .init_resource::<PlaySnapshot>()
+            .add_systems(
+                Update,
+                (press_play_controls, update_play_control_style)
+                    .chain()
+                    .after(WidgetLibraryUpdate),
+            );
+    }
+}
+
+/// Whether the scene is edited or played. Editing systems only run while editing.
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum PlayMode {
+    #[default]
+    Editing,
+    /// The gameplay runs, paused or not as per the game's [`AppState`].
+    Playing,
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayControl {
+    Play,
+    Pause,
+    Stop,
+}
+
+impl PlayControl {
+    const ALL: [Self; 3] = [Self::Play, Self::Pause, Self::Stop];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Play => "Play",
+            Self::Pause => "Pause",
+            Self::Stop => "Stop",
+        }
+    }
+}
+
+/// The edited scene as it was when play started, respawned on stop.
+#[derive(Resource, Default)]
+struct PlaySnapshot(Option<DynamicScene>);
+
+/// Adds the Play, Pause and Stop buttons, e.g. to the extra area of the menu bar.
+pub fn spawn_play_controls(builder: &mut UiBuilder<Entity>) {
+    builder.row(|row| {
+        for control in PlayControl::ALL {
+            row.container(
+                (
+                    ButtonBundle {
+                        style: Style {
+                            padding: UiRect::axes(Val::Px(8.), Val::Px(2.)),
+                            margin: UiRect::horizontal(Val::Px(2.)),
+                            ..default()
+                        },
+                        background_color: Color::NONE.into(),
+                        ..default()
+                    },
+                    control,
+                ),
+                |button| {
+                    button.label(LabelConfig {
+                        label: control.label().into(),
+                        ..default()
+                    });
+                },
+            );
+        }
+    });
+}
+
+/// Spawns the scene under the root, returning the new entity of each scene entity.
+pub fn spawn_snapshot(
+    world: &mut World,
+    root: Entity,
+    scene: &DynamicScene,
+) -> EntityHashMap<Entity> {
+    let mut entity_map = EntityHashMap::default();
+    if let Err(error) = scene.write_to_world(world, &mut entity_map) {
+        error!("Could not respawn the edited scene: {error}");
+    }
+
+    let roots: Vec<Entity> = entity_map
+        .values()
+        .copied()
+        .filter(|entity| world.get::<Parent>(*entity).is_none())
+        .collect();
+    world.entity_mut(root).push_children(&roots);
+    entity_map
+}
+
+/// The camera rendering the scene view, which the player moves and aims relative to.
+fn scene_view_camera(world: &mut World) -> Option<Entity> {
+    let mut state = SystemState::<SceneViewCursor>::new(world);
+    let cursor = state.get(world);
+    cursor.first_view_camera()
+}
+
+/// Snapshots the edited scene and respawns it as a level, so colliders are generated
+/// for its glTF scenes, then spawns the player and starts the game.
+///
+/// The level is a child of the scene view's asset root, so `LoadLevel` triggers
+/// despawning every [`Level`] keep the root.
+fn start_play(world: &mut World) {
+    let Some(root) = scene_root(world) else {
+        return;
+    };
+
+    let scene = extract_authored_scene(world, root);
+    world.entity_mut(root).despawn_descendants();
+    let level = world
+        .spawn((
+            Name::new("Level"),
+            SpatialBundle::default(),
+            Level::default(),
+        ))
+        .set_parent(root)
+        .id();
+    spawn_snapshot(world, level, &scene);
+    world.resource_mut::<PlaySnapshot>().0 = Some(scene);
+    world.resource_mut::<Selection>().clear();
+
+    // Gameplay starts once the glTF scenes are loaded
+    let paths: Vec<String> = world
+        .query::<&GltfInstance>()
+        .iter(world)
+        .map(|instance| instance.path.clone())
+        .collect();
+    for path in paths {
+        let scene = world.resource::<AssetServer>().load::<Scene>(path);
+        world.resource_mut::<LoadingAssets>().add(scene);
+    }
+
+    let mesh = world
+        .resource_mut::<Assets<Mesh>>()
+        .add(Capsule3d::new(0.5, 1.0));
+    let material = world
+        .resource_mut::<Assets<StandardMaterial>>()
+        .add(Color::srgb(0.3, 0.5, 0.9));
+    let player = world
+        .spawn((
+            Name::new("Player"),
+            PbrBundle {
+                mesh,
+                material,
+                // Placed at the level's `PlayerSpawn` once it is loaded
+                transform: Transform::from_xyz(0., 2., 0.),
+                ..default()
+            },
+            player_bundle(),
+        ))
+        .id();
+    world.entity_mut(root).add_child(player);
+
+    if let Some(camera) = scene_view_camera(world) {
+        world.entity_mut(camera).insert(MainCamera);
+    }
+
+    world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Loading);
+    world
+        .resource_mut::<NextState<PlayMode>>()
+        .set(PlayMode::Playing);
+}
+
+/// Ends the game and respawns the scene snapshot taken when play started.
+fn stop_play(world: &mut World) {
+    world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::MainMenu);
+    world
+        .resource_mut::<NextState<PlayMode>>()
+        .set(PlayMode::Editing);
+
+    // Enemies, projectiles and levels loaded by triggers are spawned outside of the scene
+    let spawned: Vec<Entity> = world
+        .query_filtered::<Entity, Or<(With<Team>, With<Projectile>, With<Level>)>>()
+        .iter(world)
+        .collect();
+    for entity in spawned {
+        if let Some(entity) = world.get_entity_mut(entity) {
+            entity.despawn_recursive();
+        }
+    }
+
+    let cameras: Vec<Entity> = world
+        .query_filtered::<Entity, With<MainCamera>>()
+        .iter(world)
+        .collect();
+    for camera in cameras {
+        world.entity_mut(camera).remove::<MainCamera>();
+    }
+
+    let Some(root) = scene_root(world) else {
+        return;
+    };
+    let Some(scene) = world.resource_mut::<PlaySnapshot>().0.take() else {
+        return;
+    };
+
+    match world.get_or_spawn(root) {
+        Some(mut root) => {
+            root.despawn_descendants();
+            if !root.contains::<Transform>() {
+                warn!("The scene view's asset root was despawned during play, respawning it");
+                root.insert(SpatialBundle::default());
+            }
+        }
+        None => {
+            error!("Could not respawn the edited scene, the scene view's asset root is gone");
+            return;
+        }
+    }
+    let respawned = spawn_snapshot(world, root, &scene);
+    // The history refers to the entities the snapshot was taken from
+    world.resource_mut::<History>().map_entities(&respawned);
+    world.resource_mut::<Selection>().clear();
+}
+
+fn press_play_controls(
+    mut commands: Commands,
+    q_controls: Query<(&Interaction, &PlayControl), Changed<Interaction>>,
+    play_mode: Res<State<PlayMode>>,
+    app_state: Res<State<AppState>>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, control) in &q_controls {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match (control, play_mode.get(), app_state.get()) {
+            (PlayControl::Play, PlayMode::Editing, _) => commands.add(start_play),
+            (PlayControl::Play, PlayMode::Playing, AppState::Paused) => {
+                next_app_state.set(AppState::InGame)
+            }
+            (PlayControl::Pause, PlayMode::Playing, AppState::InGame) => {
+                next_app_state.set(AppState::Paused)
+            }
+            (PlayControl::Pause, PlayMode::Playing, AppState::Paused) => {
+                next_app_state.set(AppState::InGame)
+            }
+            (PlayControl::Stop, PlayMode::Playing, _) => commands.add(stop_play),
+            _ => {}
+        }
+    }
+}
+
+/// Highlights the button of the current play state.
+fn update_play_control_style(
+    play_mode: Res<State<PlayMode>>,
+    app_state: Res<State<AppState>>,
+    mut q_controls: Query<(&PlayControl, &Interaction, &mut BackgroundColor)>,
+) {
+    let paused = *app_state.get() == AppState::Paused;
+    for (control, interaction, mut background_color) in &mut q_controls {
+        let active = match control {
+            PlayControl::Play => *play_mode.get() == PlayMode::Playing && !paused,
+            PlayControl::Pause => *play_mode.get() == PlayMode::Playing && paused,
+            PlayControl::Stop => false,
+        };
+        let color = if active {
+            Color::srgba(0.25, 0.45, 0.8, 0.6)
+        } else if *interaction == Interaction::Hovered {
+            Color::srgba(1., 1., 1., 0.08)
+        } else {
+            Color::NONE
+        };
+
+        if background_color.0 != color {
+            background_color.0 = color;
+        }
+    }
+}
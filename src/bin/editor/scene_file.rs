@@ -0,0 +1,348 @@
+use std::{fs, path::Path};
+
+use artificer_3d::authored_scene::{
+    is_authored_scene, save_authored_scene, GltfInstance, AUTHORED_SCENE_EXTENSION,
+};
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        InputSystem,
+    },
+    prelude::*,
+    scene::SceneInstance,
+};
+use sickle_ui::{dev_panels::scene_view::SceneView, prelude::*};
+use thiserror::Error;
+
+use crate::{
+    history::History, play::PlayMode, selection::Selection, settings::EditorSettings, UiCamera,
+};
+
+/// The folder asset paths are relative to.
+pub const ASSET_FOLDER: &str = "assets";
+
+/// The File menu, creating, opening and saving the scene edited in the scene view as
+/// an authored `.scn.ron` scene.
+pub struct SceneFilePlugin;
+
+impl Plugin for SceneFilePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CurrentSceneFile>()
+            .add_systems(
+                PreUpdate,
+                (
+                    type_in_path_prompt.after(InputSystem),
+                    run_file_menu_items.run_if(in_state(PlayMode::Editing)),
+                )
+                    .chain(),
+            )
+            .add_systems(Update, (instance_scene_view_scene, track_recent_files));
+    }
+}
+
+/// The asset path of the edited scene, once it was opened or saved.
+#[derive(Resource, Debug, Default)]
+pub struct CurrentSceneFile {
+    pub path: Option<String>,
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileMenuItem {
+    New,
+    Open,
+    Save,
+    SaveAs,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PromptAction {
+    Open,
+    SaveAs,
+}
+
+/// Asks for the asset path of the scene to open or save, e.g. `levels/arena.scn.ron`.
+#[derive(Component, Debug)]
+struct PathPrompt {
+    action: PromptAction,
+    text: String,
+    label: Entity,
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SceneFileError {
+    #[error("Could not serialize the scene: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Could not write the scene: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Adds the authored scene extension to the path if it doesn't have it.
+fn authored_scene_path(path: &str) -> String {
+    let path = path.trim();
+    if is_authored_scene(path) {
+        path.to_string()
+    } else {
+        format!("{path}.{AUTHORED_SCENE_EXTENSION}")
+    }
+}
+
+/// The entity the edited scene is spawned under.
+pub fn scene_root(world: &mut World) -> Option<Entity> {
+    world
+        .query::<&SceneView>()
+        .iter(world)
+        .next()
+        .map(SceneView::asset_root)
+}
+
+fn new_scene(world: &mut World) {
+    let Some(root) = scene_root(world) else {
+        return;
+    };
+
+    world.entity_mut(root).despawn_descendants().remove::<(
+        Handle<Scene>,
+        Handle<DynamicScene>,
+        SceneInstance,
+    )>();
+    world.resource_mut::<History>().clear();
+    world.resource_mut::<Selection>().clear();
+    world.resource_mut::<CurrentSceneFile>().path = None;
+}
+
+fn open_scene(world: &mut World, path: String) {
+    new_scene(world);
+    let Some(root) = scene_root(world) else {
+        return;
+    };
+
+    // The roots of the scene are spawned as children of the scene view root
+    let scene = world.resource::<AssetServer>().load::<DynamicScene>(&path);
+    world.entity_mut(root).insert(scene);
+    world.resource_mut::<CurrentSceneFile>().path = Some(path);
+}
+
+fn write_scene(world: &World, root: Entity, path: &str) -> Result<(), SceneFileError> {
+    let ron = save_authored_scene(world, root)?;
+    let file_path = Path::new(ASSET_FOLDER).join(path);
+    if let Some(folder) = file_path.parent() {
+        fs::create_dir_all(folder)?;
+    }
+    fs::write(file_path, ron)?;
+    Ok(())
+}
+
+fn save_scene(world: &mut World, path: String) {
+    let Some(root) = scene_root(world) else {
+        return;
+    };
+
+    match write_scene(world, root, &path) {
+        Ok(()) => {
+            info!("Saved the scene to {path}");
+            world.resource_mut::<CurrentSceneFile>().path = Some(path);
+        }
+        Err(error) => error!("Could not save the scene to {path}: {error}"),
+    }
+}
+
+fn spawn_path_prompt(
+    commands: &mut Commands,
+    ui_camera: Entity,
+    action: PromptAction,
+    text: String,
+) {
+    let label = commands
+        .spawn(TextBundle::from_section("", TextStyle::default()))
+        .id();
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(40.),
+                    left: Val::Percent(30.),
+                    width: Val::Percent(40.),
+                    padding: UiRect::all(Val::Px(8.)),
+                    ..default()
+                },
+                background_color: Color::srgb(0.1, 0.1, 0.11).into(),
+                z_index: ZIndex::Global(100),
+                ..default()
+            },
+            TargetCamera(ui_camera),
+            PathPrompt {
+                action,
+                text,
+                label,
+            },
+        ))
+        .add_child(label);
+}
+
+fn run_file_menu_items(
+    mut commands: Commands,
+    q_menu_items: Query<(&MenuItem, &FileMenuItem), Changed<MenuItem>>,
+    current: Res<CurrentSceneFile>,
+    settings: Res<EditorSettings>,
+    q_prompts: Query<(), With<PathPrompt>>,
+    q_ui_camera: Query<Entity, With<UiCamera>>,
+) {
+    for (item, file_item) in &q_menu_items {
+        if !item.interacted() {
+            continue;
+        }
+
+        let prompt = match (file_item, &current.path) {
+            (FileMenuItem::New, _) => {
+                commands.add(new_scene);
+                None
+            }
+            (FileMenuItem::Save, Some(path)) => {
+                let path = path.clone();
+                commands.add(move |world: &mut World| save_scene(world, path));
+                None
+            }
+            (FileMenuItem::Open, _) => Some((
+                PromptAction::Open,
+                settings
+                    .recent_files
+                    .first()
+                    .cloned()
+                    .unwrap_or_else(|| "levels/".to_string()),
+            )),
+            (FileMenuItem::Save, None) | (FileMenuItem::SaveAs, _) => Some((
+                PromptAction::SaveAs,
+                current
+                    .path
+                    .clone()
+                    .unwrap_or_else(|| "levels/".to_string()),
+            )),
+        };
+
+        let Some((action, text)) = prompt else {
+            continue;
+        };
+        if !q_prompts.is_empty() {
+            continue;
+        }
+        let Ok(ui_camera) = q_ui_camera.get_single() else {
+            continue;
+        };
+        spawn_path_prompt(&mut commands, ui_camera, action, text);
+    }
+}
+
+/// Edits the open [`PathPrompt`], keeping the typed keys from the hotkeys and shortcuts.
+fn type_in_path_prompt(
+    mut commands: Commands,
+    mut keyboard_input: ResMut<ButtonInput<KeyCode>>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut q_prompts: Query<(Entity, &mut PathPrompt)>,
+    mut q_texts: Query<&mut Text>,
+) {
+    let Ok((entity, mut prompt)) = q_prompts.get_single_mut() else {
+        // Keys pressed before the prompt opens aren't typed in it
+        keyboard_events.clear();
+        return;
+    };
+
+    for event in keyboard_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Character(text) => prompt.text.push_str(text),
+            Key::Space => prompt.text.push(' '),
+            Key::Backspace => {
+                prompt.text.pop();
+            }
+            Key::Enter => {
+                let path = authored_scene_path(&prompt.text);
+                match prompt.action {
+                    PromptAction::Open => {
+                        commands.add(move |world: &mut World| open_scene(world, path))
+                    }
+                    PromptAction::SaveAs => {
+                        commands.add(move |world: &mut World| save_scene(world, path))
+                    }
+                }
+                commands.entity(entity).despawn_recursive();
+                break;
+            }
+            Key::Escape => {
+                commands.entity(entity).despawn_recursive();
+                break;
+            }
+            _ => {}
+        }
+    }
+    keyboard_input.reset_all();
+
+    if let Ok(mut text) = q_texts.get_mut(prompt.label) {
+        let title = match prompt.action {
+            PromptAction::Open => "Open",
+            PromptAction::SaveAs => "Save as",
+        };
+        text.sections[0].value = format!("{title}: {}_", prompt.text);
+    }
+}
+
+fn track_recent_files(current: Res<CurrentSceneFile>, mut settings: ResMut<EditorSettings>) {
+    if !current.is_changed() {
+        return;
+    }
+    if let Some(path) = &current.path {
+        settings.add_recent_file(path);
+    }
+}
+
+/// Turns the glTF scene the scene view starts with into a [`GltfInstance`] under its
+/// root, so it is saved as a reference to the glTF file.
+fn instance_scene_view_scene(
+    mut commands: Commands,
+    q_scene_views: Query<&SceneView>,
+    q_scenes: Query<&Handle<Scene>, (With<SceneInstance>, Without<GltfInstance>)>,
+) {
+    for scene_view in &q_scene_views {
+        let root = scene_view.asset_root();
+        // Waits for the scene to be spawned, so it isn't spawned again afterwards
+        let Ok(scene) = q_scenes.get(root) else {
+            continue;
+        };
+        let Some(path) = scene.path() else {
+            continue;
+        };
+
+        let instance = commands
+            .spawn((
+                Name::new(path.path().display().to_string()),
+                SpatialBundle::default(),
+                GltfInstance {
+                    path: path.to_string(),
+                },
+            ))
+            .id();
+        commands
+            .entity(root)
+            .remove::<(Handle<Scene>, SceneInstance)>()
+            .despawn_descendants()
+            .add_child(instance);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_paths_get_the_authored_scene_extension() {
+        assert_eq!(authored_scene_path("levels/arena"), "levels/arena.scn.ron");
+        assert_eq!(
+            authored_scene_path(" levels/arena.scn.ron "),
+            "levels/arena.scn.ron"
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Selection>()
+            .add_systems(PreUpdate, remove_despawned_from_selection);
+    }
+}
+
+/// The scene entities selected in the editor, the last one being the primary selection.
+#[derive(Resource, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Selection {
+    entities: Vec<Entity>,
+}
+
+impl Selection {
+    /// The most recently selected entity, shown in the inspector.
+    pub fn primary(&self) -> Option<Entity> {
+        self.entities.last().copied()
+    }
+
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.entities.contains(&entity)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Selects only the given entity.
+    pub fn set(&mut self, entity: Entity) {
+        self.entities.clear();
+        self.entities.push(entity);
+    }
+
+    /// Adds the entity to the selection, or removes it if it was already selected.
+    pub fn toggle(&mut self, entity: Entity) {
+        if let Some(index) = self.entities.iter().position(|e| *e == entity) {
+            self.entities.remove(index);
+        } else {
+            self.entities.push(entity);
+        }
+    }
+
+    /// Selects the entity, toggling it in a multiple selection if `extend` is set.
+    pub fn select(&mut self, entity: Entity, extend: bool) {
+        if extend {
+            self.toggle(entity);
+        } else {
+            self.set(entity);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entities.clear();
+    }
+}
+
+fn remove_despawned_from_selection(mut selection: ResMut<Selection>, q_entities: Query<()>) {
+    if selection
+        .entities
+        .iter()
+        .any(|entity| !q_entities.contains(*entity))
+    {
+        selection
+            .entities
+            .retain(|entity| q_entities.contains(*entity));
+    }
+}
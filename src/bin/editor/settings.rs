@@ -0,0 +1,340 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::Path,
+};
+
+use bevy::{app::AppExit, prelude::*, window::PrimaryWindow};
+use serde::{Deserialize, Serialize};
+use sickle_ui::prelude::*;
+use thiserror::Error;
+
+use crate::hotkeys::{read_legacy_setting, GizmoHotkey, GizmoHotkeys, LegacySettingsError};
+
+/// The settings file of the user, ignored by git as it changes on every exit.
+pub const SETTINGS_PATH: &str = "editor.user.ron";
+
+/// The tracked settings file of the previous space_editor based editor, migrated on
+/// the first launch when the user has no settings yet.
+pub const LEGACY_SETTINGS_PATH: &str = "editor.ron";
+
+/// The version of the settings written by this editor.
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Loads the [`EditorSettings`] unless they were inserted to create the window, applies
+/// them on startup and saves them on exit.
+///
+/// Added before the plugins reading the settings while they build.
+pub struct EditorSettingsPlugin;
+
+impl Plugin for EditorSettingsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world().contains_resource::<EditorSettings>() {
+            app.insert_resource(EditorSettings::load_user());
+        }
+
+        app.add_systems(Startup, apply_theme_settings)
+            .add_systems(
+                Update,
+                (track_window_geometry, track_theme).after(WidgetLibraryUpdate),
+            )
+            .add_systems(Last, save_settings.run_if(on_event::<AppExit>()));
+    }
+}
+
+/// The settings kept between editor launches.
+///
+/// Fields missing from the file keep their defaults, and unknown fields are ignored.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct EditorSettings {
+    pub version: u32,
+    pub theme: ThemeSettings,
+    pub window: WindowSettings,
+    /// The names of the keys bound to each hotkey, e.g. `KeyG`.
+    pub hotkeys: BTreeMap<GizmoHotkey, Vec<String>>,
+    pub history_size: usize,
+    /// The asset paths of the recently opened or saved scenes, the latest first.
+    pub recent_files: Vec<String>,
+    /// Whether the colliders of the scene are drawn in the scene view.
+    pub show_colliders: bool,
+}
+
+impl Default for EditorSettings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            theme: ThemeSettings::default(),
+            window: WindowSettings::default(),
+            hotkeys: GizmoHotkeys::default().key_names(),
+            history_size: 200,
+            recent_files: Vec::new(),
+            show_colliders: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SchemeSetting {
+    Light,
+    #[default]
+    Dark,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ContrastSetting {
+    #[default]
+    Standard,
+    Medium,
+    High,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ThemeSettings {
+    pub scheme: SchemeSetting,
+    pub contrast: ContrastSetting,
+}
+
+impl ThemeSettings {
+    pub fn scheme(&self) -> Scheme {
+        let contrast = match self.contrast {
+            ContrastSetting::Standard => Contrast::Standard,
+            ContrastSetting::Medium => Contrast::Medium,
+            ContrastSetting::High => Contrast::High,
+        };
+        match self.scheme {
+            SchemeSetting::Light => Scheme::Light(contrast),
+            SchemeSetting::Dark => Scheme::Dark(contrast),
+        }
+    }
+}
+
+impl From<Scheme> for ThemeSettings {
+    fn from(scheme: Scheme) -> Self {
+        let (scheme, contrast) = match scheme {
+            Scheme::Light(contrast) => (SchemeSetting::Light, contrast),
+            Scheme::Dark(contrast) => (SchemeSetting::Dark, contrast),
+        };
+        let contrast = match contrast {
+            Contrast::Standard => ContrastSetting::Standard,
+            Contrast::Medium => ContrastSetting::Medium,
+            Contrast::High => ContrastSetting::High,
+        };
+        Self { scheme, contrast }
+    }
+}
+
+/// The logical size and the position of the editor window.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct WindowSettings {
+    pub width: f32,
+    pub height: f32,
+    /// Left to the window manager when not set.
+    pub position: Option<(i32, i32)>,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            width: 1280.,
+            height: 720.,
+            position: None,
+        }
+    }
+}
+
+impl WindowSettings {
+    /// A window with the size and position of the settings.
+    pub fn window(&self) -> Window {
+        Window {
+            resolution: (self.width, self.height).into(),
+            position: match self.position {
+                Some((x, y)) => WindowPosition::At(IVec2::new(x, y)),
+                None => WindowPosition::Automatic,
+            },
+            ..default()
+        }
+    }
+}
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("Could not access the settings file: {0}")]
+    Io(#[from] io::Error),
+    #[error("Could not parse the settings: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("Could not serialize the settings: {0}")]
+    Serialize(#[from] ron::Error),
+    #[error("Could not migrate the legacy settings: {0}")]
+    Legacy(#[from] LegacySettingsError),
+}
+
+/// The history size setting of the previous space_editor based editor.
+#[derive(Deserialize)]
+struct ChangeChainSettings {
+    max_change_chain_size: usize,
+}
+
+impl EditorSettings {
+    /// Reads the settings, migrating those written by the previous editor.
+    pub fn load(path: &str) -> Result<Self, SettingsError> {
+        let text = fs::read_to_string(path)?;
+        // The previous editor wrote a map of type names to RON strings
+        if ron::from_str::<HashMap<String, String>>(&text).is_ok() {
+            return Self::migrate_legacy(path);
+        }
+
+        let settings: Self = ron::from_str(&text)?;
+        if settings.version > SETTINGS_VERSION {
+            warn!(
+                "{path} was written by a newer editor (version {}), unknown settings are ignored",
+                settings.version
+            );
+        }
+        Ok(settings)
+    }
+
+    /// Reads the settings of the user, or the legacy settings if the user has none yet.
+    pub fn load_user() -> Self {
+        if Path::new(SETTINGS_PATH).exists() {
+            Self::load_or_default(SETTINGS_PATH)
+        } else {
+            Self::load_or_default(LEGACY_SETTINGS_PATH)
+        }
+    }
+
+    /// Reads the settings, falling back to the defaults if there are none or they
+    /// can't be read.
+    pub fn load_or_default(path: &str) -> Self {
+        match Self::load(path) {
+            Ok(settings) => settings,
+            Err(SettingsError::Io(error)) if error.kind() == io::ErrorKind::NotFound => {
+                Self::default()
+            }
+            Err(error) => {
+                warn!("Using the default editor settings, couldn't read {path}: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Keeps the hotkeys and the history size of a space_editor settings file. Its
+    /// other settings have no equivalent in this editor and are dropped.
+    fn migrate_legacy(path: &str) -> Result<Self, SettingsError> {
+        let mut settings = Self {
+            hotkeys: GizmoHotkeys::load_legacy(path)?.key_names(),
+            ..default()
+        };
+        if let Some(change_chain) =
+            read_legacy_setting::<ChangeChainSettings>(path, "ChangeChainSettings")?
+        {
+            settings.history_size = change_chain.max_change_chain_size;
+        }
+
+        info!("Migrated the space_editor settings in {path}");
+        Ok(settings)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), SettingsError> {
+        let settings = Self {
+            version: SETTINGS_VERSION,
+            ..self.clone()
+        };
+        let text = ron::ser::to_string_pretty(&settings, default())?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    pub fn gizmo_hotkeys(&self) -> GizmoHotkeys {
+        GizmoHotkeys::from_key_names(&self.hotkeys)
+    }
+
+    /// Moves the path to the front of the recent files.
+    pub fn add_recent_file(&mut self, path: &str) {
+        const MAX_RECENT_FILES: usize = 10;
+
+        self.recent_files.retain(|recent| recent != path);
+        self.recent_files.insert(0, path.to_string());
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+}
+
+fn apply_theme_settings(settings: Res<EditorSettings>, mut theme_data: ResMut<ThemeData>) {
+    theme_data.active_scheme = settings.theme.scheme();
+}
+
+fn track_window_geometry(
+    mut settings: ResMut<EditorSettings>,
+    q_window: Query<&Window, (With<PrimaryWindow>, Changed<Window>)>,
+) {
+    let Ok(window) = q_window.get_single() else {
+        return;
+    };
+
+    let position = match window.position {
+        WindowPosition::At(position) => Some((position.x, position.y)),
+        _ => settings.window.position,
+    };
+    let geometry = WindowSettings {
+        width: window.width(),
+        height: window.height(),
+        position,
+    };
+    if settings.window != geometry {
+        settings.window = geometry;
+    }
+}
+
+fn track_theme(mut settings: ResMut<EditorSettings>, theme_data: Res<ThemeData>) {
+    if !theme_data.is_changed() {
+        return;
+    }
+
+    let theme = ThemeSettings::from(theme_data.active_scheme);
+    if settings.theme != theme {
+        settings.theme = theme;
+    }
+}
+
+fn save_settings(settings: Res<EditorSettings>) {
+    if let Err(error) = settings.save(SETTINGS_PATH) {
+        error!("Could not save the editor settings to {SETTINGS_PATH}: {error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The settings file written by the space_editor based editor.
+    const LEGACY_SETTINGS: &str = r#"{
+    "space_persistence::PersistenceSettings": "{\"space_persistence::PersistenceSettings\":(load_on_startup:true,save_on_close:true)}",
+    "space_editor_core::hotkeys::HotkeySet<space_editor_ui::tools::gizmo::GizmoHotkey>": "{\"space_editor_core::hotkeys::HotkeySet<space_editor_ui::tools::gizmo::GizmoHotkey>\":(bindings:{Translate:[KeyG],Delete:[KeyX],Multiple:[ShiftLeft],Clone:[AltLeft],Scale:[KeyS],Rotate:[KeyR]},name:\"GizmoHotkey\")}",
+    "space_editor_ui::settings::NewWindowSettings": "{\"space_editor_ui::settings::NewWindowSettings\":(new_tab:SameNode)}",
+    "space_editor_ui::settings::GameModeSettings": "{\"space_editor_ui::settings::GameModeSettings\":(mode:Game3D)}",
+    "space_undo::ChangeChainSettings": "{\"space_undo::ChangeChainSettings\":(max_change_chain_size:200)}",
+    "space_editor_ui::sizing::Sizing": "{\"space_editor_ui::sizing::Sizing\":(icon:Regular,gizmos:Gizmos,text:14.0)}",
+}
+"#;
+
+    #[test]
+    fn legacy_settings_keep_their_hotkeys_and_history_size() {
+        let path = std::env::temp_dir().join("artificer_legacy_editor.ron");
+        fs::write(&path, LEGACY_SETTINGS).unwrap();
+        let settings = EditorSettings::load(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let keys = |hotkey: GizmoHotkey| settings.hotkeys.get(&hotkey).cloned().unwrap_or_default();
+        assert_eq!(keys(GizmoHotkey::Translate), ["KeyG"]);
+        assert_eq!(keys(GizmoHotkey::Rotate), ["KeyR"]);
+        assert_eq!(keys(GizmoHotkey::Scale), ["KeyS"]);
+        assert_eq!(keys(GizmoHotkey::Delete), ["KeyX"]);
+        assert_eq!(keys(GizmoHotkey::Clone), ["AltLeft"]);
+        assert_eq!(keys(GizmoHotkey::Multiple), ["ShiftLeft"]);
+        assert_eq!(settings.history_size, 200);
+        assert_eq!(settings.version, SETTINGS_VERSION);
+    }
+}
@@ -1,10 +0,0 @@
-use avian3d::prelude::PhysicsLayer;
-
-#[derive(PhysicsLayer)]
-pub enum GameLayer {
-    Default,
-    Player,
-    Enemy,
-    Ground,
-    Projectile,
-}
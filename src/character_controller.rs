@@ -1,23 +1,31 @@
 use avian3d::{math::*, prelude::*};
 use bevy::prelude::*;
 
-use crate::MainCamera;
+use crate::{
+    app_state::AppState,
+    vfx::{SpawnVfx, VfxKind},
+    MainCamera,
+};
 
 pub struct CharacterControllerPlugin;
 
 impl Plugin for CharacterControllerPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<MovementAction>().add_systems(
-            Update,
-            (
-                keyboard_input,
-                gamepad_input,
-                update_grounded,
-                movement,
-                apply_movement_damping,
-            )
-                .chain(),
-        );
+        app.add_event::<MovementAction>()
+            .register_type::<CharacterController>()
+            .register_type::<Grounded>()
+            .add_systems(
+                Update,
+                (
+                    keyboard_input,
+                    gamepad_input,
+                    update_grounded,
+                    movement,
+                    apply_movement_damping,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
     }
 }
 
@@ -29,12 +37,14 @@ pub enum MovementAction {
 }
 
 /// A marker component indicating that an entity is using a character controller.
-#[derive(Component)]
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
 pub struct CharacterController;
 
 /// A marker component indicating that an entity is on the ground.
-#[derive(Component)]
+#[derive(Component, Reflect)]
 #[component(storage = "SparseSet")]
+#[reflect(Component, Default)]
 pub struct Grounded {
     coyote_timer: f32,
     pub coyote_time: f32,
@@ -209,12 +219,19 @@ fn gamepad_input(
 /// Updates the [`Grounded`] status for character controllers.
 fn update_grounded(
     mut query: Query<
-        (&ShapeHits, &Rotation, &mut Grounded, Option<&MaxSlopeAngle>),
+        (
+            &ShapeHits,
+            &Rotation,
+            &ColliderAabb,
+            &mut Grounded,
+            Option<&MaxSlopeAngle>,
+        ),
         With<CharacterController>,
     >,
     time: Res<Time>,
+    mut vfx_events: EventWriter<SpawnVfx>,
 ) {
-    for (hits, rotation, mut grounded, max_slope_angle) in &mut query {
+    for (hits, rotation, aabb, mut grounded, max_slope_angle) in &mut query {
         // The character is grounded if the shape caster has a hit with a normal
         // that isn't too steep.
         let is_grounded = hits.iter().any(|hit| {
@@ -230,6 +247,12 @@ fn update_grounded(
         });
 
         if is_grounded {
+            // Only kick up dust when landing after a fall longer than the coyote time
+            if !grounded.grounded && grounded.coyote_timer <= 0. {
+                let center = (aabb.min + aabb.max) / 2.;
+                let feet = Vec3::new(center.x, aabb.min.y, center.z);
+                vfx_events.send(SpawnVfx::new(VfxKind::LandingDust, feet));
+            }
             grounded.coyote_timer = grounded.coyote_time;
             grounded.grounded = true;
         } else {
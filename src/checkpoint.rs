@@ -0,0 +1,227 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    app_state::AppState,
+    gltf_extras::{ExtrasAppExt, PlayerSpawn},
+    level::Level,
+    trigger::CheckpointReached,
+    CharacterController, Grounded,
+};
+
+pub struct CheckpointPlugin;
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Respawned>()
+            .init_resource::<KillPlane>()
+            .init_resource::<ActiveCheckpoint>()
+            .register_type::<KillPlane>()
+            .register_type::<ActiveCheckpoint>()
+            .register_extras_converter("KillPlane", |entity, value| {
+                let kill_plane: KillPlane = ron::from_str(value)?;
+                entity.commands().insert_resource(kill_plane);
+                Ok(())
+            })
+            .observe(reset_kill_plane)
+            .add_systems(
+                Update,
+                (track_checkpoints, respawn_below_kill_plane)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// The height below which the player is respawned at the [`ActiveCheckpoint`].
+///
+/// Levels can override it with a glTF custom property, e.g. `KillPlane: (height: -50.0)`,
+/// which lasts until the next level is spawned.
+#[derive(Resource, Reflect, Deserialize, Clone, Copy, Debug)]
+#[reflect(Resource)]
+pub struct KillPlane {
+    pub height: f32,
+}
+
+impl Default for KillPlane {
+    fn default() -> Self {
+        Self { height: -20.0 }
+    }
+}
+
+/// Where the player respawns, the level's [`PlayerSpawn`] until a checkpoint is reached.
+#[derive(Resource, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Resource)]
+pub struct ActiveCheckpoint {
+    pub translation: Option<Vec3>,
+}
+
+impl ActiveCheckpoint {
+    /// The respawn position, above the origin when the level has no spawn point.
+    pub fn respawn_translation(&self) -> Vec3 {
+        self.translation.unwrap_or(Vec3::Y)
+    }
+}
+
+/// An event sent when an entity is respawned at the [`ActiveCheckpoint`].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Respawned {
+    pub entity: Entity,
+    pub translation: Vec3,
+}
+
+/// Drops the [`KillPlane`] override of the previous level as soon as a new one is spawned,
+/// before its glTF properties are converted.
+fn reset_kill_plane(_trigger: Trigger<OnAdd, Level>, mut kill_plane: ResMut<KillPlane>) {
+    *kill_plane = KillPlane::default();
+}
+
+/// Moves the [`ActiveCheckpoint`] to new spawn points and reached checkpoints.
+fn track_checkpoints(
+    mut checkpoint_events: EventReader<CheckpointReached>,
+    mut active_checkpoint: ResMut<ActiveCheckpoint>,
+    q_spawns: Query<&GlobalTransform, Added<PlayerSpawn>>,
+) {
+    if let Some(spawn) = q_spawns.iter().last() {
+        active_checkpoint.translation = Some(spawn.translation());
+    }
+
+    for event in checkpoint_events.read() {
+        active_checkpoint.translation = Some(event.translation);
+    }
+}
+
+/// Respawns characters that fell below the [`KillPlane`].
+fn respawn_below_kill_plane(
+    kill_plane: Res<KillPlane>,
+    active_checkpoint: Res<ActiveCheckpoint>,
+    mut respawned_events: EventWriter<Respawned>,
+    mut q_characters: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut LinearVelocity,
+            Option<&mut Grounded>,
+        ),
+        With<CharacterController>,
+    >,
+) {
+    for (entity, mut transform, mut linear_velocity, grounded) in &mut q_characters {
+        if transform.translation.y >= kill_plane.height {
+            continue;
+        }
+
+        let translation = active_checkpoint.respawn_translation();
+        transform.translation = translation;
+        linear_velocity.0 = Vec3::ZERO;
+        if let Some(mut grounded) = grounded {
+            *grounded = Grounded::default();
+        }
+
+        respawned_events.send(Respawned {
+            entity,
+            translation,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{
+        gltf::GltfExtras, scene::ScenePlugin, state::app::StatesPlugin, time::TimeUpdateStrategy,
+    };
+
+    use super::*;
+    use crate::gltf_extras::GltfExtrasPlugin;
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            StatesPlugin,
+            AssetPlugin::default(),
+            ScenePlugin,
+            TransformPlugin,
+            HierarchyPlugin,
+            PhysicsPlugins::default(),
+        ))
+        .init_asset::<Mesh>()
+        .init_state::<AppState>()
+        .add_event::<CheckpointReached>()
+        .add_plugins((GltfExtrasPlugin, CheckpointPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(
+            1.0 / 60.0,
+        )));
+
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::InGame);
+        app.update();
+        app
+    }
+
+    #[test]
+    fn player_falling_off_the_world_respawns_at_the_checkpoint() {
+        let mut app = test_app();
+        app.insert_resource(KillPlane { height: -5.0 });
+
+        // Nothing below the player to land on
+        let player = app
+            .world_mut()
+            .spawn((
+                CharacterController,
+                RigidBody::Dynamic,
+                Collider::capsule(1.0, 0.4),
+                Grounded::default(),
+                TransformBundle::from_transform(Transform::from_xyz(0.0, 10.0, 0.0)),
+            ))
+            .id();
+
+        let checkpoint = Vec3::new(3.0, 2.0, 1.0);
+        app.world_mut().send_event(CheckpointReached {
+            checkpoint: Entity::PLACEHOLDER,
+            translation: checkpoint,
+        });
+
+        let respawned = (0..600).find_map(|_| {
+            app.update();
+            let events = app.world().resource::<Events<Respawned>>();
+            events.get_reader().read(events).next().copied()
+        });
+
+        let respawned = respawned.expect("the player never fell below the kill plane");
+        assert_eq!(respawned.entity, player);
+        assert_eq!(respawned.translation, checkpoint);
+
+        // Physics may have stepped once since the respawn
+        let transform = app.world().get::<Transform>(player).unwrap();
+        assert!(transform.translation.distance(checkpoint) < 0.1);
+        let linear_velocity = app.world().get::<LinearVelocity>(player).unwrap();
+        assert!(linear_velocity.length() < 1.0);
+    }
+
+    #[test]
+    fn kill_plane_overrides_last_until_the_next_level() {
+        let mut app = test_app();
+
+        let level = app.world_mut().spawn(Level::default()).id();
+        app.world_mut()
+            .spawn(GltfExtras {
+                value: r#"{"KillPlane":"(height: -50.0)"}"#.into(),
+            })
+            .set_parent(level);
+        app.update();
+        app.update();
+        assert_eq!(app.world().resource::<KillPlane>().height, -50.0);
+
+        app.world_mut().entity_mut(level).despawn_recursive();
+        app.world_mut().spawn(Level::default());
+        assert_eq!(
+            app.world().resource::<KillPlane>().height,
+            KillPlane::default().height
+        );
+    }
+}
@@ -0,0 +1,336 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+use bevy_hanabi::prelude::*;
+use serde::Deserialize;
+use thiserror::Error;
+
+/// A particle effect described in RON, see `assets/effects/*.effect.ron`.
+///
+/// Shapes and directions are in the local space of the effect, particles are
+/// emitted along its local Y axis by a `Directional` velocity.
+#[derive(Asset, TypePath, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct EffectDef {
+    /// The maximum number of particles alive at a time.
+    pub capacity: u32,
+    pub spawner: SpawnerDef,
+    pub simulation_space: SimulationSpaceDef,
+    pub position: PositionDef,
+    pub velocity: Option<VelocityDef>,
+    /// The lifetime of the particles, in seconds.
+    pub lifetime: ValueDef,
+    /// The size of the particles, unless they have a `size_over_lifetime`.
+    pub size: f32,
+    pub acceleration: (f32, f32, f32),
+    pub drag: f32,
+    /// The keys of the linear RGBA color gradient over the lifetime of the particles.
+    pub color_over_lifetime: Vec<(f32, (f32, f32, f32, f32))>,
+    /// The keys of the size gradient over the lifetime of the particles.
+    pub size_over_lifetime: Vec<(f32, f32)>,
+}
+
+impl Default for EffectDef {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            spawner: SpawnerDef::Once(16.0),
+            simulation_space: SimulationSpaceDef::Global,
+            position: PositionDef::Point((0.0, 0.0, 0.0)),
+            velocity: None,
+            lifetime: ValueDef::Constant(1.0),
+            size: 0.05,
+            acceleration: (0.0, 0.0, 0.0),
+            drag: 0.0,
+            color_over_lifetime: vec![],
+            size_over_lifetime: vec![],
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum SpawnerDef {
+    /// Spawns this many particles each time the effect is reset.
+    Once(f32),
+    /// Spawns this many particles per second.
+    Rate(f32),
+    /// Spawns `count` particles every `period` seconds.
+    Burst { count: f32, period: f32 },
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum SimulationSpaceDef {
+    /// Particles move with the effect.
+    Local,
+    /// Particles stay where they were emitted when the effect moves.
+    Global,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+pub enum DimensionDef {
+    #[default]
+    Volume,
+    Surface,
+}
+
+impl From<DimensionDef> for ShapeDimension {
+    fn from(dimension: DimensionDef) -> Self {
+        match dimension {
+            DimensionDef::Volume => ShapeDimension::Volume,
+            DimensionDef::Surface => ShapeDimension::Surface,
+        }
+    }
+}
+
+/// A constant or a uniformly random value.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum ValueDef {
+    Constant(f32),
+    Uniform(f32, f32),
+}
+
+impl ValueDef {
+    fn expr(&self, writer: &ExprWriter) -> WriterExpr {
+        match *self {
+            ValueDef::Constant(value) => writer.lit(value),
+            ValueDef::Uniform(min, max) => writer.lit(min).uniform(writer.lit(max)),
+        }
+    }
+}
+
+/// Where the particles are spawned.
+#[derive(Deserialize, Clone, Debug)]
+pub enum PositionDef {
+    Point((f32, f32, f32)),
+    Sphere {
+        center: (f32, f32, f32),
+        radius: f32,
+        #[serde(default)]
+        dimension: DimensionDef,
+    },
+    Circle {
+        center: (f32, f32, f32),
+        axis: (f32, f32, f32),
+        radius: f32,
+        #[serde(default)]
+        dimension: DimensionDef,
+    },
+    /// A truncated cone along the Y axis.
+    Cone {
+        height: f32,
+        base_radius: f32,
+        top_radius: f32,
+        #[serde(default)]
+        dimension: DimensionDef,
+    },
+}
+
+/// The initial velocity of the particles.
+#[derive(Deserialize, Clone, Debug)]
+pub enum VelocityDef {
+    /// Away from `center`.
+    Sphere {
+        center: (f32, f32, f32),
+        speed: ValueDef,
+    },
+    /// Around `axis`, tangent to a circle centered on `origin`.
+    Tangent {
+        origin: (f32, f32, f32),
+        axis: (f32, f32, f32),
+        speed: ValueDef,
+    },
+    /// Along the Y axis, straying from it more with a larger `spread`.
+    Directional { spread: f32, speed: ValueDef },
+}
+
+/// Adds a modifier to an effect once its expression module is complete.
+type AddModifier = Box<dyn FnOnce(EffectAsset) -> EffectAsset>;
+
+impl EffectDef {
+    /// Builds the Hanabi effect. `Once` spawners wait for their [`EffectSpawner`] to be reset.
+    pub fn effect(&self, name: &str) -> EffectAsset {
+        let writer = ExprWriter::new();
+        let vec3 = |(x, y, z): (f32, f32, f32)| writer.lit(Vec3::new(x, y, z)).expr();
+
+        // The module is only complete once every expression has been written, so
+        // the modifiers are added to the effect afterwards.
+        let mut modifiers: Vec<AddModifier> = vec![];
+
+        match self.position {
+            PositionDef::Point(position) => {
+                let init_pos = SetAttributeModifier::new(Attribute::POSITION, vec3(position));
+                modifiers.push(Box::new(move |effect| effect.init(init_pos)));
+            }
+            PositionDef::Sphere {
+                center,
+                radius,
+                dimension,
+            } => {
+                let init_pos = SetPositionSphereModifier {
+                    center: vec3(center),
+                    radius: writer.lit(radius).expr(),
+                    dimension: dimension.into(),
+                };
+                modifiers.push(Box::new(move |effect| effect.init(init_pos)));
+            }
+            PositionDef::Circle {
+                center,
+                axis,
+                radius,
+                dimension,
+            } => {
+                let init_pos = SetPositionCircleModifier {
+                    center: vec3(center),
+                    axis: vec3(axis),
+                    radius: writer.lit(radius).expr(),
+                    dimension: dimension.into(),
+                };
+                modifiers.push(Box::new(move |effect| effect.init(init_pos)));
+            }
+            PositionDef::Cone {
+                height,
+                base_radius,
+                top_radius,
+                dimension,
+            } => {
+                let init_pos = SetPositionCone3dModifier {
+                    height: writer.lit(height).expr(),
+                    base_radius: writer.lit(base_radius).expr(),
+                    top_radius: writer.lit(top_radius).expr(),
+                    dimension: dimension.into(),
+                };
+                modifiers.push(Box::new(move |effect| effect.init(init_pos)));
+            }
+        }
+
+        match &self.velocity {
+            None => {}
+            Some(VelocityDef::Sphere { center, speed }) => {
+                let init_vel = SetVelocitySphereModifier {
+                    center: vec3(*center),
+                    speed: speed.expr(&writer).expr(),
+                };
+                modifiers.push(Box::new(move |effect| effect.init(init_vel)));
+            }
+            Some(VelocityDef::Tangent {
+                origin,
+                axis,
+                speed,
+            }) => {
+                let init_vel = SetVelocityTangentModifier {
+                    origin: vec3(*origin),
+                    axis: vec3(*axis),
+                    speed: speed.expr(&writer).expr(),
+                };
+                modifiers.push(Box::new(move |effect| effect.init(init_vel)));
+            }
+            Some(VelocityDef::Directional { spread, speed }) => {
+                // Random direction around the Y axis, wider with the spread
+                let direction = ((writer.rand(VectorType::VEC3F) * writer.lit(2.)
+                    - writer.lit(1.))
+                    * writer.lit(*spread)
+                    + writer.lit(Vec3::Y))
+                .normalized();
+                let velocity = direction * speed.expr(&writer);
+                let init_vel = SetAttributeModifier::new(Attribute::VELOCITY, velocity.expr());
+                modifiers.push(Box::new(move |effect| effect.init(init_vel)));
+            }
+        }
+
+        let init_age = SetAttributeModifier::new(Attribute::AGE, writer.lit(0.).expr());
+        let init_lifetime =
+            SetAttributeModifier::new(Attribute::LIFETIME, self.lifetime.expr(&writer).expr());
+        modifiers.push(Box::new(move |effect| {
+            effect.init(init_age).init(init_lifetime)
+        }));
+
+        if self.size_over_lifetime.is_empty() {
+            let init_size =
+                SetAttributeModifier::new(Attribute::SIZE, writer.lit(self.size).expr());
+            modifiers.push(Box::new(move |effect| effect.init(init_size)));
+        } else {
+            let mut gradient = Gradient::new();
+            for (ratio, size) in &self.size_over_lifetime {
+                gradient.add_key(*ratio, Vec2::splat(*size));
+            }
+            modifiers.push(Box::new(move |effect| {
+                effect.render(SizeOverLifetimeModifier {
+                    gradient,
+                    screen_space_size: false,
+                })
+            }));
+        }
+
+        if self.acceleration != (0.0, 0.0, 0.0) {
+            let update_accel = AccelModifier::new(vec3(self.acceleration));
+            modifiers.push(Box::new(move |effect| effect.update(update_accel)));
+        }
+        if self.drag > 0.0 {
+            let update_drag = LinearDragModifier::new(writer.lit(self.drag).expr());
+            modifiers.push(Box::new(move |effect| effect.update(update_drag)));
+        }
+
+        if !self.color_over_lifetime.is_empty() {
+            let mut gradient = Gradient::new();
+            for (ratio, (red, green, blue, alpha)) in &self.color_over_lifetime {
+                gradient.add_key(*ratio, Vec4::new(*red, *green, *blue, *alpha));
+            }
+            modifiers.push(Box::new(move |effect| {
+                effect.render(ColorOverLifetimeModifier { gradient })
+            }));
+        }
+
+        let spawner = match &self.spawner {
+            SpawnerDef::Once(count) => Spawner::once((*count).into(), false),
+            SpawnerDef::Rate(rate) => Spawner::rate((*rate).into()),
+            SpawnerDef::Burst { count, period } => {
+                Spawner::burst((*count).into(), (*period).into())
+            }
+        };
+
+        let effect = EffectAsset::new(vec![self.capacity], spawner, writer.finish())
+            .with_name(name)
+            .with_simulation_space(match self.simulation_space {
+                SimulationSpaceDef::Local => SimulationSpace::Local,
+                SimulationSpaceDef::Global => SimulationSpace::Global,
+            });
+
+        modifiers
+            .into_iter()
+            .fold(effect, |effect, modifier| modifier(effect))
+    }
+}
+
+#[derive(Default)]
+pub struct EffectDefLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum EffectDefLoaderError {
+    #[error("Could not read the effect: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse the effect: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for EffectDefLoader {
+    type Asset = EffectDef;
+    type Settings = ();
+    type Error = EffectDefLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(ron::de::from_bytes(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["effect.ron"]
+    }
+}
@@ -0,0 +1,91 @@
+use avian3d::prelude::PhysicsLayer;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::vfx::{SpawnVfx, VfxKind};
+
+pub struct GameManagementPlugin;
+
+impl Plugin for GameManagementPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DamageEvent>()
+            .register_type::<Health>()
+            .register_type::<Team>()
+            .add_systems(Update, apply_damage);
+    }
+}
+
+#[derive(PhysicsLayer, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameLayer {
+    Default,
+    Player,
+    Enemy,
+    Ground,
+    Projectile,
+}
+
+impl GameLayer {
+    pub const ALL: [Self; 5] = [
+        Self::Default,
+        Self::Player,
+        Self::Enemy,
+        Self::Ground,
+        Self::Projectile,
+    ];
+}
+
+/// The hit points of an entity.
+#[derive(Component, Reflect, Debug, Clone, Copy)]
+#[reflect(Component, Default)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            current: 100.0,
+            max: 100.0,
+        }
+    }
+}
+
+/// The team an entity belongs to, also selecting the team color of its library material.
+#[derive(Component, Reflect, Debug, Clone, Default, PartialEq, Eq)]
+#[reflect(Component, Default)]
+pub struct Team(pub String);
+
+impl Team {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+}
+
+/// An event sent when an entity takes damage.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: f32,
+    /// Where the damage came from, if it has a source in the world.
+    pub source: Option<Vec3>,
+}
+
+/// Applies [`DamageEvent`]s to the [`Health`] of their targets.
+fn apply_damage(
+    mut damage_events: EventReader<DamageEvent>,
+    mut vfx_events: EventWriter<SpawnVfx>,
+    mut query: Query<(&mut Health, Option<&GlobalTransform>)>,
+) {
+    for event in damage_events.read() {
+        if let Ok((mut health, transform)) = query.get_mut(event.target) {
+            let was_alive = health.current > 0.0;
+            health.current = (health.current - event.amount).max(0.0);
+
+            let died = was_alive && health.current <= 0.0;
+            if let Some(transform) = transform.filter(|_| died) {
+                vfx_events.send(SpawnVfx::new(VfxKind::DeathBurst, transform.translation()));
+            }
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::sync::Arc;
+
+use avian3d::prelude::LinearVelocity;
+use bevy::{ecs::system::EntityCommands, gltf::GltfExtras, prelude::*, utils::HashMap};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+use crate::CharacterController;
+
+pub struct GltfExtrasPlugin;
+
+impl Plugin for GltfExtrasPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ExtrasRegistry>()
+            .register_extras_component::<PlayerSpawn>("PlayerSpawn")
+            .register_extras_component::<EnemySpawn>("EnemySpawn")
+            .register_extras_component::<LightOverride>("LightOverride")
+            .add_systems(
+                Update,
+                (
+                    apply_gltf_extras,
+                    place_player_at_spawn,
+                    apply_light_overrides,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Converts the RON value of a glTF custom property into components on the node entity.
+pub type ExtrasConverter =
+    Arc<dyn Fn(&mut EntityCommands, &str) -> ron::error::SpannedResult<()> + Send + Sync>;
+
+/// Converters for glTF node extras, keyed by custom property name.
+///
+/// Properties are authored in Blender with the property name as key and a RON
+/// string as value, e.g. `EnemySpawn: (kind: "grunt")`.
+#[derive(Resource, Default)]
+pub struct ExtrasRegistry {
+    converters: HashMap<String, ExtrasConverter>,
+}
+
+impl ExtrasRegistry {
+    pub fn register(&mut self, name: impl Into<String>, converter: ExtrasConverter) -> &mut Self {
+        self.converters.insert(name.into(), converter);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ExtrasConverter> {
+        self.converters.get(name)
+    }
+}
+
+pub trait ExtrasAppExt {
+    /// Registers a converter that deserializes the property value straight into a component.
+    fn register_extras_component<T: Component + DeserializeOwned>(
+        &mut self,
+        name: &str,
+    ) -> &mut Self;
+
+    /// Registers a custom converter for the given property name.
+    fn register_extras_converter(
+        &mut self,
+        name: &str,
+        converter: impl Fn(&mut EntityCommands, &str) -> ron::error::SpannedResult<()>
+            + Send
+            + Sync
+            + 'static,
+    ) -> &mut Self;
+}
+
+impl ExtrasAppExt for App {
+    fn register_extras_component<T: Component + DeserializeOwned>(
+        &mut self,
+        name: &str,
+    ) -> &mut Self {
+        self.register_extras_converter(name, |entity, value| {
+            entity.insert(ron::from_str::<T>(value)?);
+            Ok(())
+        })
+    }
+
+    fn register_extras_converter(
+        &mut self,
+        name: &str,
+        converter: impl Fn(&mut EntityCommands, &str) -> ron::error::SpannedResult<()>
+            + Send
+            + Sync
+            + 'static,
+    ) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_insert_with(ExtrasRegistry::default)
+            .register(name, Arc::new(converter));
+        self
+    }
+}
+
+/// Where the player is placed when a level is loaded.
+#[derive(Component, Deserialize, Debug, Default, Clone)]
+pub struct PlayerSpawn;
+
+/// Where an enemy of the given kind is spawned.
+#[derive(Component, Deserialize, Debug, Clone)]
+pub struct EnemySpawn {
+    pub kind: String,
+}
+
+/// Overrides the properties of the lights exported with a node.
+#[derive(Component, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct LightOverride {
+    pub color: Option<(f32, f32, f32)>,
+    pub intensity: Option<f32>,
+    pub range: Option<f32>,
+    pub shadows_enabled: Option<bool>,
+}
+
+/// Runs the registered converters for every property of newly spawned glTF extras.
+fn apply_gltf_extras(
+    mut commands: Commands,
+    registry: Res<ExtrasRegistry>,
+    q_extras: Query<(Entity, &GltfExtras, Option<&Name>), Added<GltfExtras>>,
+) {
+    for (entity, extras, name) in &q_extras {
+        let Ok(Value::Object(properties)) = serde_json::from_str(&extras.value) else {
+            warn!("Invalid glTF extras on {name:?}: {}", extras.value);
+            continue;
+        };
+
+        for (property, value) in properties {
+            let Some(converter) = registry.get(&property) else {
+                debug!("No converter registered for glTF property \"{property}\"");
+                continue;
+            };
+
+            // Blender exports custom properties as strings, anything else is passed as JSON
+            let value = match value {
+                Value::String(value) => value,
+                value => value.to_string(),
+            };
+
+            if let Err(error) = converter(&mut commands.entity(entity), &value) {
+                warn!("Failed to convert glTF property \"{property}\" on {name:?}: {error}");
+            }
+        }
+    }
+}
+
+/// Moves the player to the most recently spawned [`PlayerSpawn`].
+fn place_player_at_spawn(
+    q_spawns: Query<&GlobalTransform, Added<PlayerSpawn>>,
+    mut q_player: Query<(&mut Transform, &mut LinearVelocity), With<CharacterController>>,
+) {
+    let Some(spawn) = q_spawns.iter().last() else {
+        return;
+    };
+
+    for (mut transform, mut linear_velocity) in &mut q_player {
+        transform.translation = spawn.translation();
+        linear_velocity.0 = Vec3::ZERO;
+    }
+}
+
+/// Applies [`LightOverride`]s to the lights of the node and its children.
+fn apply_light_overrides(
+    q_overrides: Query<(Entity, &LightOverride), Added<LightOverride>>,
+    q_children: Query<&Children>,
+    mut q_point_lights: Query<&mut PointLight>,
+    mut q_spot_lights: Query<&mut SpotLight>,
+    mut q_directional_lights: Query<&mut DirectionalLight>,
+) {
+    for (entity, light_override) in &q_overrides {
+        let color = light_override.color.map(|(r, g, b)| Color::srgb(r, g, b));
+
+        for light in std::iter::once(entity).chain(q_children.iter_descendants(entity)) {
+            if let Ok(mut point_light) = q_point_lights.get_mut(light) {
+                if let Some(color) = color {
+                    point_light.color = color;
+                }
+                if let Some(intensity) = light_override.intensity {
+                    point_light.intensity = intensity;
+                }
+                if let Some(range) = light_override.range {
+                    point_light.range = range;
+                }
+                if let Some(shadows_enabled) = light_override.shadows_enabled {
+                    point_light.shadows_enabled = shadows_enabled;
+                }
+            }
+
+            if let Ok(mut spot_light) = q_spot_lights.get_mut(light) {
+                if let Some(color) = color {
+                    spot_light.color = color;
+                }
+                if let Some(intensity) = light_override.intensity {
+                    spot_light.intensity = intensity;
+                }
+                if let Some(range) = light_override.range {
+                    spot_light.range = range;
+                }
+                if let Some(shadows_enabled) = light_override.shadows_enabled {
+                    spot_light.shadows_enabled = shadows_enabled;
+                }
+            }
+
+            if let Ok(mut directional_light) = q_directional_lights.get_mut(light) {
+                if let Some(color) = color {
+                    directional_light.color = color;
+                }
+                if let Some(intensity) = light_override.intensity {
+                    directional_light.illuminance = intensity;
+                }
+                if let Some(shadows_enabled) = light_override.shadows_enabled {
+                    directional_light.shadows_enabled = shadows_enabled;
+                }
+            }
+        }
+    }
+}
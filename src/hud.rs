@@ -0,0 +1,351 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use sickle_ui::prelude::*;
+
+use crate::{
+    app_state::Gameplay,
+    game_management::{DamageEvent, GameLayer, Health},
+    projectile::Weapon,
+    CharacterController, MainCamera,
+};
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HudColors>()
+            .add_systems(OnEnter(Gameplay), spawn_hud)
+            .add_systems(
+                Update,
+                (
+                    update_hud_colors,
+                    exclude_player_from_crosshair,
+                    update_crosshair,
+                    update_health_bar,
+                    update_weapon_indicator,
+                    spawn_damage_feedback,
+                    update_damage_indicators,
+                    update_damage_numbers,
+                )
+                    .chain()
+                    .run_if(in_state(Gameplay)),
+            );
+    }
+}
+
+/// How long damage direction indicators and damage numbers stay on screen, in seconds.
+const DAMAGE_FEEDBACK_DURATION: f32 = 1.0;
+
+/// HUD colors derived from the active sickle_ui [`ThemeData`].
+#[derive(Resource, Default)]
+struct HudColors {
+    crosshair: Color,
+    crosshair_target: Color,
+    bar_background: Color,
+    health: Color,
+    cooldown: Color,
+    damage: Color,
+}
+
+#[derive(Component)]
+struct Crosshair;
+
+#[derive(Component)]
+struct HealthBarFill;
+
+#[derive(Component)]
+struct AmmoLabel;
+
+#[derive(Component)]
+struct CooldownBarFill;
+
+#[derive(Component)]
+struct DamageIndicator {
+    timer: Timer,
+}
+
+#[derive(Component)]
+struct DamageNumber {
+    world_position: Vec3,
+    timer: Timer,
+}
+
+fn update_hud_colors(theme_data: Res<ThemeData>, mut hud_colors: ResMut<HudColors>) {
+    if !theme_data.is_changed() {
+        return;
+    }
+
+    let colors = theme_data.colors();
+    *hud_colors = HudColors {
+        crosshair: colors.on(On::Surface),
+        crosshair_target: colors.accent(Accent::Error),
+        bar_background: colors.container(Container::SurfaceMid),
+        health: colors.accent(Accent::Error),
+        cooldown: colors.accent(Accent::Primary),
+        damage: colors.accent(Accent::Error),
+    };
+}
+
+fn spawn_hud(mut commands: Commands, theme_data: Res<ThemeData>) {
+    let spacing = theme_data.spacing;
+
+    commands
+        .ui_builder(UiRoot)
+        .container(
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.),
+                    height: Val::Percent(100.),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+            |hud| {
+                hud.spawn((NodeBundle::default(), Crosshair))
+                    .style()
+                    .size(Val::Px(6.));
+
+                hud.column(|bars| {
+                    bars.container(NodeBundle::default(), |background| {
+                        background
+                            .spawn((NodeBundle::default(), HealthBarFill))
+                            .style()
+                            .height(Val::Percent(100.));
+                    })
+                    .style()
+                    .width(Val::Px(200.))
+                    .height(Val::Px(spacing.gaps.medium));
+
+                    bars.spawn((TextBundle::default(), AmmoLabel));
+
+                    bars.container(NodeBundle::default(), |background| {
+                        background
+                            .spawn((NodeBundle::default(), CooldownBarFill))
+                            .style()
+                            .height(Val::Percent(100.));
+                    })
+                    .style()
+                    .width(Val::Px(200.))
+                    .height(Val::Px(spacing.gaps.small));
+                })
+                .style()
+                .position_type(PositionType::Absolute)
+                .left(Val::Px(spacing.gaps.large))
+                .bottom(Val::Px(spacing.gaps.large))
+                .row_gap(Val::Px(spacing.gaps.small));
+            },
+        )
+        .insert((Name::new("HUD"), StateScoped(Gameplay)));
+}
+
+/// Keeps the player's own collider out of the crosshair ray of the camera.
+fn exclude_player_from_crosshair(
+    q_player: Query<Entity, With<CharacterController>>,
+    mut q_camera: Query<&mut RayCaster, With<MainCamera>>,
+) {
+    let Ok(player) = q_player.get_single() else {
+        return;
+    };
+
+    for mut ray_caster in &mut q_camera {
+        if !ray_caster.query_filter.excluded_entities.contains(&player) {
+            ray_caster.query_filter.excluded_entities.insert(player);
+        }
+    }
+}
+
+/// Tints the crosshair when the camera ray is aimed at an enemy.
+fn update_crosshair(
+    hud_colors: Res<HudColors>,
+    q_camera: Query<&RayHits, With<MainCamera>>,
+    q_layers: Query<&CollisionLayers>,
+    mut q_crosshair: Query<&mut BackgroundColor, With<Crosshair>>,
+) {
+    let on_enemy = q_camera
+        .get_single()
+        .ok()
+        .and_then(|hits| hits.iter_sorted().next())
+        .and_then(|hit| q_layers.get(hit.entity).ok())
+        .is_some_and(|layers| layers.memberships.has_all(GameLayer::Enemy));
+
+    for mut background in &mut q_crosshair {
+        background.0 = if on_enemy {
+            hud_colors.crosshair_target
+        } else {
+            hud_colors.crosshair
+        };
+    }
+}
+
+fn update_health_bar(
+    hud_colors: Res<HudColors>,
+    q_player: Query<&Health, With<CharacterController>>,
+    mut q_fill: Query<(&mut Style, &mut BackgroundColor, &Parent), With<HealthBarFill>>,
+    mut q_background: Query<&mut BackgroundColor, Without<HealthBarFill>>,
+) {
+    let Ok(health) = q_player.get_single() else {
+        return;
+    };
+
+    for (mut style, mut color, parent) in &mut q_fill {
+        style.width = Val::Percent(100. * health.current / health.max);
+        color.0 = hud_colors.health;
+        if let Ok(mut background) = q_background.get_mut(parent.get()) {
+            background.0 = hud_colors.bar_background;
+        }
+    }
+}
+
+fn update_weapon_indicator(
+    hud_colors: Res<HudColors>,
+    q_player: Query<Ref<Weapon>, With<CharacterController>>,
+    mut q_label: Query<(&mut Text, Ref<AmmoLabel>)>,
+    mut q_fill: Query<(&mut Style, &mut BackgroundColor), With<CooldownBarFill>>,
+) {
+    let Ok(weapon) = q_player.get_single() else {
+        return;
+    };
+
+    for (mut text, label) in &mut q_label {
+        if !(weapon.is_changed() || hud_colors.is_changed() || label.is_added()) {
+            continue;
+        }
+
+        // The cooldown changes the weapon every frame, the ammo only when shooting
+        let ammo = format!("{} / {}", weapon.ammo, weapon.max_ammo);
+        let unchanged = text.sections.first().is_some_and(|section| {
+            section.value == ammo && section.style.color == hud_colors.crosshair
+        });
+        if !unchanged {
+            *text = Text::from_section(
+                ammo,
+                TextStyle {
+                    color: hud_colors.crosshair,
+                    ..default()
+                },
+            );
+        }
+    }
+
+    for (mut style, mut color) in &mut q_fill {
+        style.width = Val::Percent(100. * weapon.cooldown.fraction());
+        color.0 = hud_colors.cooldown;
+    }
+}
+
+/// Spawns a direction indicator when the player is hurt and a floating number
+/// when anything else is.
+fn spawn_damage_feedback(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    hud_colors: Res<HudColors>,
+    q_player: Query<(Entity, &Transform), With<CharacterController>>,
+    q_camera: Query<&Transform, With<MainCamera>>,
+    q_targets: Query<&GlobalTransform>,
+) {
+    let Ok((player, player_transform)) = q_player.get_single() else {
+        return;
+    };
+
+    for event in damage_events.read() {
+        if event.target == player {
+            let (Some(source), Ok(camera)) = (event.source, q_camera.get_single()) else {
+                continue;
+            };
+
+            // Angle of the source around the screen center, 0 being straight ahead
+            let to_source = source - player_transform.translation;
+            let angle = to_source
+                .dot(*camera.right())
+                .atan2(to_source.dot(*camera.forward()));
+
+            commands.spawn((
+                NodeBundle {
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        left: Val::Percent(50. + 30. * angle.sin()),
+                        top: Val::Percent(50. - 30. * angle.cos()),
+                        width: Val::Px(24.),
+                        height: Val::Px(24.),
+                        ..default()
+                    },
+                    background_color: hud_colors.damage.into(),
+                    ..default()
+                },
+                DamageIndicator {
+                    timer: Timer::from_seconds(DAMAGE_FEEDBACK_DURATION, TimerMode::Once),
+                },
+                StateScoped(Gameplay),
+            ));
+        } else if let Ok(target) = q_targets.get(event.target) {
+            commands.spawn((
+                TextBundle::from_section(
+                    format!("{:.0}", event.amount),
+                    TextStyle {
+                        font_size: 24.,
+                        color: hud_colors.damage,
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    ..default()
+                }),
+                DamageNumber {
+                    world_position: target.translation(),
+                    timer: Timer::from_seconds(DAMAGE_FEEDBACK_DURATION, TimerMode::Once),
+                },
+                StateScoped(Gameplay),
+            ));
+        }
+    }
+}
+
+fn update_damage_indicators(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut query: Query<(Entity, &mut DamageIndicator, &mut BackgroundColor)>,
+) {
+    for (entity, mut indicator, mut color) in &mut query {
+        indicator.timer.tick(time.delta());
+        if indicator.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            color.0.set_alpha(1. - indicator.timer.fraction());
+        }
+    }
+}
+
+/// Moves damage numbers upwards from where the damage was dealt while fading them out.
+fn update_damage_numbers(
+    mut commands: Commands,
+    time: Res<Time>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut query: Query<(Entity, &mut DamageNumber, &mut Style, &mut Text)>,
+) {
+    let Ok((camera, camera_transform)) = q_camera.get_single() else {
+        return;
+    };
+
+    for (entity, mut number, mut style, mut text) in &mut query {
+        number.timer.tick(time.delta());
+        if number.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let fraction = number.timer.fraction();
+        let world_position = number.world_position + Vec3::Y * fraction;
+        let Some(position) = camera.world_to_viewport(camera_transform, world_position) else {
+            continue;
+        };
+
+        style.left = Val::Px(position.x);
+        style.top = Val::Px(position.y);
+        for section in &mut text.sections {
+            section.style.color.set_alpha(1. - fraction);
+        }
+    }
+}
@@ -0,0 +1,513 @@
+use avian3d::prelude::*;
+use bevy::{ecs::entity::EntityHashSet, prelude::*};
+use serde::Deserialize;
+
+use crate::{
+    app_state::AppState, game_management::Health, gltf_extras::ExtrasAppExt, level::body_of,
+    projectile::Weapon, CharacterController, MainCamera,
+};
+
+/// The radius of the sphere swept forward by an [`InteractAction::Interact`].
+const INTERACT_RADIUS: f32 = 0.4;
+/// How far in front of the character the [`InteractAction::Interact`] shape cast reaches.
+const INTERACT_DISTANCE: f32 = 1.5;
+/// How far in front of the character a held prop is carried.
+const HOLD_DISTANCE: f32 = 1.5;
+/// How fast a held prop catches up with where it is carried.
+const HOLD_STIFFNESS: f32 = 12.0;
+
+pub struct InteractionPlugin;
+
+impl Plugin for InteractionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<InteractAction>()
+            .add_event::<OperateDoor>()
+            .register_type::<Inventory>()
+            .register_extras_component::<Pickup>("Pickup")
+            .register_extras_component::<Prop>("Prop")
+            .register_extras_component::<Switch>("Switch")
+            .register_extras_component::<Door>("Door")
+            .add_systems(
+                Update,
+                (
+                    (keyboard_input, gamepad_input),
+                    interact,
+                    carry_held_props,
+                    collect_pickups,
+                    (setup_switches, press_switches).chain(),
+                    operate_doors,
+                    move_doors,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                PostUpdate,
+                setup_doors.after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// An event sent for an interaction input action.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InteractAction {
+    /// Uses whatever is in front of the character, or drops the held prop.
+    Interact,
+    /// Throws the held prop.
+    Throw,
+}
+
+/// The keys collected by the player.
+#[derive(Component, Reflect, Debug, Clone, Default)]
+#[reflect(Component, Default)]
+pub struct Inventory {
+    pub keys: Vec<String>,
+}
+
+impl Inventory {
+    pub fn has_key(&self, key: &str) -> bool {
+        self.keys.iter().any(|k| k == key)
+    }
+}
+
+/// The kind of item a [`Pickup`] grants.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub enum PickupKind {
+    Health,
+    Ammo,
+    Key(String),
+}
+
+/// An item that is picked up by the player when touching its sensor collider.
+#[derive(Component, Deserialize, Debug, Clone)]
+pub struct Pickup {
+    pub kind: PickupKind,
+    /// The health or ammo granted, 0 refilling it completely.
+    #[serde(default)]
+    pub amount: f32,
+}
+
+/// A dynamic body the player can push around, and carry and throw if `throwable`.
+#[derive(Component, Deserialize, Debug, Clone)]
+pub struct Prop {
+    #[serde(default = "Prop::default_throwable")]
+    pub throwable: bool,
+    /// The speed the prop is thrown at.
+    #[serde(default = "Prop::default_throw_speed")]
+    pub throw_speed: f32,
+}
+
+impl Prop {
+    fn default_throwable() -> bool {
+        true
+    }
+
+    fn default_throw_speed() -> f32 {
+        12.0
+    }
+}
+
+impl Default for Prop {
+    fn default() -> Self {
+        Self {
+            throwable: Self::default_throwable(),
+            throw_speed: Self::default_throw_speed(),
+        }
+    }
+}
+
+/// A prop carried by a character.
+#[derive(Component, Debug)]
+#[component(storage = "SparseSet")]
+pub struct Held {
+    pub holder: Entity,
+}
+
+/// Operates the [`Door`]s with the `target` id, either when the player
+/// interacts with it or, for pressure plates, while a body rests on it.
+#[derive(Component, Deserialize, Debug, Clone)]
+pub struct Switch {
+    pub target: String,
+    #[serde(default)]
+    pub pressure_plate: bool,
+}
+
+/// The runtime state of a [`Switch`].
+#[derive(Component, Debug, Clone, Copy, Default)]
+pub struct SwitchState {
+    /// The contacts of characters and props with the switch, closing the doors of a
+    /// pressure plate once there are none.
+    pub pressers: u32,
+}
+
+/// A kinematic body sliding by `open_offset` when opened.
+#[derive(Component, Deserialize, Debug, Clone)]
+pub struct Door {
+    pub id: String,
+    /// The key needed to open the door by interacting with it.
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default = "Door::default_open_offset")]
+    pub open_offset: (f32, f32, f32),
+    /// The speed the door slides at.
+    #[serde(default = "Door::default_speed")]
+    pub speed: f32,
+}
+
+impl Door {
+    fn default_open_offset() -> (f32, f32, f32) {
+        (0.0, 3.0, 0.0)
+    }
+
+    fn default_speed() -> f32 {
+        2.0
+    }
+}
+
+/// The runtime state of a [`Door`].
+#[derive(Component, Debug, Clone, Copy)]
+pub struct DoorState {
+    pub open: bool,
+    /// The global translation of the closed door, as doors are moved by their
+    /// world space [`LinearVelocity`].
+    pub closed_translation: Vec3,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DoorCommand {
+    Open,
+    Close,
+    Toggle,
+}
+
+/// An event that opens or closes every [`Door`] with the given id.
+#[derive(Event, Clone, Debug)]
+pub struct OperateDoor {
+    pub id: String,
+    pub command: DoorCommand,
+}
+
+/// Sends [`InteractAction`] events based on keyboard and mouse input.
+fn keyboard_input(
+    mut interact_events: EventWriter<InteractAction>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyE) {
+        interact_events.send(InteractAction::Interact);
+    }
+    if mouse_input.just_pressed(MouseButton::Right) {
+        interact_events.send(InteractAction::Throw);
+    }
+}
+
+/// Sends [`InteractAction`] events based on gamepad input.
+fn gamepad_input(
+    mut interact_events: EventWriter<InteractAction>,
+    gamepads: Res<Gamepads>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+) {
+    for gamepad in gamepads.iter() {
+        let interact_button = GamepadButton {
+            gamepad,
+            button_type: GamepadButtonType::West,
+        };
+        let throw_button = GamepadButton {
+            gamepad,
+            button_type: GamepadButtonType::LeftTrigger2,
+        };
+
+        if buttons.just_pressed(interact_button) {
+            interact_events.send(InteractAction::Interact);
+        }
+        if buttons.just_pressed(throw_button) {
+            interact_events.send(InteractAction::Throw);
+        }
+    }
+}
+
+/// The horizontal direction the camera looks in, which the character faces.
+fn facing(camera: &Transform) -> Option<Dir3> {
+    Dir3::new(camera.forward().with_y(0.0)).ok()
+}
+
+/// Handles [`InteractAction`]s by using what is in front of the character.
+#[allow(clippy::too_many_arguments)]
+fn interact(
+    mut commands: Commands,
+    mut interact_events: EventReader<InteractAction>,
+    mut door_events: EventWriter<OperateDoor>,
+    spatial_query: SpatialQuery,
+    q_player: Query<(Entity, &Transform, Option<&Inventory>), With<CharacterController>>,
+    q_camera: Query<&Transform, With<MainCamera>>,
+    q_collider_parents: Query<&ColliderParent>,
+    q_props: Query<&Prop>,
+    mut q_held: Query<(Entity, &Held, &Prop, &mut LinearVelocity)>,
+    q_switches: Query<&Switch>,
+    q_doors: Query<&Door>,
+) {
+    let Ok((player, player_transform, inventory)) = q_player.get_single() else {
+        return;
+    };
+    let Ok(camera) = q_camera.get_single() else {
+        return;
+    };
+    let Some(direction) = facing(camera) else {
+        return;
+    };
+
+    for action in interact_events.read() {
+        // Whatever is held is dropped or thrown first
+        if let Some((prop, _, prop_settings, mut linear_velocity)) = q_held
+            .iter_mut()
+            .find(|(_, held, _, _)| held.holder == player)
+        {
+            if *action == InteractAction::Throw {
+                linear_velocity.0 = (*direction + Vec3::Y * 0.3) * prop_settings.throw_speed;
+            }
+            commands.entity(prop).remove::<Held>();
+            continue;
+        }
+
+        if *action != InteractAction::Interact {
+            continue;
+        }
+
+        let Some(hit) = spatial_query.cast_shape(
+            &Collider::sphere(INTERACT_RADIUS),
+            player_transform.translation,
+            Quat::IDENTITY,
+            direction,
+            INTERACT_DISTANCE,
+            true,
+            SpatialQueryFilter::default().with_excluded_entities([player]),
+        ) else {
+            continue;
+        };
+
+        let target = body_of(hit.entity, &q_collider_parents);
+        if let Ok(switch) = q_switches.get(target) {
+            if !switch.pressure_plate {
+                door_events.send(OperateDoor {
+                    id: switch.target.clone(),
+                    command: DoorCommand::Toggle,
+                });
+            }
+        } else if let Ok(door) = q_doors.get(target) {
+            let unlocked = door.key.as_ref().map_or(true, |key| {
+                inventory.is_some_and(|inventory| inventory.has_key(key))
+            });
+            if unlocked {
+                door_events.send(OperateDoor {
+                    id: door.id.clone(),
+                    command: DoorCommand::Toggle,
+                });
+            } else {
+                info!("The door \"{}\" is locked", door.id);
+            }
+        } else if q_props.get(target).is_ok_and(|prop| prop.throwable) {
+            commands.entity(target).insert(Held { holder: player });
+        }
+    }
+}
+
+/// Pulls held props in front of their holder.
+fn carry_held_props(
+    q_holders: Query<&Transform, With<CharacterController>>,
+    q_camera: Query<&Transform, With<MainCamera>>,
+    mut q_held: Query<(
+        &Held,
+        &GlobalTransform,
+        &mut LinearVelocity,
+        &mut AngularVelocity,
+    )>,
+) {
+    let Some(direction) = q_camera.get_single().ok().and_then(facing) else {
+        return;
+    };
+
+    for (held, transform, mut linear_velocity, mut angular_velocity) in &mut q_held {
+        let Ok(holder) = q_holders.get(held.holder) else {
+            continue;
+        };
+
+        let target = holder.translation + *direction * HOLD_DISTANCE + Vec3::Y * 0.5;
+        linear_velocity.0 = (target - transform.translation()) * HOLD_STIFFNESS;
+        angular_velocity.0 = Vec3::ZERO;
+    }
+}
+
+/// Applies the [`Pickup`]s the player touches and removes them from the level.
+fn collect_pickups(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionStarted>,
+    mut q_player: Query<
+        (
+            Option<&mut Health>,
+            Option<&mut Weapon>,
+            Option<&mut Inventory>,
+        ),
+        With<CharacterController>,
+    >,
+    q_collider_parents: Query<&ColliderParent>,
+    q_pickups: Query<&Pickup>,
+) {
+    // A pickup with several colliders starts several collisions before it is despawned
+    let mut collected = EntityHashSet::default();
+    for CollisionStarted(a, b) in collision_events.read() {
+        let (player, pickup_entity) = if q_player.contains(*a) {
+            (*a, body_of(*b, &q_collider_parents))
+        } else if q_player.contains(*b) {
+            (*b, body_of(*a, &q_collider_parents))
+        } else {
+            continue;
+        };
+        let Ok(pickup) = q_pickups.get(pickup_entity) else {
+            continue;
+        };
+        if !collected.insert(pickup_entity) {
+            continue;
+        }
+        let Ok((health, weapon, inventory)) = q_player.get_mut(player) else {
+            continue;
+        };
+
+        match &pickup.kind {
+            PickupKind::Health => {
+                let Some(mut health) = health else {
+                    continue;
+                };
+                health.current = if pickup.amount > 0.0 {
+                    (health.current + pickup.amount).min(health.max)
+                } else {
+                    health.max
+                };
+            }
+            PickupKind::Ammo => {
+                let Some(mut weapon) = weapon else {
+                    continue;
+                };
+                weapon.ammo = if pickup.amount > 0.0 {
+                    (weapon.ammo + pickup.amount as u32).min(weapon.max_ammo)
+                } else {
+                    weapon.max_ammo
+                };
+            }
+            PickupKind::Key(key) => match inventory {
+                Some(mut inventory) => inventory.keys.push(key.clone()),
+                None => {
+                    commands.entity(player).insert(Inventory {
+                        keys: vec![key.clone()],
+                    });
+                }
+            },
+        }
+
+        commands.entity(pickup_entity).despawn_recursive();
+    }
+}
+
+fn setup_switches(mut commands: Commands, q_switches: Query<Entity, Added<Switch>>) {
+    for entity in &q_switches {
+        commands.entity(entity).insert(SwitchState::default());
+    }
+}
+
+/// Opens the doors of pressure plates while a character or a prop rests on them.
+fn press_switches(
+    mut started_events: EventReader<CollisionStarted>,
+    mut ended_events: EventReader<CollisionEnded>,
+    mut door_events: EventWriter<OperateDoor>,
+    q_collider_parents: Query<&ColliderParent>,
+    mut q_switches: Query<(&Switch, &mut SwitchState)>,
+    q_pressers: Query<(), Or<(With<CharacterController>, With<Prop>)>>,
+) {
+    let started = started_events
+        .read()
+        .map(|CollisionStarted(a, b)| (*a, *b, true));
+    let ended = ended_events
+        .read()
+        .map(|CollisionEnded(a, b)| (*a, *b, false));
+
+    for (a, b, pressed) in started.chain(ended) {
+        let (a, b) = (
+            body_of(a, &q_collider_parents),
+            body_of(b, &q_collider_parents),
+        );
+        let switch_entity = if q_switches.contains(a) && q_pressers.contains(b) {
+            a
+        } else if q_switches.contains(b) && q_pressers.contains(a) {
+            b
+        } else {
+            continue;
+        };
+        let Ok((switch, mut state)) = q_switches.get_mut(switch_entity) else {
+            continue;
+        };
+        if !switch.pressure_plate {
+            continue;
+        }
+
+        // Only the first presser opens the doors and only the last one closes them
+        let command = if pressed {
+            state.pressers += 1;
+            (state.pressers == 1).then_some(DoorCommand::Open)
+        } else {
+            state.pressers = state.pressers.saturating_sub(1);
+            (state.pressers == 0).then_some(DoorCommand::Close)
+        };
+        if let Some(command) = command {
+            door_events.send(OperateDoor {
+                id: switch.target.clone(),
+                command,
+            });
+        }
+    }
+}
+
+/// Remembers where new doors are closed, once their global transform is propagated.
+fn setup_doors(
+    mut commands: Commands,
+    q_doors: Query<(Entity, &GlobalTransform), (With<Door>, Without<DoorState>)>,
+) {
+    for (entity, transform) in &q_doors {
+        commands.entity(entity).insert(DoorState {
+            open: false,
+            closed_translation: transform.translation(),
+        });
+    }
+}
+
+fn operate_doors(
+    mut door_events: EventReader<OperateDoor>,
+    mut q_doors: Query<(&Door, &mut DoorState)>,
+) {
+    for event in door_events.read() {
+        for (door, mut state) in &mut q_doors {
+            if door.id != event.id {
+                continue;
+            }
+            state.open = match event.command {
+                DoorCommand::Open => true,
+                DoorCommand::Close => false,
+                DoorCommand::Toggle => !state.open,
+            };
+        }
+    }
+}
+
+/// Slides doors towards their open or closed position.
+fn move_doors(mut q_doors: Query<(&Door, &DoorState, &GlobalTransform, &mut LinearVelocity)>) {
+    for (door, state, transform, mut linear_velocity) in &mut q_doors {
+        let (x, y, z) = door.open_offset;
+        let target = if state.open {
+            state.closed_translation + Vec3::new(x, y, z)
+        } else {
+            state.closed_translation
+        };
+
+        // Kinematic bodies are moved by their velocity so they push what they touch
+        let offset = target - transform.translation();
+        linear_velocity.0 = offset.normalize_or_zero() * door.speed.min(offset.length() * 10.0);
+    }
+}
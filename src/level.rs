@@ -0,0 +1,358 @@
+use avian3d::prelude::*;
+use bevy::{gltf::GltfExtras, prelude::*};
+use serde_json::Value;
+
+use crate::{
+    app_state::LoadingAssets, authored_collider::AuthoredCollider,
+    authored_scene::is_authored_scene, game_management::GameLayer,
+};
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, generate_level_colliders);
+    }
+}
+
+/// A marker component for the root of a glTF scene spawned as part of a level.
+#[derive(Component, Default)]
+pub struct Level {
+    /// Whether mesh nodes without an explicit rigid body should be simulated.
+    pub dynamic: bool,
+}
+
+/// A bundle that spawns a glTF scene as a level with generated colliders.
+#[derive(Bundle)]
+pub struct LevelBundle {
+    level: Level,
+    scene: SceneBundle,
+}
+
+impl LevelBundle {
+    pub fn new(scene: Handle<Scene>) -> Self {
+        Self {
+            level: Level::default(),
+            scene: SceneBundle { scene, ..default() },
+        }
+    }
+
+    /// Marks every mesh node of the scene as a dynamic rigid body unless
+    /// the node says otherwise.
+    pub fn dynamic(mut self) -> Self {
+        self.level.dynamic = true;
+        self
+    }
+}
+
+/// A bundle that spawns a scene authored in the editor as a level, with colliders
+/// generated for the glTF scenes it places.
+#[derive(Bundle)]
+pub struct AuthoredLevelBundle {
+    level: Level,
+    scene: DynamicSceneBundle,
+}
+
+impl AuthoredLevelBundle {
+    pub fn new(scene: Handle<DynamicScene>) -> Self {
+        Self {
+            level: Level::default(),
+            scene: DynamicSceneBundle { scene, ..default() },
+        }
+    }
+
+    /// See [`LevelBundle::dynamic`].
+    pub fn dynamic(mut self) -> Self {
+        self.level.dynamic = true;
+        self
+    }
+}
+
+/// Spawns the level at the given asset path, either a glTF scene or a `.scn.ron`
+/// scene authored in the editor.
+pub fn spawn_level(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    loading_assets: &mut LoadingAssets,
+    path: &str,
+    dynamic: bool,
+) -> Entity {
+    if is_authored_scene(path) {
+        let level = AuthoredLevelBundle::new(loading_assets.add(asset_server.load(path)));
+        commands
+            .spawn(if dynamic { level.dynamic() } else { level })
+            .id()
+    } else {
+        let level = LevelBundle::new(loading_assets.add(asset_server.load(path)));
+        commands
+            .spawn(if dynamic { level.dynamic() } else { level })
+            .id()
+    }
+}
+
+/// The collider shape generated for a level mesh node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LevelCollider {
+    Trimesh,
+    ConvexHull,
+    None,
+}
+
+/// The kind of sensor generated for a level node instead of a solid collider.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LevelSensor {
+    /// Only detects the player.
+    Pickup,
+    /// Detects every layer, the
+    /// [`TriggerVolume`](crate::trigger::TriggerVolume) filtering what it reacts to.
+    Trigger,
+}
+
+/// Collider settings for a single level node, resolved from its name and glTF extras.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LevelNodeSettings {
+    pub collider: LevelCollider,
+    pub rigid_body: RigidBody,
+    /// Whether the collider only detects bodies instead of blocking them.
+    pub sensor: Option<LevelSensor>,
+}
+
+impl LevelNodeSettings {
+    /// Resolves the settings of a node.
+    ///
+    /// Naming conventions are applied first (`_convex`, `_trimesh`, `_nocol`,
+    /// `_dynamic` and `_static` suffixes), then the gameplay extras (`Pickup` and
+    /// `TriggerVolume` nodes are static sensors, `Door`s kinematic and `Prop`s
+    /// dynamic), then the
+    /// `Collider` and `RigidBody` glTF extras, so custom properties set in
+    /// Blender always win.
+    pub fn resolve(name: Option<&str>, extras: Option<&str>, dynamic: bool) -> Self {
+        let mut rigid_body = if dynamic {
+            RigidBody::Dynamic
+        } else {
+            RigidBody::Static
+        };
+        let mut collider = None;
+        let mut sensor = None;
+
+        if let Some(name) = name {
+            let name = name.to_lowercase();
+            if name.ends_with("_dynamic") {
+                rigid_body = RigidBody::Dynamic;
+            } else if name.ends_with("_static") {
+                rigid_body = RigidBody::Static;
+            } else if name.ends_with("_convex") {
+                collider = Some(LevelCollider::ConvexHull);
+            } else if name.ends_with("_trimesh") {
+                collider = Some(LevelCollider::Trimesh);
+            } else if name.ends_with("_nocol") {
+                collider = Some(LevelCollider::None);
+            }
+        }
+
+        if let Some(Value::Object(extras)) = extras.and_then(|e| serde_json::from_str(e).ok()) {
+            if extras.contains_key("Pickup") {
+                sensor = Some(LevelSensor::Pickup);
+            } else if extras.contains_key("TriggerVolume") {
+                sensor = Some(LevelSensor::Trigger);
+            } else if extras.contains_key("Door") {
+                rigid_body = RigidBody::Kinematic;
+            } else if extras.contains_key("Prop") {
+                rigid_body = RigidBody::Dynamic;
+            }
+            if let Some(value) = extras.get("RigidBody").and_then(Value::as_str) {
+                match value.trim() {
+                    "Static" => rigid_body = RigidBody::Static,
+                    "Dynamic" => rigid_body = RigidBody::Dynamic,
+                    "Kinematic" => rigid_body = RigidBody::Kinematic,
+                    other => warn!("Unknown RigidBody extra \"{other}\""),
+                }
+            }
+            if let Some(value) = extras.get("Collider").and_then(Value::as_str) {
+                match value.trim() {
+                    "Trimesh" => collider = Some(LevelCollider::Trimesh),
+                    "ConvexHull" => collider = Some(LevelCollider::ConvexHull),
+                    "None" => collider = Some(LevelCollider::None),
+                    other => warn!("Unknown Collider extra \"{other}\""),
+                }
+            }
+        }
+
+        if sensor.is_some() {
+            rigid_body = RigidBody::Static;
+            collider = collider.or(Some(LevelCollider::ConvexHull));
+        }
+
+        // Trimeshes have no volume, so simulated bodies default to convex hulls
+        let collider = collider.unwrap_or(match rigid_body {
+            RigidBody::Dynamic => LevelCollider::ConvexHull,
+            _ => LevelCollider::Trimesh,
+        });
+
+        Self {
+            collider,
+            rigid_body,
+            sensor,
+        }
+    }
+
+    /// The collision layers used for the node's collider.
+    pub fn collision_layers(&self) -> CollisionLayers {
+        match self.sensor {
+            Some(LevelSensor::Pickup) => {
+                return CollisionLayers::new(GameLayer::Default, GameLayer::Player)
+            }
+            Some(LevelSensor::Trigger) => {
+                return CollisionLayers::new(GameLayer::Default, LayerMask::ALL)
+            }
+            None => {}
+        }
+
+        match self.rigid_body {
+            RigidBody::Static => CollisionLayers::new(GameLayer::Ground, LayerMask::ALL),
+            _ => CollisionLayers::new(GameLayer::Default, LayerMask::ALL),
+        }
+    }
+}
+
+/// The rigid body a collider belongs to, which holds the gameplay components
+/// of level nodes whose colliders are on their mesh primitives.
+pub fn body_of(collider: Entity, q_collider_parents: &Query<&ColliderParent>) -> Entity {
+    q_collider_parents
+        .get(collider)
+        .map_or(collider, ColliderParent::get)
+}
+
+/// Generates colliders for the meshes of level scenes once they are spawned.
+///
+/// glTF mesh primitives are spawned as children of their node, so the rigid body
+/// is inserted on the node and the collider on each primitive.
+fn generate_level_colliders(
+    mut commands: Commands,
+    q_meshes: Query<(Entity, &Parent), Added<Handle<Mesh>>>,
+    q_nodes: Query<(Option<&Name>, Option<&GltfExtras>)>,
+    q_parents: Query<&Parent>,
+    q_levels: Query<&Level>,
+    q_authored: Query<(), With<AuthoredCollider>>,
+) {
+    for (entity, node) in &q_meshes {
+        let Some(level) = q_parents
+            .iter_ancestors(entity)
+            .find_map(|ancestor| q_levels.get(ancestor).ok())
+        else {
+            continue;
+        };
+        // Colliders authored in the editor replace the generated ones
+        if std::iter::once(entity)
+            .chain(q_parents.iter_ancestors(entity))
+            .any(|ancestor| q_authored.contains(ancestor))
+        {
+            continue;
+        }
+
+        let Ok((name, extras)) = q_nodes.get(node.get()) else {
+            continue;
+        };
+
+        let settings = LevelNodeSettings::resolve(
+            name.map(Name::as_str),
+            extras.map(|extras| extras.value.as_str()),
+            level.dynamic,
+        );
+
+        let constructor = match settings.collider {
+            LevelCollider::Trimesh => ColliderConstructor::TrimeshFromMesh,
+            LevelCollider::ConvexHull => ColliderConstructor::ConvexHullFromMesh,
+            LevelCollider::None => continue,
+        };
+
+        commands.entity(node.get()).insert(settings.rigid_body);
+        let mut collider = commands.entity(entity);
+        collider.insert((constructor, settings.collision_layers()));
+        if settings.sensor.is_some() {
+            collider.insert(Sensor);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(name: &str, extras: &str) -> LevelNodeSettings {
+        let extras = (!extras.is_empty()).then_some(extras);
+        LevelNodeSettings::resolve(Some(name), extras, false)
+    }
+
+    #[test]
+    fn static_nodes_default_to_trimeshes_and_dynamic_ones_to_convex_hulls() {
+        let node = LevelNodeSettings::resolve(None, None, false);
+        assert_eq!(node.rigid_body, RigidBody::Static);
+        assert_eq!(node.collider, LevelCollider::Trimesh);
+        assert_eq!(node.sensor, None);
+
+        let node = LevelNodeSettings::resolve(None, None, true);
+        assert_eq!(node.rigid_body, RigidBody::Dynamic);
+        assert_eq!(node.collider, LevelCollider::ConvexHull);
+    }
+
+    #[test]
+    fn name_suffixes_select_the_body_and_collider() {
+        assert_eq!(
+            resolve("Rock_Convex", "").collider,
+            LevelCollider::ConvexHull
+        );
+        assert_eq!(resolve("wall_trimesh", "").collider, LevelCollider::Trimesh);
+        assert_eq!(resolve("grass_nocol", "").collider, LevelCollider::None);
+
+        let crate_node = resolve("crate_dynamic", "");
+        assert_eq!(crate_node.rigid_body, RigidBody::Dynamic);
+        assert_eq!(crate_node.collider, LevelCollider::ConvexHull);
+        let pillar = LevelNodeSettings::resolve(Some("pillar_static"), None, true);
+        assert_eq!(pillar.rigid_body, RigidBody::Static);
+        assert_eq!(pillar.collider, LevelCollider::Trimesh);
+    }
+
+    #[test]
+    fn gameplay_extras_override_name_suffixes() {
+        assert_eq!(
+            resolve("door_static", r#"{"Door": "(id: \"a\")"}"#).rigid_body,
+            RigidBody::Kinematic
+        );
+        assert_eq!(
+            resolve("barrel_static", r#"{"Prop": "()"}"#).rigid_body,
+            RigidBody::Dynamic
+        );
+    }
+
+    #[test]
+    fn collider_and_rigid_body_extras_override_gameplay_extras() {
+        let node = resolve(
+            "barrel_trimesh",
+            r#"{"Prop": "()", "RigidBody": "Kinematic", "Collider": "ConvexHull"}"#,
+        );
+        assert_eq!(node.rigid_body, RigidBody::Kinematic);
+        assert_eq!(node.collider, LevelCollider::ConvexHull);
+
+        // Unknown values keep what was resolved before
+        let node = resolve(
+            "rock_convex",
+            r#"{"RigidBody": "Floating", "Collider": "Box"}"#,
+        );
+        assert_eq!(node.rigid_body, RigidBody::Static);
+        assert_eq!(node.collider, LevelCollider::ConvexHull);
+    }
+
+    #[test]
+    fn sensors_are_static_convex_hulls_unless_a_collider_is_set() {
+        let pickup = resolve("key_dynamic", r#"{"Pickup": "()", "RigidBody": "Dynamic"}"#);
+        assert_eq!(pickup.sensor, Some(LevelSensor::Pickup));
+        assert_eq!(pickup.rigid_body, RigidBody::Static);
+        assert_eq!(pickup.collider, LevelCollider::ConvexHull);
+
+        let trigger = resolve("zone_trimesh", r#"{"TriggerVolume": "()"}"#);
+        assert_eq!(trigger.sensor, Some(LevelSensor::Trigger));
+        assert_eq!(trigger.rigid_body, RigidBody::Static);
+        assert_eq!(trigger.collider, LevelCollider::Trimesh);
+    }
+}
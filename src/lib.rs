@@ -0,0 +1,97 @@
+//! Code shared by the game and the editor, which hosts the gameplay in its scene view.
+use authored_collider::AuthoredColliderPlugin;
+use authored_scene::AuthoredScenePlugin;
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use bevy_hanabi::HanabiPlugin;
+use checkpoint::CheckpointPlugin;
+use game_management::{GameLayer, GameManagementPlugin, Health, Team};
+use gltf_extras::GltfExtrasPlugin;
+use interaction::{InteractionPlugin, Inventory};
+use level::LevelPlugin;
+use material_library::MaterialLibraryPlugin;
+use projectile::{ProjectilePlugin, Weapon};
+use toon_material::ToonMaterialPlugin;
+use trigger::TriggerPlugin;
+use vfx::VfxPlugin;
+
+use app_state::AppStatePlugin;
+use character_controller::{
+    CharacterController, CharacterControllerBundle, CharacterControllerPlugin, Grounded,
+};
+
+pub mod app_state;
+pub mod authored_collider;
+pub mod authored_scene;
+pub mod character_controller;
+pub mod checkpoint;
+pub mod effect_def;
+pub mod game_management;
+pub mod gltf_extras;
+pub mod hud;
+pub mod interaction;
+pub mod level;
+pub mod lighting;
+pub mod material_library;
+pub mod menu;
+pub mod post_process;
+pub mod projectile;
+pub mod save_game;
+pub mod toon_material;
+pub mod trigger;
+pub mod vfx;
+
+/// The camera the player looks through, which movement and aiming are relative to.
+#[derive(Component, Reflect, Clone, Default)]
+#[reflect(Component, Default)]
+pub struct MainCamera;
+
+/// The physics, player, combat and level plugins, without the game's menus, HUD and
+/// camera, so the editor can run the gameplay in its scene view.
+pub struct GameplayPlugin;
+
+impl Plugin for GameplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(PhysicsPlugins::default())
+            .add_plugins(HanabiPlugin)
+            .add_plugins(AppStatePlugin)
+            .add_plugins(CharacterControllerPlugin)
+            .add_plugins(VfxPlugin)
+            .add_plugins(LevelPlugin)
+            .add_plugins(AuthoredScenePlugin)
+            .add_plugins(AuthoredColliderPlugin)
+            .add_plugins(GltfExtrasPlugin)
+            .add_plugins(GameManagementPlugin)
+            .add_plugins(InteractionPlugin)
+            .add_plugins(TriggerPlugin)
+            .add_plugins(CheckpointPlugin)
+            .add_plugins(ProjectilePlugin)
+            .add_plugins(ToonMaterialPlugin)
+            .add_plugins(MaterialLibraryPlugin);
+    }
+}
+
+/// The gameplay components of the player, placed at the level's `PlayerSpawn` once
+/// it is loaded.
+pub fn player_bundle() -> impl Bundle {
+    (
+        Team::new("blue"),
+        CharacterControllerBundle::new(Collider::capsule(1.0, 0.4)).with_movement(
+            100.0,
+            0.92,
+            8.0,
+            (70f32).to_radians(),
+        ),
+        CollisionLayers::new(
+            GameLayer::Player,
+            [GameLayer::Enemy, GameLayer::Ground, GameLayer::Default],
+        ),
+        Grounded::default(),
+        Health::default(),
+        Weapon::default(),
+        Inventory::default(),
+        Friction::ZERO.with_combine_rule(CoefficientCombine::Min),
+        Restitution::ZERO.with_combine_rule(CoefficientCombine::Min),
+        GravityScale(2.0),
+    )
+}
@@ -0,0 +1,430 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    pbr::{CascadeShadowConfig, CascadeShadowConfigBuilder},
+    prelude::*,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    app_state::{AppState, LoadingAssets},
+    gltf_extras::ExtrasAppExt,
+};
+
+pub const DEFAULT_LIGHTING_PRESET: &str = "day";
+
+pub struct LightingPlugin;
+
+impl Plugin for LightingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LightingPreset>()
+            .init_asset_loader::<LightingPresetLoader>()
+            .init_resource::<TimeOfDay>()
+            .register_type::<TimeOfDay>()
+            .register_extras_component::<LevelLighting>("LevelLighting")
+            .add_systems(Startup, load_default_preset)
+            .add_systems(
+                Update,
+                (
+                    apply_level_lighting,
+                    advance_time_of_day.run_if(in_state(AppState::InGame)),
+                    apply_lighting,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// The current time of day, driving the sun and the lighting keyframes.
+#[derive(Resource, Reflect, Clone, Debug)]
+#[reflect(Resource)]
+pub struct TimeOfDay {
+    /// The hour of the day, from 0 to 24.
+    pub hour: f32,
+    /// Stops time from advancing, e.g. to inspect the lighting at a given hour.
+    pub paused: bool,
+    /// The preset whose keyframes are used.
+    pub preset: Handle<LightingPreset>,
+}
+
+impl Default for TimeOfDay {
+    fn default() -> Self {
+        Self {
+            hour: 12.0,
+            paused: false,
+            preset: Handle::default(),
+        }
+    }
+}
+
+/// The directional light moved and colored by the [`TimeOfDay`].
+#[derive(Component, Default)]
+pub struct Sun;
+
+/// Sets the lighting preset and optionally the hour of a level, authored as a
+/// glTF custom property, e.g. `LevelLighting: (preset: "dusk", hour: Some(19.0))`.
+#[derive(Component, Deserialize, Debug, Clone)]
+pub struct LevelLighting {
+    pub preset: String,
+    #[serde(default)]
+    pub hour: Option<f32>,
+}
+
+/// Named lighting loaded from `assets/lighting/<name>.lighting.ron`.
+#[derive(Asset, TypePath, Deserialize, Clone, Debug)]
+pub struct LightingPreset {
+    /// The real time a full day lasts, in seconds. 0 stops time.
+    #[serde(default)]
+    pub day_length: f32,
+    /// The angle between the path of the sun and the vertical, in degrees.
+    #[serde(default)]
+    pub sun_tilt: f32,
+    #[serde(default)]
+    pub shadows: ShadowCascades,
+    /// Keyframes sorted by hour, interpolated around the clock.
+    pub keyframes: Vec<LightingKeyframe>,
+}
+
+impl LightingPreset {
+    pub fn path(name: &str) -> String {
+        format!("lighting/{name}.lighting.ron")
+    }
+
+    /// The lighting at the given hour.
+    pub fn state_at(&self, hour: f32) -> LightingState {
+        let hour = hour.rem_euclid(24.0);
+        let sun_rotation = sun_rotation(hour, self.sun_tilt.to_radians());
+
+        let (Some(first), Some(last)) = (self.keyframes.first(), self.keyframes.last()) else {
+            return LightingState {
+                sun_rotation,
+                ..default()
+            };
+        };
+
+        // The keyframes surrounding the hour, wrapping around midnight
+        let next_index = self
+            .keyframes
+            .iter()
+            .position(|keyframe| keyframe.hour > hour);
+        let (previous, next) = match next_index {
+            Some(0) | None => (last, first),
+            Some(index) => (&self.keyframes[index - 1], &self.keyframes[index]),
+        };
+
+        let span = (next.hour - previous.hour).rem_euclid(24.0);
+        let t = if span > 0.0 {
+            (hour - previous.hour).rem_euclid(24.0) / span
+        } else {
+            0.0
+        };
+
+        let lerp = |from: f32, to: f32| from + (to - from) * t;
+        let mix = |from: (f32, f32, f32), to: (f32, f32, f32)| {
+            let from = LinearRgba::from(Color::srgb(from.0, from.1, from.2));
+            let to = LinearRgba::from(Color::srgb(to.0, to.1, to.2));
+            Color::from(from.mix(&to, t))
+        };
+
+        LightingState {
+            sun_rotation,
+            sun_color: mix(previous.sun_color, next.sun_color),
+            illuminance: lerp(previous.illuminance, next.illuminance),
+            ambient_color: mix(previous.ambient_color, next.ambient_color),
+            ambient_brightness: lerp(previous.ambient_brightness, next.ambient_brightness),
+        }
+    }
+}
+
+/// The rotation of a light shining from the sun at the given hour, rising in
+/// the east (+X) at 6 and setting in the west at 18.
+pub fn sun_rotation(hour: f32, tilt: f32) -> Quat {
+    let angle = (hour - 6.0) / 12.0 * PI;
+    let to_sun = Quat::from_rotation_x(tilt) * Vec3::new(angle.cos(), angle.sin(), 0.0);
+    Transform::default().looking_to(-to_sun, Vec3::Z).rotation
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct LightingKeyframe {
+    pub hour: f32,
+    /// The sRGB color of the sun.
+    pub sun_color: (f32, f32, f32),
+    /// The sun illuminance, in lux.
+    pub illuminance: f32,
+    /// The sRGB color of the ambient light.
+    pub ambient_color: (f32, f32, f32),
+    pub ambient_brightness: f32,
+}
+
+impl Default for LightingKeyframe {
+    fn default() -> Self {
+        Self {
+            hour: 12.0,
+            sun_color: (1.0, 1.0, 1.0),
+            illuminance: light_consts::lux::AMBIENT_DAYLIGHT,
+            ambient_color: (1.0, 1.0, 1.0),
+            ambient_brightness: 80.0,
+        }
+    }
+}
+
+/// The shadow cascades of the sun, see [`CascadeShadowConfigBuilder`].
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct ShadowCascades {
+    pub num_cascades: usize,
+    pub minimum_distance: f32,
+    pub maximum_distance: f32,
+    pub first_cascade_far_bound: f32,
+    pub overlap_proportion: f32,
+}
+
+impl Default for ShadowCascades {
+    fn default() -> Self {
+        let builder = CascadeShadowConfigBuilder::default();
+        Self {
+            num_cascades: builder.num_cascades,
+            minimum_distance: builder.minimum_distance,
+            maximum_distance: builder.maximum_distance,
+            first_cascade_far_bound: builder.first_cascade_far_bound,
+            overlap_proportion: builder.overlap_proportion,
+        }
+    }
+}
+
+impl From<ShadowCascades> for CascadeShadowConfig {
+    fn from(cascades: ShadowCascades) -> Self {
+        CascadeShadowConfigBuilder {
+            num_cascades: cascades.num_cascades,
+            minimum_distance: cascades.minimum_distance,
+            maximum_distance: cascades.maximum_distance,
+            first_cascade_far_bound: cascades.first_cascade_far_bound,
+            overlap_proportion: cascades.overlap_proportion,
+        }
+        .build()
+    }
+}
+
+/// The lighting of a [`LightingPreset`] at a given hour.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightingState {
+    pub sun_rotation: Quat,
+    pub sun_color: Color,
+    pub illuminance: f32,
+    pub ambient_color: Color,
+    pub ambient_brightness: f32,
+}
+
+impl Default for LightingState {
+    fn default() -> Self {
+        Self {
+            sun_rotation: Quat::IDENTITY,
+            sun_color: Color::WHITE,
+            illuminance: light_consts::lux::AMBIENT_DAYLIGHT,
+            ambient_color: Color::WHITE,
+            ambient_brightness: 80.0,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LightingPresetLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum LightingPresetLoaderError {
+    #[error("Could not read the lighting preset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse the lighting preset: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for LightingPresetLoader {
+    type Asset = LightingPreset;
+    type Settings = ();
+    type Error = LightingPresetLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut preset: LightingPreset = ron::de::from_bytes(&bytes)?;
+        preset.keyframes.sort_by(|a, b| a.hour.total_cmp(&b.hour));
+        Ok(preset)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["lighting.ron"]
+    }
+}
+
+fn load_default_preset(
+    asset_server: Res<AssetServer>,
+    mut loading_assets: ResMut<LoadingAssets>,
+    mut time_of_day: ResMut<TimeOfDay>,
+) {
+    time_of_day.preset =
+        loading_assets.add(asset_server.load(LightingPreset::path(DEFAULT_LIGHTING_PRESET)));
+}
+
+/// Switches to the lighting of newly loaded levels.
+fn apply_level_lighting(
+    asset_server: Res<AssetServer>,
+    mut time_of_day: ResMut<TimeOfDay>,
+    query: Query<&LevelLighting, Added<LevelLighting>>,
+) {
+    for level_lighting in &query {
+        time_of_day.preset = asset_server.load(LightingPreset::path(&level_lighting.preset));
+        if let Some(hour) = level_lighting.hour {
+            time_of_day.hour = hour;
+        }
+    }
+}
+
+fn advance_time_of_day(
+    time: Res<Time>,
+    presets: Res<Assets<LightingPreset>>,
+    mut time_of_day: ResMut<TimeOfDay>,
+) {
+    if time_of_day.paused {
+        return;
+    }
+
+    let Some(preset) = presets.get(&time_of_day.preset) else {
+        return;
+    };
+
+    if preset.day_length > 0.0 {
+        time_of_day.hour =
+            (time_of_day.hour + time.delta_seconds() * 24.0 / preset.day_length).rem_euclid(24.0);
+    }
+}
+
+/// Moves and colors the [`Sun`] and the ambient light for the current [`TimeOfDay`].
+fn apply_lighting(
+    mut commands: Commands,
+    time_of_day: Res<TimeOfDay>,
+    presets: Res<Assets<LightingPreset>>,
+    mut preset_events: EventReader<AssetEvent<LightingPreset>>,
+    mut ambient_light: ResMut<AmbientLight>,
+    mut q_sun: Query<(Entity, &mut Transform, &mut DirectionalLight), With<Sun>>,
+    q_added_sun: Query<(), Added<Sun>>,
+) {
+    let preset_id = time_of_day.preset.id();
+    let preset_changed = preset_events
+        .read()
+        .any(|event| event.is_loaded_with_dependencies(preset_id) || event.is_modified(preset_id));
+
+    if !time_of_day.is_changed() && !preset_changed && q_added_sun.is_empty() {
+        return;
+    }
+
+    let Some(preset) = presets.get(preset_id) else {
+        return;
+    };
+
+    let state = preset.state_at(time_of_day.hour);
+    ambient_light.color = state.ambient_color;
+    ambient_light.brightness = state.ambient_brightness;
+
+    for (entity, mut transform, mut light) in &mut q_sun {
+        transform.rotation = state.sun_rotation;
+        light.color = state.sun_color;
+        light.illuminance = state.illuminance;
+        // No shadows are cast from below the horizon
+        light.shadows_enabled = transform.forward().y < 0.0;
+
+        if preset_changed || q_added_sun.contains(entity) {
+            commands
+                .entity(entity)
+                .insert(CascadeShadowConfig::from(preset.shadows));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframe(hour: f32, illuminance: f32, ambient_brightness: f32) -> LightingKeyframe {
+        LightingKeyframe {
+            hour,
+            illuminance,
+            ambient_brightness,
+            ..default()
+        }
+    }
+
+    fn preset(keyframes: Vec<LightingKeyframe>) -> LightingPreset {
+        LightingPreset {
+            day_length: 0.0,
+            sun_tilt: 0.0,
+            shadows: default(),
+            keyframes,
+        }
+    }
+
+    fn day() -> LightingPreset {
+        preset(vec![
+            LightingKeyframe {
+                sun_color: (1.0, 0.5, 0.0),
+                ..keyframe(6.0, 1000.0, 50.0)
+            },
+            keyframe(12.0, 10000.0, 100.0),
+            keyframe(23.0, 0.0, 10.0),
+        ])
+    }
+
+    fn assert_near(actual: f32, expected: f32) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn a_keyframe_hour_gives_its_lighting() {
+        let state = day().state_at(6.0);
+        assert_near(state.illuminance, 1000.0);
+        assert_near(state.ambient_brightness, 50.0);
+        let sun_color = state.sun_color.to_srgba();
+        assert_near(sun_color.red, 1.0);
+        assert_near(sun_color.green, 0.5);
+        assert_near(sun_color.blue, 0.0);
+
+        assert_near(day().state_at(12.0).illuminance, 10000.0);
+    }
+
+    #[test]
+    fn hours_between_keyframes_are_interpolated() {
+        let state = day().state_at(9.0);
+        assert_near(state.illuminance, 5500.0);
+        assert_near(state.ambient_brightness, 75.0);
+    }
+
+    #[test]
+    fn the_last_keyframe_wraps_around_midnight_to_the_first() {
+        // 7 hours from the 23h keyframe to the 6h one
+        assert_near(day().state_at(23.0).illuminance, 0.0);
+        assert_near(day().state_at(0.0).illuminance, 1000.0 / 7.0);
+        assert_near(day().state_at(2.5).illuminance, 500.0);
+        assert_near(day().state_at(24.0).illuminance, 1000.0 / 7.0);
+    }
+
+    #[test]
+    fn a_single_keyframe_lights_the_whole_day() {
+        let preset = preset(vec![keyframe(12.0, 3000.0, 40.0)]);
+        for hour in [0.0, 6.0, 12.0, 18.5, 23.9] {
+            let state = preset.state_at(hour);
+            assert_near(state.illuminance, 3000.0);
+            assert_near(state.ambient_brightness, 40.0);
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::HashMap,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    app_state::LoadingAssets,
+    game_management::Team,
+    toon_material::{ToonMaterial, ToonMaterialBuilder},
+};
+
+pub const MATERIAL_LIBRARY_PATH: &str = "materials/library.materials.ron";
+
+pub struct MaterialLibraryPlugin;
+
+impl Plugin for MaterialLibraryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<MaterialLibraryAsset>()
+            .init_asset_loader::<MaterialLibraryLoader>()
+            .register_type::<LibraryMaterial>()
+            .add_systems(Startup, load_material_library)
+            .add_systems(
+                Update,
+                (build_material_library, apply_library_materials).chain(),
+            );
+    }
+}
+
+/// A color in the material library.
+#[derive(Deserialize, Clone, Debug)]
+pub enum ColorDef {
+    Srgb(f32, f32, f32),
+    Srgba(f32, f32, f32, f32),
+    /// A named color of the library palette.
+    Palette(String),
+    /// The color of the entity's [`Team`], white when it has none.
+    Team,
+}
+
+impl ColorDef {
+    fn resolve(&self, library: &MaterialLibraryAsset, team: Option<&str>) -> Color {
+        let srgb = |&(red, green, blue): &(f32, f32, f32)| Color::srgb(red, green, blue);
+
+        match self {
+            ColorDef::Srgb(red, green, blue) => Color::srgb(*red, *green, *blue),
+            ColorDef::Srgba(red, green, blue, alpha) => Color::srgba(*red, *green, *blue, *alpha),
+            ColorDef::Palette(name) => library.palette.get(name).map(srgb).unwrap_or_else(|| {
+                warn!("Unknown palette color {name}");
+                Color::WHITE
+            }),
+            ColorDef::Team => team
+                .and_then(|team| library.teams.get(team))
+                .map(srgb)
+                .unwrap_or(Color::WHITE),
+        }
+    }
+}
+
+/// A named material of the library, unset fields keep the [`ToonMaterialBuilder`] defaults.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ToonMaterialDef {
+    pub base_color: ColorDef,
+    #[serde(default)]
+    pub base_color_texture: Option<String>,
+    #[serde(default)]
+    pub quantize_steps: Option<u32>,
+    #[serde(default)]
+    pub shadow_color: Option<ColorDef>,
+    #[serde(default)]
+    pub highlight_color: Option<ColorDef>,
+    #[serde(default)]
+    pub rim_color: Option<ColorDef>,
+    #[serde(default)]
+    pub rim_threshold: Option<f32>,
+    #[serde(default)]
+    pub specular_color: Option<ColorDef>,
+    #[serde(default)]
+    pub specular_threshold: Option<f32>,
+    #[serde(default)]
+    pub glossiness: Option<f32>,
+    #[serde(default)]
+    pub outline_color: Option<ColorDef>,
+    #[serde(default)]
+    pub outline_thickness: Option<f32>,
+    #[serde(default)]
+    pub ramp_texture: Option<String>,
+    #[serde(skip)]
+    base_color_texture_handle: Option<Handle<Image>>,
+    #[serde(skip)]
+    ramp_texture_handle: Option<Handle<Image>>,
+}
+
+impl ToonMaterialDef {
+    /// Whether the material has one variant per team.
+    fn uses_team(&self) -> bool {
+        [
+            Some(&self.base_color),
+            self.shadow_color.as_ref(),
+            self.highlight_color.as_ref(),
+            self.rim_color.as_ref(),
+            self.specular_color.as_ref(),
+            self.outline_color.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|color| matches!(color, ColorDef::Team))
+    }
+
+    fn builder(&self, library: &MaterialLibraryAsset, team: Option<&str>) -> ToonMaterialBuilder {
+        let color = |color: &ColorDef| color.resolve(library, team);
+
+        let mut builder = ToonMaterialBuilder::new(color(&self.base_color));
+        if let Some(texture) = &self.base_color_texture_handle {
+            builder = builder.base_color_texture(texture.clone());
+        }
+        if let Some(steps) = self.quantize_steps {
+            builder = builder.quantize_steps(steps);
+        }
+        if let Some(shadow_color) = &self.shadow_color {
+            builder = builder.shadow_color(color(shadow_color));
+        }
+        if let Some(highlight_color) = &self.highlight_color {
+            builder = builder.highlight_color(color(highlight_color));
+        }
+        if let Some(rim_color) = &self.rim_color {
+            builder = builder.rim_color(color(rim_color));
+        }
+        if let Some(threshold) = self.rim_threshold {
+            builder = builder.rim_threshold(threshold);
+        }
+        if let Some(specular_color) = &self.specular_color {
+            builder = builder.specular_color(color(specular_color));
+        }
+        if let Some(threshold) = self.specular_threshold {
+            builder = builder.specular_threshold(threshold);
+        }
+        if let Some(glossiness) = self.glossiness {
+            builder = builder.glossiness(glossiness);
+        }
+        if let Some(outline_color) = &self.outline_color {
+            builder = builder.outline_color(color(outline_color));
+        }
+        if let Some(thickness) = self.outline_thickness {
+            builder = builder.outline_thickness(thickness);
+        }
+        if let Some(texture) = &self.ramp_texture_handle {
+            builder = builder.ramp_texture(texture.clone());
+        }
+        builder
+    }
+}
+
+/// Named toon materials and the palette they are colored from, see
+/// `assets/materials/library.materials.ron`.
+#[derive(Asset, TypePath, Deserialize, Clone, Debug)]
+pub struct MaterialLibraryAsset {
+    /// Named sRGB colors shared by the materials.
+    #[serde(default)]
+    pub palette: HashMap<String, (f32, f32, f32)>,
+    /// The sRGB color of each team.
+    #[serde(default)]
+    pub teams: HashMap<String, (f32, f32, f32)>,
+    pub materials: HashMap<String, ToonMaterialDef>,
+}
+
+#[derive(Default)]
+pub struct MaterialLibraryLoader;
+
+#[non_exhaustive]
+#[derive(Debug, Error)]
+pub enum MaterialLibraryLoaderError {
+    #[error("Could not read the material library: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse the material library: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for MaterialLibraryLoader {
+    type Asset = MaterialLibraryAsset;
+    type Settings = ();
+    type Error = MaterialLibraryLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut library: MaterialLibraryAsset = ron::de::from_bytes(&bytes)?;
+
+        for material in library.materials.values_mut() {
+            material.base_color_texture_handle = material
+                .base_color_texture
+                .clone()
+                .map(|path| load_context.load(path));
+            material.ramp_texture_handle = material
+                .ramp_texture
+                .clone()
+                .map(|path| load_context.load(path));
+        }
+
+        Ok(library)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["materials.ron"]
+    }
+}
+
+/// The materials built from the [`MaterialLibraryAsset`].
+///
+/// The handles are kept when the library is reloaded so that entities using
+/// them are updated in place.
+#[derive(Resource)]
+pub struct MaterialLibrary {
+    handle: Handle<MaterialLibraryAsset>,
+    materials: HashMap<(String, Option<String>), Handle<ToonMaterial>>,
+}
+
+impl MaterialLibrary {
+    /// The named material, in the colors of `team` if it has team variants.
+    pub fn get(&self, name: &str, team: Option<&str>) -> Option<Handle<ToonMaterial>> {
+        self.materials
+            .get(&(name.to_string(), team.map(str::to_string)))
+            .or_else(|| self.materials.get(&(name.to_string(), None)))
+            .cloned()
+    }
+}
+
+/// Gives the entity the named material of the [`MaterialLibrary`], colored by its [`Team`].
+#[derive(Component, Reflect, Clone, Debug, Default)]
+#[reflect(Component, Default)]
+pub struct LibraryMaterial(pub String);
+
+impl LibraryMaterial {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self(name.into())
+    }
+}
+
+fn load_material_library(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading_assets: ResMut<LoadingAssets>,
+) {
+    commands.insert_resource(MaterialLibrary {
+        handle: loading_assets.add(asset_server.load(MATERIAL_LIBRARY_PATH)),
+        materials: HashMap::default(),
+    });
+}
+
+/// (Re)builds the library materials whenever the asset is loaded or modified.
+fn build_material_library(
+    mut asset_events: EventReader<AssetEvent<MaterialLibraryAsset>>,
+    library_assets: Res<Assets<MaterialLibraryAsset>>,
+    mut library: ResMut<MaterialLibrary>,
+    mut materials: ResMut<Assets<ToonMaterial>>,
+) {
+    let library_id = library.handle.id();
+    let reloaded = asset_events.read().fold(false, |reloaded, event| {
+        reloaded || event.is_loaded_with_dependencies(library_id) || event.is_modified(library_id)
+    });
+    if !reloaded {
+        return;
+    }
+
+    let Some(asset) = library_assets.get(library_id) else {
+        return;
+    };
+
+    for (name, def) in &asset.materials {
+        let teams: Vec<Option<&str>> = if def.uses_team() {
+            std::iter::once(None)
+                .chain(asset.teams.keys().map(|team| Some(team.as_str())))
+                .collect()
+        } else {
+            vec![None]
+        };
+
+        for team in teams {
+            let material = def.builder(asset, team).build();
+            let key = (name.clone(), team.map(str::to_string));
+            match library.materials.get(&key) {
+                Some(handle) => materials.insert(handle.id(), material),
+                None => {
+                    let handle = materials.add(material);
+                    library.materials.insert(key, handle);
+                }
+            }
+        }
+    }
+}
+
+fn apply_library_materials(
+    library: Res<MaterialLibrary>,
+    mut query: Query<(
+        Ref<LibraryMaterial>,
+        Option<Ref<Team>>,
+        &mut Handle<ToonMaterial>,
+    )>,
+) {
+    for (library_material, team, mut material) in &mut query {
+        let team_changed = team.as_ref().is_some_and(|team| team.is_changed());
+        if !library.is_changed() && !library_material.is_changed() && !team_changed {
+            continue;
+        }
+
+        match library.get(
+            &library_material.0,
+            team.as_ref().map(|team| team.0.as_str()),
+        ) {
+            Some(handle) => *material = handle,
+            None if library.materials.is_empty() => {}
+            None => warn!("Unknown library material {}", library_material.0),
+        }
+    }
+}
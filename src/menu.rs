@@ -0,0 +1,139 @@
+use bevy::{app::AppExit, prelude::*};
+use sickle_ui::prelude::*;
+
+use crate::app_state::AppState;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::MainMenu), spawn_main_menu)
+            .add_systems(OnEnter(AppState::Loading), spawn_loading_screen)
+            .add_systems(OnEnter(AppState::Paused), spawn_pause_menu)
+            .add_systems(OnEnter(AppState::GameOver), spawn_game_over_screen)
+            .add_systems(Update, handle_menu_buttons);
+    }
+}
+
+/// The action performed when a menu button is pressed.
+#[derive(Component, Clone, Copy, Debug)]
+pub enum MenuButton {
+    Play,
+    Resume,
+    Retry,
+    MainMenu,
+    Quit,
+}
+
+fn spawn_main_menu(mut commands: Commands, theme_data: Res<ThemeData>) {
+    spawn_screen(
+        &mut commands,
+        &theme_data,
+        AppState::MainMenu,
+        "Artificer",
+        &[("Play", MenuButton::Play), ("Quit", MenuButton::Quit)],
+    );
+}
+
+fn spawn_loading_screen(mut commands: Commands, theme_data: Res<ThemeData>) {
+    spawn_screen(
+        &mut commands,
+        &theme_data,
+        AppState::Loading,
+        "Loading...",
+        &[],
+    );
+}
+
+fn spawn_pause_menu(mut commands: Commands, theme_data: Res<ThemeData>) {
+    spawn_screen(
+        &mut commands,
+        &theme_data,
+        AppState::Paused,
+        "Paused",
+        &[
+            ("Resume", MenuButton::Resume),
+            ("Main Menu", MenuButton::MainMenu),
+            ("Quit", MenuButton::Quit),
+        ],
+    );
+}
+
+fn spawn_game_over_screen(mut commands: Commands, theme_data: Res<ThemeData>) {
+    spawn_screen(
+        &mut commands,
+        &theme_data,
+        AppState::GameOver,
+        "Game Over",
+        &[
+            ("Retry", MenuButton::Retry),
+            ("Main Menu", MenuButton::MainMenu),
+        ],
+    );
+}
+
+/// Spawns a full screen column with a title and a list of buttons that lives
+/// as long as `state` is active.
+fn spawn_screen(
+    commands: &mut Commands,
+    theme_data: &ThemeData,
+    state: AppState,
+    title: &str,
+    buttons: &[(&str, MenuButton)],
+) {
+    let colors = theme_data.colors();
+    let spacing = theme_data.spacing;
+
+    commands
+        .ui_builder(UiRoot)
+        .column(|column| {
+            column.label(LabelConfig {
+                label: title.into(),
+                ..default()
+            });
+
+            for (label, button) in buttons {
+                column
+                    .container((ButtonBundle::default(), *button), |container| {
+                        container.label(LabelConfig {
+                            label: (*label).into(),
+                            ..default()
+                        });
+                    })
+                    .style()
+                    .width(Val::Px(200.))
+                    .justify_content(JustifyContent::Center)
+                    .margin(UiRect::top(Val::Px(spacing.gaps.medium)))
+                    .padding(UiRect::all(Val::Px(spacing.gaps.small)))
+                    .background_color(colors.accent(Accent::Primary));
+            }
+        })
+        .insert(StateScoped(state))
+        .style()
+        .width(Val::Percent(100.))
+        .height(Val::Percent(100.))
+        .justify_content(JustifyContent::Center)
+        .align_items(AlignItems::Center)
+        .background_color(colors.container(Container::SurfaceMid).with_alpha(0.8));
+}
+
+fn handle_menu_buttons(
+    q_buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    for (interaction, button) in &q_buttons {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        match button {
+            MenuButton::Play => next_state.set(AppState::Loading),
+            MenuButton::Resume | MenuButton::Retry => next_state.set(AppState::InGame),
+            MenuButton::MainMenu => next_state.set(AppState::MainMenu),
+            MenuButton::Quit => {
+                exit.send(AppExit::Success);
+            }
+        }
+    }
+}
@@ -0,0 +1,582 @@
+use bevy::{
+    core_pipeline::{
+        bloom::{BloomCompositeMode, BloomPrefilterSettings, BloomSettings},
+        core_3d::graph::{Core3d, Node3d},
+        fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+        prepass::{DepthPrepass, ViewPrepassTextures},
+    },
+    ecs::query::QueryItem,
+    prelude::*,
+    render::{
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
+        },
+        render_asset::RenderAssets,
+        render_graph::{
+            NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+        },
+        render_resource::{
+            binding_types::{
+                sampler, texture_2d, texture_depth_2d, texture_depth_2d_multisampled,
+                uniform_buffer,
+            },
+            *,
+        },
+        renderer::{RenderContext, RenderDevice},
+        texture::{BevyDefault, FallbackImage, GpuImage},
+        view::{ExtractedView, ViewTarget},
+        Render, RenderApp, RenderSet,
+    },
+};
+
+use crate::{app_state::Gameplay, game_management::DamageEvent, CharacterController};
+
+pub struct PostProcessPlugin;
+
+impl Plugin for PostProcessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((
+            ExtractComponentPlugin::<PostProcessSettings>::default(),
+            UniformComponentPlugin::<PostProcessUniform>::default(),
+        ))
+        .add_systems(Update, sync_bloom)
+        .add_systems(Update, update_damage_vignette.run_if(in_state(Gameplay)));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .init_resource::<SpecializedRenderPipelines<PostProcessPipeline>>()
+            .add_systems(
+                Render,
+                prepare_post_process_pipelines.in_set(RenderSet::Prepare),
+            )
+            .add_render_graph_node::<ViewNodeRunner<PostProcessNode>>(Core3d, PostProcessLabel)
+            .add_render_graph_edges(
+                Core3d,
+                (
+                    Node3d::Tonemapping,
+                    PostProcessLabel,
+                    Node3d::EndMainPassPostProcessing,
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app.init_resource::<PostProcessPipeline>();
+    }
+}
+
+/// The post processing effects applied to a camera, each one disabled when `None`.
+#[derive(Component, Clone, Debug, Default)]
+pub struct PostProcessSettings {
+    pub bloom: Option<BloomSettings>,
+    pub color_grading: Option<LutColorGrading>,
+    pub pixelation: Option<Pixelation>,
+    pub vignette: Option<Vignette>,
+    pub outline: Option<ScreenSpaceOutline>,
+}
+
+impl PostProcessSettings {
+    /// Bloom that only picks up the brightest bands and highlights of the toon material.
+    pub fn toon_bloom() -> BloomSettings {
+        BloomSettings {
+            intensity: 0.2,
+            prefilter_settings: BloomPrefilterSettings {
+                threshold: 0.9,
+                threshold_softness: 0.1,
+            },
+            composite_mode: BloomCompositeMode::Additive,
+            ..BloomSettings::NATURAL
+        }
+    }
+}
+
+/// Color grading from a strip LUT image of `n` slices of `n` x `n` pixels,
+/// the blue channel selecting the slice.
+#[derive(Clone, Debug)]
+pub struct LutColorGrading {
+    pub lut: Handle<Image>,
+    /// How much of the graded color replaces the original one, from 0 to 1.
+    pub intensity: f32,
+}
+
+/// Renders the image with larger pixels and fewer colors.
+#[derive(Clone, Copy, Debug)]
+pub struct Pixelation {
+    /// The size of the rendered pixels in screen pixels.
+    pub pixel_size: f32,
+    /// The number of levels per color channel, 0 keeps the original colors.
+    pub color_levels: u32,
+    /// The strength of the ordered dithering applied before reducing the colors, from 0 to 1.
+    pub dither_strength: f32,
+}
+
+impl Default for Pixelation {
+    fn default() -> Self {
+        Self {
+            pixel_size: 3.0,
+            color_levels: 8,
+            dither_strength: 0.5,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Vignette {
+    pub color: Color,
+    pub intensity: f32,
+    /// How far from the screen corners the vignette fades in, from 0 to 1.
+    pub smoothness: f32,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            color: Color::BLACK,
+            intensity: 0.3,
+            smoothness: 0.6,
+        }
+    }
+}
+
+/// Outlines drawn where the depth buffer changes abruptly, requires a [`DepthPrepass`].
+#[derive(Clone, Copy, Debug)]
+pub struct ScreenSpaceOutline {
+    /// The color of the outline, its alpha being the opacity.
+    pub color: Color,
+    /// The outline thickness in pixels.
+    pub thickness: u32,
+    /// The relative view depth difference between neighbouring pixels drawn as an outline.
+    pub depth_threshold: f32,
+}
+
+impl Default for ScreenSpaceOutline {
+    fn default() -> Self {
+        Self {
+            color: Color::BLACK,
+            thickness: 1,
+            depth_threshold: 0.1,
+        }
+    }
+}
+
+/// A vignette flashed on the camera when the player takes damage.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct DamageVignette {
+    pub color: Color,
+    /// The intensity right after taking damage.
+    pub intensity: f32,
+    /// The time it takes to fade out, in seconds.
+    pub duration: f32,
+    /// The current fade, from 1 when hit to 0.
+    pub fade: f32,
+}
+
+impl Default for DamageVignette {
+    fn default() -> Self {
+        Self {
+            color: Color::srgb(0.6, 0.0, 0.0),
+            intensity: 0.8,
+            duration: 0.5,
+            fade: 0.0,
+        }
+    }
+}
+
+/// The GPU representation of [`PostProcessSettings`].
+#[derive(Component, ShaderType, Clone, Copy, Default)]
+pub struct PostProcessUniform {
+    lut_intensity: f32,
+    pixel_size: f32,
+    color_levels: f32,
+    dither_strength: f32,
+    vignette_color: LinearRgba,
+    vignette_intensity: f32,
+    vignette_smoothness: f32,
+    outline_color: LinearRgba,
+    outline_thickness: f32,
+    outline_depth_threshold: f32,
+}
+
+/// The LUT of the extracted view, if color grading is enabled.
+#[derive(Component, Clone)]
+pub struct PostProcessLut(Option<Handle<Image>>);
+
+impl ExtractComponent for PostProcessSettings {
+    type QueryData = (&'static Self, Option<&'static DamageVignette>);
+    type QueryFilter = ();
+    type Out = (PostProcessUniform, PostProcessLut);
+
+    fn extract_component(
+        (settings, damage_vignette): QueryItem<'_, Self::QueryData>,
+    ) -> Option<Self::Out> {
+        let mut uniform = PostProcessUniform::default();
+
+        if let Some(color_grading) = &settings.color_grading {
+            uniform.lut_intensity = color_grading.intensity;
+        }
+        if let Some(pixelation) = &settings.pixelation {
+            uniform.pixel_size = pixelation.pixel_size;
+            uniform.color_levels = pixelation.color_levels as f32;
+            uniform.dither_strength = pixelation.dither_strength;
+        }
+        if let Some(vignette) = &settings.vignette {
+            uniform.vignette_color = vignette.color.to_linear();
+            uniform.vignette_intensity = vignette.intensity;
+            uniform.vignette_smoothness = vignette.smoothness;
+        }
+        if let Some(damage_vignette) =
+            damage_vignette.filter(|damage| damage.fade * damage.intensity > 0.0)
+        {
+            // Blend towards the damage color as it takes over the base vignette
+            let damage_intensity = damage_vignette.intensity * damage_vignette.fade;
+            let total = uniform.vignette_intensity + damage_intensity;
+            uniform.vignette_color = uniform
+                .vignette_color
+                .mix(&damage_vignette.color.to_linear(), damage_intensity / total);
+            uniform.vignette_intensity = total;
+            uniform.vignette_smoothness = uniform.vignette_smoothness.max(0.8);
+        }
+        if let Some(outline) = &settings.outline {
+            uniform.outline_color = outline.color.to_linear();
+            uniform.outline_thickness = outline.thickness as f32;
+            uniform.outline_depth_threshold = outline.depth_threshold;
+        }
+
+        let lut = settings
+            .color_grading
+            .as_ref()
+            .map(|color_grading| color_grading.lut.clone());
+
+        Some((uniform, PostProcessLut(lut)))
+    }
+}
+
+/// Whether a camera rendered in HDR before [`sync_bloom`] turned it on for bloom.
+#[derive(Component, Clone, Copy, Debug)]
+struct HdrBeforeBloom(bool);
+
+/// Adds Bevy's bloom to cameras that enable it, which requires HDR, and restores
+/// their HDR setting once it is disabled.
+fn sync_bloom(
+    mut commands: Commands,
+    mut query: Query<
+        (
+            Entity,
+            &PostProcessSettings,
+            &mut Camera,
+            Option<&HdrBeforeBloom>,
+        ),
+        Changed<PostProcessSettings>,
+    >,
+) {
+    for (entity, settings, mut camera, hdr_before_bloom) in &mut query {
+        match &settings.bloom {
+            Some(bloom) => {
+                commands.entity(entity).insert(bloom.clone());
+                if hdr_before_bloom.is_none() {
+                    commands.entity(entity).insert(HdrBeforeBloom(camera.hdr));
+                }
+                camera.hdr = true;
+            }
+            None => {
+                commands
+                    .entity(entity)
+                    .remove::<(BloomSettings, HdrBeforeBloom)>();
+                if let Some(HdrBeforeBloom(hdr)) = hdr_before_bloom {
+                    camera.hdr = *hdr;
+                }
+            }
+        }
+    }
+}
+
+/// Flashes the damage vignette when the player is hurt and fades it out.
+fn update_damage_vignette(
+    time: Res<Time>,
+    mut damage_events: EventReader<DamageEvent>,
+    q_player: Query<Entity, With<CharacterController>>,
+    mut q_vignettes: Query<&mut DamageVignette>,
+) {
+    let hurt = damage_events
+        .read()
+        .any(|event| q_player.contains(event.target));
+
+    for mut vignette in &mut q_vignettes {
+        if hurt {
+            vignette.fade = 1.0;
+        } else if vignette.fade > 0.0 {
+            vignette.fade = (vignette.fade - time.delta_seconds() / vignette.duration).max(0.0);
+        }
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct PostProcessLabel;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PostProcessPipelineKey {
+    hdr: bool,
+    /// Whether the depth prepass is bound for the outline, and if it is multisampled.
+    depth: Option<bool>,
+}
+
+#[derive(Resource)]
+struct PostProcessPipeline {
+    layout: BindGroupLayout,
+    depth_layout: BindGroupLayout,
+    multisampled_depth_layout: BindGroupLayout,
+    screen_sampler: Sampler,
+    lut_sampler: Sampler,
+    shader: Handle<Shader>,
+}
+
+impl PostProcessPipeline {
+    fn layout(&self, depth: Option<bool>) -> &BindGroupLayout {
+        match depth {
+            None => &self.layout,
+            Some(false) => &self.depth_layout,
+            Some(true) => &self.multisampled_depth_layout,
+        }
+    }
+}
+
+impl FromWorld for PostProcessPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let render_device = world.resource::<RenderDevice>();
+
+        let screen = texture_2d(TextureSampleType::Float { filterable: true });
+        let filtering = sampler(SamplerBindingType::Filtering);
+        let settings = uniform_buffer::<PostProcessUniform>(true);
+
+        let layout = render_device.create_bind_group_layout(
+            "post_process_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (screen, filtering, settings, screen, filtering),
+            ),
+        );
+        let depth_layout = render_device.create_bind_group_layout(
+            "post_process_depth_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    screen,
+                    filtering,
+                    settings,
+                    screen,
+                    filtering,
+                    texture_depth_2d(),
+                ),
+            ),
+        );
+        let multisampled_depth_layout = render_device.create_bind_group_layout(
+            "post_process_multisampled_depth_bind_group_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    screen,
+                    filtering,
+                    settings,
+                    screen,
+                    filtering,
+                    texture_depth_2d_multisampled(),
+                ),
+            ),
+        );
+
+        let screen_sampler = render_device.create_sampler(&SamplerDescriptor::default());
+        let lut_sampler = render_device.create_sampler(&SamplerDescriptor {
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        });
+
+        Self {
+            layout,
+            depth_layout,
+            multisampled_depth_layout,
+            screen_sampler,
+            lut_sampler,
+            shader: world.load_asset("shaders/post_processing.wgsl"),
+        }
+    }
+}
+
+impl SpecializedRenderPipeline for PostProcessPipeline {
+    type Key = PostProcessPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut shader_defs = Vec::new();
+        if let Some(multisampled) = key.depth {
+            shader_defs.push("DEPTH_OUTLINE".into());
+            if multisampled {
+                shader_defs.push("MULTISAMPLED".into());
+            }
+        }
+
+        RenderPipelineDescriptor {
+            label: Some("post_process_pipeline".into()),
+            layout: vec![self.layout(key.depth).clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: self.shader.clone(),
+                shader_defs,
+                entry_point: "fragment".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: if key.hdr {
+                        ViewTarget::TEXTURE_FORMAT_HDR
+                    } else {
+                        TextureFormat::bevy_default()
+                    },
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: vec![],
+        }
+    }
+}
+
+#[derive(Component)]
+struct PostProcessPipelineId {
+    id: CachedRenderPipelineId,
+    key: PostProcessPipelineKey,
+}
+
+fn prepare_post_process_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<PostProcessPipeline>>,
+    post_process_pipeline: Res<PostProcessPipeline>,
+    msaa: Res<Msaa>,
+    views: Query<(
+        Entity,
+        &ExtractedView,
+        &PostProcessUniform,
+        Has<DepthPrepass>,
+    )>,
+) {
+    for (entity, view, uniform, depth_prepass) in &views {
+        let key = PostProcessPipelineKey {
+            hdr: view.hdr,
+            depth: (depth_prepass && uniform.outline_thickness > 0.0).then(|| msaa.samples() > 1),
+        };
+        let id = pipelines.specialize(&pipeline_cache, &post_process_pipeline, key);
+        commands
+            .entity(entity)
+            .insert(PostProcessPipelineId { id, key });
+    }
+}
+
+#[derive(Default)]
+struct PostProcessNode;
+
+impl ViewNode for PostProcessNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static PostProcessPipelineId,
+        &'static PostProcessLut,
+        &'static DynamicUniformIndex<PostProcessUniform>,
+        Option<&'static ViewPrepassTextures>,
+    );
+
+    fn run(
+        &self,
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, pipeline_id, lut, settings_index, prepass_textures): QueryItem<
+            Self::ViewQuery,
+        >,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let post_process_pipeline = world.resource::<PostProcessPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(pipeline_id.id) else {
+            return Ok(());
+        };
+
+        let settings_uniforms = world.resource::<ComponentUniforms<PostProcessUniform>>();
+        let Some(settings_binding) = settings_uniforms.uniforms().binding() else {
+            return Ok(());
+        };
+
+        // Without a LUT the fallback image is bound and the intensity is 0
+        let lut_view = match lut
+            .0
+            .as_ref()
+            .and_then(|lut| world.resource::<RenderAssets<GpuImage>>().get(lut))
+        {
+            Some(gpu_image) => &gpu_image.texture_view,
+            None => &world.resource::<FallbackImage>().d2.texture_view,
+        };
+
+        let depth_view = match pipeline_id.key.depth {
+            Some(_) => {
+                let Some(depth_view) = prepass_textures.and_then(|textures| textures.depth_view())
+                else {
+                    return Ok(());
+                };
+                Some(depth_view)
+            }
+            None => None,
+        };
+
+        let post_process = view_target.post_process_write();
+        let layout = post_process_pipeline.layout(pipeline_id.key.depth);
+
+        let bind_group = match depth_view {
+            Some(depth_view) => render_context.render_device().create_bind_group(
+                "post_process_bind_group",
+                layout,
+                &BindGroupEntries::sequential((
+                    post_process.source,
+                    &post_process_pipeline.screen_sampler,
+                    settings_binding.clone(),
+                    lut_view,
+                    &post_process_pipeline.lut_sampler,
+                    depth_view,
+                )),
+            ),
+            None => render_context.render_device().create_bind_group(
+                "post_process_bind_group",
+                layout,
+                &BindGroupEntries::sequential((
+                    post_process.source,
+                    &post_process_pipeline.screen_sampler,
+                    settings_binding.clone(),
+                    lut_view,
+                    &post_process_pipeline.lut_sampler,
+                )),
+            ),
+        };
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some("post_process_pass"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: post_process.destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[settings_index.index()]);
+        render_pass.draw(0..3, 0..1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+use avian3d::{
+    collision::{CollisionLayers, CollisionStarted, Collisions},
+    dynamics::rigid_body::{LinearVelocity, RigidBody},
+    math::Vector3,
+    prelude::{Collider, RayCaster, RayHits, Rotation, Sensor},
+};
+use bevy::{ecs::query::QuerySingleError, prelude::*};
+
+use crate::{app_state::AppState, CharacterController, MainCamera};
+use crate::{
+    game_management::{DamageEvent, GameLayer, Health},
+    material_library::LibraryMaterial,
+    toon_material::ToonMaterial,
+    vfx::{SpawnVfx, VfxKind},
+};
+
+/// Fires the player's weapon and moves and resolves the projectiles while in game.
+pub struct ProjectilePlugin;
+
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Projectile>()
+            .register_type::<Weapon>()
+            .add_systems(
+                Update,
+                (
+                    update_weapons,
+                    mouse_input,
+                    projectile_hits,
+                    update_projectiles,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(Update, restore_projectiles);
+    }
+}
+
+/// Base projectile component marker
+#[derive(Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct Projectile {
+    pub direction: Vector3,
+    pub speed: f32,
+    pub lifetime: f32,
+    pub damage: f32,
+}
+
+impl Default for Projectile {
+    fn default() -> Self {
+        Self {
+            direction: Vector3::new(1., 1., 1.),
+            speed: 10.,
+            lifetime: 1.,
+            damage: 10.,
+        }
+    }
+}
+
+/// The ammunition and fire rate of an entity shooting projectiles.
+#[derive(Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct Weapon {
+    pub ammo: u32,
+    pub max_ammo: u32,
+    /// The time to wait between two shots.
+    pub cooldown: Timer,
+}
+
+impl Default for Weapon {
+    fn default() -> Self {
+        Self {
+            ammo: 30,
+            max_ammo: 30,
+            cooldown: Timer::from_seconds(0.15, TimerMode::Once),
+        }
+    }
+}
+
+/// Sends [`MovementAction`] events based on keyboard input.
+pub fn mouse_input(
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut query: Query<(&Transform, &mut Weapon), With<CharacterController>>,
+    query_camera: Query<&Transform, With<MainCamera>>,
+    query_ray: Query<(&RayCaster, &RayHits), With<MainCamera>>,
+    mut vfx_events: EventWriter<SpawnVfx>,
+) {
+    if !mouse_input.pressed(MouseButton::Left) {
+        return;
+    }
+    let mut pos = Vec3::ZERO;
+    match query.get_single_mut() {
+        Ok((transform, mut weapon)) => {
+            if !weapon.cooldown.finished() || weapon.ammo == 0 {
+                return;
+            }
+            weapon.ammo -= 1;
+            weapon.cooldown.reset();
+            pos = transform.translation;
+        }
+        Err(QuerySingleError::NoEntities(_)) => {
+            println!("Error: There is no player!");
+        }
+        Err(QuerySingleError::MultipleEntities(_)) => {
+            println!("Error: There is more than one player!");
+        }
+    }
+
+    let Ok(cam_transform) = query_camera.get_single() else {
+        return;
+    };
+    let speed = 10f32;
+
+    let max_aim_distance = 1000f32;
+    let mut hit_location = cam_transform.translation + cam_transform.forward() * max_aim_distance;
+
+    if !query_ray.is_empty() {
+        let (ray, hits) = query_ray.single();
+        let hit = hits.iter_sorted().next();
+        if !hit.is_none() {
+            let impact_time = hit.unwrap().time_of_impact;
+            hit_location = ray.origin + *ray.direction * impact_time;
+        }
+    }
+
+    let dir = hit_location - pos;
+
+    vfx_events.send(
+        SpawnVfx::new(
+            VfxKind::MuzzleFlash,
+            pos + Vec3::Y * 0.1 + dir.normalize() * 0.5,
+        )
+        .with_direction(dir),
+    );
+
+    commands.spawn((
+        Projectile::default(),
+        LinearVelocity {
+            0: bevy::prelude::Vec3::from(dir.normalize()) * speed,
+        },
+        projectile_body(Transform::from_xyz(pos.x, pos.y + 0.1, pos.z), &mut meshes),
+    ));
+}
+
+/// Rebuilds the physics body and visuals of projectiles restored from a save game.
+pub fn restore_projectiles(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    query: Query<(Entity, &Transform), (With<Projectile>, Without<Collider>)>,
+) {
+    for (entity, transform) in &query {
+        commands
+            .entity(entity)
+            .insert(projectile_body(*transform, &mut meshes));
+    }
+}
+
+/// The components shared by every projectile besides its [`Projectile`] state.
+fn projectile_body(transform: Transform, meshes: &mut Assets<Mesh>) -> impl Bundle {
+    (
+        RigidBody::Kinematic,
+        Collider::cuboid(1.0, 1.0, 1.0),
+        CollisionLayers::new(
+            GameLayer::Projectile,
+            [GameLayer::Enemy, GameLayer::Ground, GameLayer::Default],
+        ),
+        MaterialMeshBundle::<ToonMaterial> {
+            mesh: meshes.add(Sphere::default()),
+            transform,
+            ..default()
+        },
+        LibraryMaterial::new("projectile"),
+    )
+}
+
+/// Ticks weapon cooldowns and refills ammunition when R is pressed.
+pub fn update_weapons(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut query: Query<&mut Weapon>,
+    time: Res<Time>,
+) {
+    for mut weapon in &mut query {
+        weapon.cooldown.tick(time.delta());
+        if keyboard_input.just_pressed(KeyCode::KeyR) {
+            weapon.ammo = weapon.max_ammo;
+        }
+    }
+}
+
+/// Damages entities with [`Health`] hit by a projectile and despawns the projectile.
+#[allow(clippy::too_many_arguments)]
+pub fn projectile_hits(
+    mut commands: Commands,
+    mut collision_events: EventReader<CollisionStarted>,
+    mut damage_events: EventWriter<DamageEvent>,
+    mut vfx_events: EventWriter<SpawnVfx>,
+    collisions: Res<Collisions>,
+    query: Query<(&Projectile, &Transform, &LinearVelocity)>,
+    query_health: Query<(), With<Health>>,
+    query_rotation: Query<&Rotation>,
+    query_sensors: Query<(), With<Sensor>>,
+) {
+    for CollisionStarted(entity1, entity2) in collision_events.read() {
+        for (projectile_entity, target) in [(*entity1, *entity2), (*entity2, *entity1)] {
+            let Ok((projectile, transform, linear_velocity)) = query.get(projectile_entity) else {
+                continue;
+            };
+            // Projectiles fly through trigger volumes
+            if query_sensors.contains(target) {
+                continue;
+            }
+
+            // Sparks fly off the surface that was hit, or back towards the shooter
+            let normal = hit_normal(&collisions, &query_rotation, projectile_entity, target)
+                .unwrap_or(-linear_velocity.0);
+            vfx_events.send(
+                SpawnVfx::new(VfxKind::ImpactSparks, transform.translation).with_direction(normal),
+            );
+
+            if query_health.contains(target) {
+                damage_events.send(DamageEvent {
+                    target,
+                    amount: projectile.damage,
+                    source: Some(transform.translation),
+                });
+            }
+
+            commands.entity(projectile_entity).despawn();
+        }
+    }
+}
+
+/// The world space normal of the surface of `target` touched by `projectile`.
+fn hit_normal(
+    collisions: &Collisions,
+    query_rotation: &Query<&Rotation>,
+    projectile: Entity,
+    target: Entity,
+) -> Option<Vec3> {
+    let contacts = collisions.get(projectile, target)?;
+    let manifold = contacts.manifolds.first()?;
+    let rotation = query_rotation.get(target).ok()?;
+
+    // Each normal is expressed in the local space of its entity and points away from it
+    let normal = if contacts.entity1 == target {
+        manifold.normal1
+    } else {
+        manifold.normal2
+    };
+    Some(rotation.mul_vec3(normal))
+}
+
+pub fn update_projectiles(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Projectile, &Transform)>,
+    mut vfx_events: EventWriter<SpawnVfx>,
+    time: Res<Time>,
+) {
+    for (entity, mut projectile, transform) in &mut query {
+        projectile.lifetime -= time.delta_seconds();
+        if projectile.lifetime <= 0.0 {
+            commands.entity(entity).despawn();
+        } else {
+            vfx_events.send(SpawnVfx::new(
+                VfxKind::ProjectileTrail,
+                transform.translation,
+            ));
+        }
+    }
+}
@@ -0,0 +1,440 @@
+use std::{error::Error, fmt, fs, path::PathBuf};
+
+use avian3d::prelude::*;
+use bevy::{
+    ecs::{entity::EntityHashMap, event::ManualEventReader},
+    prelude::*,
+    reflect::TypeRegistry,
+    scene::serde::{SceneDeserializer, SceneSerializer},
+};
+use serde::{
+    de::{self, DeserializeSeed, IgnoredAny, MapAccess, Visitor},
+    ser::SerializeStruct,
+    Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    app_state::AppState, game_management::Health, interaction::Inventory, projectile::Projectile,
+    CharacterController, Grounded,
+};
+
+/// The version written to new save files. Bump it whenever the saved components change.
+pub const SAVE_VERSION: u32 = 2;
+
+/// The oldest save version that can still be loaded. Version 1 saves predate the
+/// player's [`Inventory`], which they load as empty.
+pub const OLDEST_SAVE_VERSION: u32 = 1;
+
+/// The file used by the quick save and quick load keys.
+pub const QUICKSAVE_PATH: &str = "saves/quicksave.ron";
+
+pub struct SaveGamePlugin;
+
+impl Plugin for SaveGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveGame>()
+            .add_event::<LoadGame>()
+            .add_systems(
+                Update,
+                (
+                    quicksave_input.run_if(in_state(AppState::InGame)),
+                    save_game,
+                    load_game,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// An event requesting the gameplay state to be written to a save file.
+#[derive(Event, Clone)]
+pub struct SaveGame {
+    pub path: PathBuf,
+}
+
+/// An event requesting the gameplay state to be restored from a save file.
+#[derive(Event, Clone)]
+pub struct LoadGame {
+    pub path: PathBuf,
+}
+
+/// Serializes the gameplay state of the world to a versioned RON save file.
+///
+/// The player, live projectiles and dynamic rigid bodies are saved with their
+/// transform, velocities and gameplay components.
+pub fn serialize_save(world: &mut World) -> Result<String, ron::Error> {
+    let mut q_saved =
+        world.query_filtered::<Entity, Or<(With<CharacterController>, With<Projectile>)>>();
+    let mut q_bodies = world.query::<(Entity, &RigidBody)>();
+
+    let mut entities: Vec<Entity> = q_saved.iter(world).collect();
+    entities.extend(
+        q_bodies
+            .iter(world)
+            .filter(|(_, rigid_body)| rigid_body.is_dynamic())
+            .map(|(entity, _)| entity),
+    );
+    entities.sort();
+    entities.dedup();
+
+    let scene = DynamicSceneBuilder::from_world(world)
+        .deny_all_resources()
+        .allow::<Name>()
+        .allow::<Transform>()
+        .allow::<LinearVelocity>()
+        .allow::<AngularVelocity>()
+        .allow::<CharacterController>()
+        .allow::<Grounded>()
+        .allow::<Health>()
+        .allow::<Inventory>()
+        .allow::<Projectile>()
+        .extract_entities(entities.into_iter())
+        .build();
+
+    let type_registry = world.resource::<AppTypeRegistry>().read();
+    ron::ser::to_string_pretty(
+        &SaveFileSerializer {
+            scene: &scene,
+            registry: &type_registry,
+        },
+        ron::ser::PrettyConfig::default(),
+    )
+}
+
+/// Restores the gameplay state of a save file into a running world.
+///
+/// The saved player and named dynamic bodies are written onto their live
+/// counterparts, while live projectiles are replaced by the saved ones.
+pub fn deserialize_save(world: &mut World, contents: &str) -> Result<(), Box<dyn Error>> {
+    let (version, mut scene) = {
+        let type_registry = world.resource::<AppTypeRegistry>().read();
+        let mut deserializer = ron::de::Deserializer::from_str(contents)?;
+        SaveFileDeserializer {
+            registry: &type_registry,
+        }
+        .deserialize(&mut deserializer)?
+    };
+
+    let mut q_projectiles = world.query_filtered::<Entity, With<Projectile>>();
+    let projectiles: Vec<Entity> = q_projectiles.iter(world).collect();
+    for entity in projectiles {
+        world.despawn(entity);
+    }
+
+    let mut q_player = world.query_filtered::<Entity, With<CharacterController>>();
+    let player = q_player.get_single(world).ok();
+    let mut q_bodies = world.query::<(Entity, &Name, &RigidBody)>();
+    let bodies: Vec<(Entity, Name)> = q_bodies
+        .iter(world)
+        .filter(|(_, _, rigid_body)| rigid_body.is_dynamic())
+        .map(|(entity, name, _)| (entity, name.clone()))
+        .collect();
+
+    let mut entity_map = EntityHashMap::default();
+    scene.entities.retain(|saved| {
+        let has = |predicate: fn(&dyn Reflect) -> bool| {
+            saved.components.iter().any(|c| predicate(c.as_ref()))
+        };
+
+        if has(|c| c.represents::<CharacterController>()) {
+            if let Some(player) = player {
+                entity_map.insert(saved.entity, player);
+                return true;
+            }
+            return false;
+        }
+
+        if has(|c| c.represents::<Projectile>()) {
+            return true;
+        }
+
+        let name = saved
+            .components
+            .iter()
+            .find(|c| c.represents::<Name>())
+            .and_then(|c| Name::from_reflect(c.as_ref()));
+        let body = name.and_then(|name| bodies.iter().find(|(_, body)| *body == name));
+        match body {
+            Some((entity, _)) => {
+                entity_map.insert(saved.entity, *entity);
+                true
+            }
+            None => {
+                warn!(
+                    "Dropping saved entity {:?} with no live counterpart",
+                    saved.entity
+                );
+                false
+            }
+        }
+    });
+
+    let type_registry = world.resource::<AppTypeRegistry>().clone();
+    scene.write_to_world_with(world, &mut entity_map, &type_registry)?;
+
+    if version < 2 {
+        if let Some(player) = player {
+            world.entity_mut(player).insert(Inventory::default());
+        }
+    }
+    Ok(())
+}
+
+/// Saves and loads the quick save with F5 and F9.
+fn quicksave_input(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut save_events: EventWriter<SaveGame>,
+    mut load_events: EventWriter<LoadGame>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        save_events.send(SaveGame {
+            path: QUICKSAVE_PATH.into(),
+        });
+    }
+
+    if keyboard_input.just_pressed(KeyCode::F9) {
+        load_events.send(LoadGame {
+            path: QUICKSAVE_PATH.into(),
+        });
+    }
+}
+
+fn save_game(world: &mut World, mut reader: Local<ManualEventReader<SaveGame>>) {
+    let requests: Vec<SaveGame> = reader
+        .read(world.resource::<Events<SaveGame>>())
+        .cloned()
+        .collect();
+
+    for request in requests {
+        let result = serialize_save(world)
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|contents| {
+                if let Some(parent) = request.path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&request.path, contents)?;
+                Ok(())
+            });
+
+        match result {
+            Ok(()) => info!("Saved game to {}", request.path.display()),
+            Err(error) => error!("Failed to save game to {}: {error}", request.path.display()),
+        }
+    }
+}
+
+fn load_game(world: &mut World, mut reader: Local<ManualEventReader<LoadGame>>) {
+    let requests: Vec<LoadGame> = reader
+        .read(world.resource::<Events<LoadGame>>())
+        .cloned()
+        .collect();
+
+    for request in requests {
+        let result = fs::read_to_string(&request.path)
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|contents| deserialize_save(world, &contents));
+
+        match result {
+            Ok(()) => info!("Loaded game from {}", request.path.display()),
+            Err(error) => error!(
+                "Failed to load game from {}: {error}",
+                request.path.display()
+            ),
+        }
+    }
+}
+
+/// Writes a save file as `(version: .., scene: ..)`.
+struct SaveFileSerializer<'a> {
+    scene: &'a DynamicScene,
+    registry: &'a TypeRegistry,
+}
+
+impl Serialize for SaveFileSerializer<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("SaveFile", 2)?;
+        state.serialize_field("version", &SAVE_VERSION)?;
+        state.serialize_field("scene", &SceneSerializer::new(self.scene, self.registry))?;
+        state.end()
+    }
+}
+
+/// Reads a save file and its version, rejecting versions this build doesn't know how
+/// to restore.
+struct SaveFileDeserializer<'a> {
+    registry: &'a TypeRegistry,
+}
+
+impl<'de> DeserializeSeed<'de> for SaveFileDeserializer<'_> {
+    type Value = (u32, DynamicScene);
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_struct("SaveFile", &["version", "scene"], self)
+    }
+}
+
+impl<'de> Visitor<'de> for SaveFileDeserializer<'_> {
+    type Value = (u32, DynamicScene);
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a save file")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut version = None;
+        let mut scene = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "version" => version = Some(map.next_value::<u32>()?),
+                "scene" => {
+                    scene = Some(map.next_value_seed(SceneDeserializer {
+                        type_registry: self.registry,
+                    })?)
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        let version = version.ok_or_else(|| de::Error::missing_field("version"))?;
+        if !(OLDEST_SAVE_VERSION..=SAVE_VERSION).contains(&version) {
+            return Err(de::Error::custom(format!(
+                "unsupported save version {version}, expected {OLDEST_SAVE_VERSION} to {SAVE_VERSION}"
+            )));
+        }
+
+        let scene = scene.ok_or_else(|| de::Error::missing_field("scene"))?;
+        Ok((version, scene))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_world() -> World {
+        let mut world = World::new();
+        let registry = AppTypeRegistry::default();
+        {
+            let mut registry = registry.write();
+            registry.register::<Name>();
+            registry.register::<Transform>();
+            registry.register::<RigidBody>();
+            registry.register::<LinearVelocity>();
+            registry.register::<AngularVelocity>();
+            registry.register::<CharacterController>();
+            registry.register::<Grounded>();
+            registry.register::<Health>();
+            registry.register::<Inventory>();
+            registry.register::<Projectile>();
+        }
+        world.insert_resource(registry);
+        world
+    }
+
+    /// Spawns the player and an enemy, returning them.
+    fn spawn_actors(world: &mut World, health: f32, keys: &[&str]) -> (Entity, Entity) {
+        let player = world
+            .spawn((
+                Name::new("Player"),
+                Transform::from_xyz(health, 0., 0.),
+                CharacterController,
+                Grounded::default(),
+                Health {
+                    current: health,
+                    max: 100.,
+                },
+                Inventory {
+                    keys: keys.iter().map(|key| key.to_string()).collect(),
+                },
+            ))
+            .id();
+        let enemy = world
+            .spawn((
+                Name::new("Enemy"),
+                Transform::from_xyz(0., health, 0.),
+                RigidBody::Dynamic,
+                LinearVelocity(Vec3::X * health),
+                Health {
+                    current: health / 2.,
+                    max: 100.,
+                },
+            ))
+            .id();
+        (player, enemy)
+    }
+
+    #[test]
+    fn a_save_restores_the_gameplay_state() {
+        let mut saved_world = test_world();
+        spawn_actors(&mut saved_world, 40., &["red"]);
+        saved_world.spawn((
+            Transform::from_xyz(1., 2., 3.),
+            Projectile {
+                damage: 25.,
+                ..default()
+            },
+        ));
+        let contents = serialize_save(&mut saved_world).unwrap();
+
+        let mut world = test_world();
+        let (player, enemy) = spawn_actors(&mut world, 90., &[]);
+        let stale_projectile = world
+            .spawn((Transform::default(), Projectile::default()))
+            .id();
+        deserialize_save(&mut world, &contents).unwrap();
+
+        assert_eq!(world.get::<Health>(player).unwrap().current, 40.);
+        assert_eq!(
+            world.get::<Transform>(player).unwrap().translation,
+            Vec3::new(40., 0., 0.)
+        );
+        assert_eq!(world.get::<Inventory>(player).unwrap().keys, ["red"]);
+
+        assert_eq!(world.get::<Health>(enemy).unwrap().current, 20.);
+        assert_eq!(world.get::<LinearVelocity>(enemy).unwrap().0, Vec3::X * 40.);
+
+        // Live projectiles are replaced by the saved ones
+        assert!(world.get_entity(stale_projectile).is_none());
+        let mut q_projectiles = world.query::<(&Transform, &Projectile)>();
+        let (transform, projectile) = q_projectiles.single(&world);
+        assert_eq!(transform.translation, Vec3::new(1., 2., 3.));
+        assert_eq!(projectile.damage, 25.);
+    }
+
+    fn with_version(contents: &str, version: u32) -> String {
+        contents.replacen(
+            &format!("version: {SAVE_VERSION}"),
+            &format!("version: {version}"),
+            1,
+        )
+    }
+
+    #[test]
+    fn saves_of_unknown_versions_are_rejected() {
+        let mut world = test_world();
+        spawn_actors(&mut world, 40., &[]);
+        let contents = serialize_save(&mut world).unwrap();
+
+        assert!(deserialize_save(&mut world, &with_version(&contents, 0)).is_err());
+        assert!(deserialize_save(&mut world, &with_version(&contents, SAVE_VERSION + 1)).is_err());
+        assert!(deserialize_save(&mut world, &contents).is_ok());
+    }
+
+    #[test]
+    fn version_1_saves_load_with_an_empty_inventory() {
+        let mut saved_world = test_world();
+        let (saved_player, _) = spawn_actors(&mut saved_world, 40., &[]);
+        saved_world.entity_mut(saved_player).remove::<Inventory>();
+        let contents = with_version(&serialize_save(&mut saved_world).unwrap(), 1);
+
+        let mut world = test_world();
+        let (player, _) = spawn_actors(&mut world, 90., &["red"]);
+        deserialize_save(&mut world, &contents).unwrap();
+
+        assert_eq!(world.get::<Health>(player).unwrap().current, 40.);
+        assert!(world.get::<Inventory>(player).unwrap().keys.is_empty());
+    }
+}
@@ -0,0 +1,447 @@
+use bevy::{
+    core_pipeline::prepass::DeferredPrepass,
+    pbr::{
+        deferred::DEFERRED_LIGHTING_SHADER_HANDLE, DefaultOpaqueRendererMethod, ExtendedMaterial,
+        MaterialExtension, MaterialExtensionKey, MaterialExtensionPipeline, OpaqueRendererMethod,
+    },
+    prelude::*,
+    render::{
+        mesh::MeshVertexBufferLayoutRef,
+        render_resource::{
+            AsBindGroup, RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+        },
+    },
+};
+
+use crate::MainCamera;
+
+pub struct ToonMaterialPlugin;
+
+impl Plugin for ToonMaterialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(MaterialPlugin::<ToonMaterial>::default())
+            .init_resource::<ToonRenderSettings>()
+            .add_systems(
+                Update,
+                (toggle_render_method, apply_toon_render_settings).chain(),
+            );
+
+        // Replace Bevy's deferred lighting shader with one that keeps the toon bands
+        app.world_mut().resource_mut::<Assets<Shader>>().insert(
+            DEFERRED_LIGHTING_SHADER_HANDLE.id(),
+            Shader::from_wgsl(
+                include_str!("../assets/shaders/toon_deferred_lighting.wgsl"),
+                "shaders/toon_deferred_lighting.wgsl",
+            ),
+        );
+    }
+}
+
+/// Selects the renderer used for opaque toon materials.
+///
+/// In deferred mode only the lighting bands are kept, as the band colors, ramp
+/// texture, rim light, specular highlight and outline need the material data
+/// that isn't stored in the gbuffer.
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct ToonRenderSettings {
+    /// Either [`OpaqueRendererMethod::Forward`] or [`OpaqueRendererMethod::Deferred`],
+    /// `Auto` falls back to forward.
+    pub method: OpaqueRendererMethod,
+}
+
+impl Default for ToonRenderSettings {
+    fn default() -> Self {
+        Self {
+            method: OpaqueRendererMethod::Forward,
+        }
+    }
+}
+
+/// Switches between the forward and deferred renderer with F3.
+fn toggle_render_method(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<ToonRenderSettings>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F3) {
+        settings.method = match settings.method {
+            OpaqueRendererMethod::Deferred => OpaqueRendererMethod::Forward,
+            _ => OpaqueRendererMethod::Deferred,
+        };
+    }
+}
+
+fn apply_toon_render_settings(
+    mut commands: Commands,
+    settings: Res<ToonRenderSettings>,
+    mut default_method: ResMut<DefaultOpaqueRendererMethod>,
+    mut msaa: ResMut<Msaa>,
+    q_cameras: Query<Entity, With<MainCamera>>,
+    q_added_cameras: Query<(), Added<MainCamera>>,
+) {
+    if !settings.is_changed() && q_added_cameras.is_empty() {
+        return;
+    }
+
+    for camera in &q_cameras {
+        match settings.method {
+            OpaqueRendererMethod::Deferred => {
+                commands.entity(camera).insert(DeferredPrepass);
+            }
+            _ => {
+                commands.entity(camera).remove::<DeferredPrepass>();
+            }
+        }
+    }
+
+    // The deferred renderer doesn't support MSAA
+    match settings.method {
+        OpaqueRendererMethod::Deferred => {
+            default_method.set_to_deferred();
+            *msaa = Msaa::Off;
+        }
+        _ => {
+            default_method.set_to_forward();
+            *msaa = Msaa::default();
+        }
+    }
+}
+
+/// The toon material used by every mesh of the game.
+pub type ToonMaterial = ExtendedMaterial<StandardMaterial, MyExtension>;
+
+/// Builds a [`ToonMaterial`], starting from the [`MyExtension`] defaults.
+#[derive(Clone, Debug)]
+pub struct ToonMaterialBuilder {
+    base: StandardMaterial,
+    extension: MyExtension,
+}
+
+impl ToonMaterialBuilder {
+    pub fn new(base_color: impl Into<Color>) -> Self {
+        Self {
+            base: StandardMaterial {
+                base_color: base_color.into(),
+                // Follows the `DefaultOpaqueRendererMethod` set from `ToonRenderSettings`
+                opaque_render_method: OpaqueRendererMethod::Auto,
+                ..Default::default()
+            },
+            extension: MyExtension::default(),
+        }
+    }
+
+    pub fn base_color_texture(mut self, texture: Handle<Image>) -> Self {
+        self.base.base_color_texture = Some(texture);
+        self
+    }
+
+    pub fn quantize_steps(mut self, steps: u32) -> Self {
+        self.extension.quantize_steps = steps;
+        self
+    }
+
+    pub fn shadow_color(mut self, color: impl Into<Color>) -> Self {
+        self.extension.shadow_color = color.into().to_linear();
+        self
+    }
+
+    pub fn highlight_color(mut self, color: impl Into<Color>) -> Self {
+        self.extension.highlight_color = color.into().to_linear();
+        self
+    }
+
+    pub fn rim_color(mut self, color: impl Into<Color>) -> Self {
+        self.extension.rim_color = color.into().to_linear();
+        self
+    }
+
+    pub fn rim_threshold(mut self, threshold: f32) -> Self {
+        self.extension.rim_threshold = threshold;
+        self
+    }
+
+    pub fn specular_color(mut self, color: impl Into<Color>) -> Self {
+        self.extension.specular_color = color.into().to_linear();
+        self
+    }
+
+    pub fn specular_threshold(mut self, threshold: f32) -> Self {
+        self.extension.specular_threshold = threshold;
+        self
+    }
+
+    pub fn glossiness(mut self, glossiness: f32) -> Self {
+        self.extension.glossiness = glossiness;
+        self
+    }
+
+    pub fn outline_color(mut self, color: impl Into<Color>) -> Self {
+        self.extension.outline_color = color.into().to_linear();
+        self
+    }
+
+    pub fn outline_thickness(mut self, thickness: f32) -> Self {
+        self.extension.outline_thickness = thickness;
+        self
+    }
+
+    pub fn ramp_texture(mut self, texture: Handle<Image>) -> Self {
+        self.extension.ramp_texture = Some(texture);
+        self
+    }
+
+    pub fn build(self) -> ToonMaterial {
+        ExtendedMaterial {
+            base: self.base,
+            extension: self.extension,
+        }
+    }
+}
+
+/// Toon shading on top of a [`StandardMaterial`], see `assets/shaders/toon_shader.wgsl`.
+///
+/// Outlines are found from the depth and normal prepass textures, so they are
+/// only drawn by cameras with a `DepthPrepass` and `NormalPrepass`.
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone)]
+#[bind_group_data(ToonMaterialKey)]
+pub struct MyExtension {
+    // We need to ensure that the bindings of the base material and the extension do not conflict,
+    // so we start from binding slot 100, leaving slots 0-99 for the base material.
+    /// The number of lighting bands.
+    #[uniform(100)]
+    pub quantize_steps: u32,
+    /// The tint of the darkest band, ignored when a ramp texture is set.
+    #[uniform(100)]
+    pub shadow_color: LinearRgba,
+    /// The tint of the brightest band, ignored when a ramp texture is set.
+    #[uniform(100)]
+    pub highlight_color: LinearRgba,
+    /// The color of the rim light, its alpha being the intensity.
+    #[uniform(100)]
+    pub rim_color: LinearRgba,
+    /// How close to the silhouette the rim light starts, from 0 to 1.
+    #[uniform(100)]
+    pub rim_threshold: f32,
+    /// The color of the specular highlight, its alpha being the intensity.
+    #[uniform(100)]
+    pub specular_color: LinearRgba,
+    /// The specular intensity above which the highlight is drawn, from 0 to 1.
+    #[uniform(100)]
+    pub specular_threshold: f32,
+    /// The specular exponent, higher values give smaller highlights.
+    #[uniform(100)]
+    pub glossiness: f32,
+    /// The color of the outline, its alpha being the opacity.
+    #[uniform(100)]
+    pub outline_color: LinearRgba,
+    /// The outline thickness in pixels, 0 disables the outline.
+    #[uniform(100)]
+    pub outline_thickness: f32,
+    /// The relative view depth difference between neighbouring pixels drawn as an outline.
+    #[uniform(100)]
+    pub outline_depth_threshold: f32,
+    /// The normal difference (one minus the cosine) between neighbouring pixels drawn as an outline.
+    #[uniform(100)]
+    pub outline_normal_threshold: f32,
+    /// A horizontal gradient sampled per band instead of the band colors.
+    #[texture(101)]
+    #[sampler(102)]
+    pub ramp_texture: Option<Handle<Image>>,
+}
+
+impl Default for MyExtension {
+    fn default() -> Self {
+        Self {
+            quantize_steps: 3,
+            shadow_color: LinearRgba::WHITE,
+            highlight_color: LinearRgba::WHITE,
+            rim_color: LinearRgba::new(1.0, 1.0, 1.0, 0.0),
+            rim_threshold: 0.7,
+            specular_color: LinearRgba::new(1.0, 1.0, 1.0, 0.0),
+            specular_threshold: 0.5,
+            glossiness: 32.0,
+            outline_color: LinearRgba::BLACK,
+            outline_thickness: 0.0,
+            outline_depth_threshold: 0.1,
+            outline_normal_threshold: 0.4,
+            ramp_texture: None,
+        }
+    }
+}
+
+impl MaterialExtension for MyExtension {
+    fn fragment_shader() -> ShaderRef {
+        "shaders/toon_shader.wgsl".into()
+    }
+
+    fn deferred_fragment_shader() -> ShaderRef {
+        "shaders/toon_shader.wgsl".into()
+    }
+
+    fn specialize(
+        _pipeline: &MaterialExtensionPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        key: MaterialExtensionKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        if key.bind_group_data.ramp {
+            if let Some(fragment) = descriptor.fragment.as_mut() {
+                fragment.shader_defs.push("TOON_RAMP".into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The pipeline key of [`MyExtension`], selecting between band colors and the ramp texture.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ToonMaterialKey {
+    ramp: bool,
+}
+
+impl From<&MyExtension> for ToonMaterialKey {
+    fn from(extension: &MyExtension) -> Self {
+        Self {
+            ramp: extension.ramp_texture.is_some(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use bevy::{
+        log::LogPlugin,
+        render::{
+            render_resource::{Shader, Source},
+            settings::{RenderCreation, WgpuSettings},
+            RenderPlugin,
+        },
+        window::ExitCondition,
+        winit::WinitPlugin,
+    };
+    use naga::valid::{Capabilities, ValidationFlags, Validator};
+    use naga_oil::compose::{
+        ComposableModuleDescriptor, Composer, NagaModuleDescriptor, ShaderDefValue, ShaderLanguage,
+        ShaderType,
+    };
+
+    use super::*;
+
+    /// The shaders Bevy's plugins register, without a window or a GPU.
+    fn bevy_shaders() -> Vec<Shader> {
+        let mut app = App::new();
+        app.add_plugins(
+            DefaultPlugins
+                .build()
+                .disable::<WinitPlugin>()
+                .disable::<LogPlugin>()
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    ..default()
+                })
+                .set(RenderPlugin {
+                    render_creation: RenderCreation::Automatic(WgpuSettings {
+                        backends: None,
+                        ..default()
+                    }),
+                    ..default()
+                }),
+        );
+        app.finish();
+
+        app.world()
+            .resource::<Assets<Shader>>()
+            .iter()
+            .map(|(_, shader)| shader.clone())
+            .collect()
+    }
+
+    /// A composer with the `bevy_pbr` and `bevy_render` modules our shaders import.
+    fn bevy_composer() -> Composer {
+        let mut composer = Composer::default().with_capabilities(Capabilities::all());
+
+        // Modules can only be added once the modules they import are
+        let mut pending: Vec<Shader> = bevy_shaders()
+            .into_iter()
+            .filter(|shader| matches!(shader.source, Source::Wgsl(_)))
+            .collect();
+        loop {
+            let count = pending.len();
+            pending.retain(|shader| {
+                composer
+                    .add_composable_module(ComposableModuleDescriptor {
+                        source: shader.source.as_str(),
+                        file_path: &shader.path,
+                        language: ShaderLanguage::Wgsl,
+                        ..default()
+                    })
+                    .is_err()
+            });
+            if pending.len() == count {
+                break;
+            }
+        }
+        composer
+    }
+
+    fn validate(composer: &mut Composer, source: &str, file_path: &str, defs: &[&str]) {
+        // The defs Bevy's pipelines set for every material
+        let mut shader_defs: HashMap<String, ShaderDefValue> = [
+            ("MAX_DIRECTIONAL_LIGHTS", ShaderDefValue::UInt(10)),
+            ("MAX_CASCADES_PER_LIGHT", ShaderDefValue::UInt(4)),
+            ("AVAILABLE_STORAGE_BUFFER_BINDINGS", ShaderDefValue::UInt(8)),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+        shader_defs.extend(
+            defs.iter()
+                .map(|def| (def.to_string(), ShaderDefValue::Bool(true))),
+        );
+
+        let module = composer
+            .make_naga_module(NagaModuleDescriptor {
+                source,
+                file_path,
+                shader_type: ShaderType::Wgsl,
+                shader_defs,
+                ..default()
+            })
+            .unwrap_or_else(|error| {
+                panic!(
+                    "Could not compose {file_path} with {defs:?}: {}",
+                    error.emit_to_string(composer)
+                )
+            });
+        if let Err(error) =
+            Validator::new(ValidationFlags::all(), Capabilities::all()).validate(&module)
+        {
+            panic!("Could not validate {file_path} with {defs:?}: {error:?}");
+        }
+    }
+
+    #[test]
+    fn toon_shaders_compose_and_validate() {
+        let mut composer = bevy_composer();
+
+        let toon_shader = include_str!("../assets/shaders/toon_shader.wgsl");
+        for defs in [
+            &[][..],
+            &["TOON_RAMP"],
+            &["DEPTH_PREPASS", "NORMAL_PREPASS"],
+            &["VERTEX_UVS", "VERTEX_NORMALS", "MULTISAMPLED"],
+            &["PREPASS_PIPELINE", "DEFERRED_PREPASS"],
+        ] {
+            validate(&mut composer, toon_shader, "shaders/toon_shader.wgsl", defs);
+        }
+
+        validate(
+            &mut composer,
+            include_str!("../assets/shaders/toon_deferred_lighting.wgsl"),
+            "shaders/toon_deferred_lighting.wgsl",
+            &[],
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    app_state::{AppState, LoadingAssets},
+    game_management::{GameLayer, Health, Team},
+    gltf_extras::{EnemySpawn, ExtrasAppExt},
+    level::{body_of, spawn_level, Level},
+    material_library::LibraryMaterial,
+    toon_material::ToonMaterial,
+    vfx::{SpawnVfx, VfxKind},
+};
+
+pub struct TriggerPlugin;
+
+impl Plugin for TriggerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<TriggerEntered>()
+            .add_event::<TriggerExited>()
+            .add_event::<SpawnWave>()
+            .add_event::<CheckpointReached>()
+            .register_extras_component::<TriggerVolume>("TriggerVolume")
+            .add_systems(
+                Update,
+                (detect_triggers, run_trigger_responses, spawn_waves)
+                    .chain()
+                    .run_if(in_state(AppState::InGame)),
+            );
+    }
+}
+
+/// A sensor reacting to bodies of the given layers entering it.
+///
+/// Authored as a glTF custom property, e.g.
+/// `TriggerVolume: (once: true, responses: [SpawnWave(kind: "grunt", count: 3)])`,
+/// the level generates a sensor collider for the node. Volumes spawned from
+/// code need a [`Collider`], a [`Sensor`] and [`TriggerVolume::collision_layers`].
+#[derive(Component, Deserialize, Debug, Clone)]
+pub struct TriggerVolume {
+    /// The layers of the bodies the volume reacts to.
+    #[serde(default = "TriggerVolume::default_layers")]
+    pub layers: Vec<GameLayer>,
+    /// Only reacts to the first body entering it.
+    #[serde(default)]
+    pub once: bool,
+    /// What happens when a body enters the volume.
+    #[serde(default)]
+    pub responses: Vec<TriggerResponse>,
+}
+
+impl TriggerVolume {
+    fn default_layers() -> Vec<GameLayer> {
+        vec![GameLayer::Player]
+    }
+
+    pub fn layer_mask(&self) -> LayerMask {
+        self.layers.iter().fold(LayerMask::NONE, |mask, layer| {
+            mask | LayerMask::from(*layer)
+        })
+    }
+
+    /// The collision layers of the sensor collider of the volume.
+    pub fn collision_layers(&self) -> CollisionLayers {
+        CollisionLayers::new(GameLayer::Default, self.layer_mask())
+    }
+
+    /// Whether a collider with the given layers sets the volume off.
+    fn detects(&self, layers: Option<&CollisionLayers>) -> bool {
+        let memberships = layers.copied().unwrap_or_default().memberships;
+        memberships & self.layer_mask() != LayerMask::NONE
+    }
+}
+
+impl Default for TriggerVolume {
+    fn default() -> Self {
+        Self {
+            layers: Self::default_layers(),
+            once: false,
+            responses: vec![],
+        }
+    }
+}
+
+/// What a [`TriggerVolume`] does when a body enters it.
+#[derive(Deserialize, Debug, Clone)]
+pub enum TriggerResponse {
+    /// Replaces the current levels with the given glTF scene, or a `.scn.ron` scene
+    /// authored in the editor.
+    LoadLevel {
+        scene: String,
+        #[serde(default)]
+        dynamic: bool,
+    },
+    /// Spawns `count` enemies at each [`EnemySpawn`] of the given kind.
+    SpawnWave {
+        kind: String,
+        #[serde(default = "default_wave_count")]
+        count: u32,
+    },
+    /// Plays a particle effect at the volume.
+    PlayEffect(VfxKind),
+    /// Makes the volume the place the player respawns at.
+    Checkpoint,
+}
+
+fn default_wave_count() -> u32 {
+    1
+}
+
+/// A [`TriggerVolume`] that only reacted once.
+#[derive(Component, Debug)]
+#[component(storage = "SparseSet")]
+pub struct Triggered;
+
+/// An event sent when a body enters a [`TriggerVolume`].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TriggerEntered {
+    pub trigger: Entity,
+    pub entity: Entity,
+}
+
+/// An event sent when a body leaves a [`TriggerVolume`].
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TriggerExited {
+    pub trigger: Entity,
+    pub entity: Entity,
+}
+
+/// An event that spawns enemies at the [`EnemySpawn`]s of the given kind.
+#[derive(Event, Clone, Debug)]
+pub struct SpawnWave {
+    pub kind: String,
+    pub count: u32,
+}
+
+/// An event sent when the player reaches a checkpoint.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct CheckpointReached {
+    pub checkpoint: Entity,
+    pub translation: Vec3,
+}
+
+/// Sends [`TriggerEntered`] and [`TriggerExited`] events for the collisions of trigger volumes.
+fn detect_triggers(
+    mut commands: Commands,
+    mut started_events: EventReader<CollisionStarted>,
+    mut ended_events: EventReader<CollisionEnded>,
+    mut entered_events: EventWriter<TriggerEntered>,
+    mut exited_events: EventWriter<TriggerExited>,
+    q_collider_parents: Query<&ColliderParent>,
+    q_volumes: Query<(&TriggerVolume, Has<Triggered>)>,
+    q_layers: Query<&CollisionLayers>,
+) {
+    let started = started_events
+        .read()
+        .map(|CollisionStarted(a, b)| (*a, *b, true));
+    let ended = ended_events
+        .read()
+        .map(|CollisionEnded(a, b)| (*a, *b, false));
+
+    for (a, b, entered) in started.chain(ended) {
+        for (sensor, other) in [(a, b), (b, a)] {
+            let trigger = body_of(sensor, &q_collider_parents);
+            let Ok((volume, triggered)) = q_volumes.get(trigger) else {
+                continue;
+            };
+            if !volume.detects(q_layers.get(other).ok()) {
+                continue;
+            }
+
+            let entity = body_of(other, &q_collider_parents);
+            if !entered {
+                exited_events.send(TriggerExited { trigger, entity });
+            } else if !triggered {
+                entered_events.send(TriggerEntered { trigger, entity });
+                if volume.once {
+                    commands.entity(trigger).insert(Triggered);
+                }
+            }
+        }
+    }
+}
+
+/// Runs the [`TriggerResponse`]s of the volumes that were entered.
+#[allow(clippy::too_many_arguments)]
+fn run_trigger_responses(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading_assets: ResMut<LoadingAssets>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut entered_events: EventReader<TriggerEntered>,
+    mut wave_events: EventWriter<SpawnWave>,
+    mut vfx_events: EventWriter<SpawnVfx>,
+    mut checkpoint_events: EventWriter<CheckpointReached>,
+    q_volumes: Query<(&TriggerVolume, &GlobalTransform)>,
+    q_levels: Query<Entity, With<Level>>,
+) {
+    for event in entered_events.read() {
+        let Ok((volume, transform)) = q_volumes.get(event.trigger) else {
+            continue;
+        };
+
+        for response in &volume.responses {
+            match response {
+                TriggerResponse::LoadLevel { scene, dynamic } => {
+                    for level in &q_levels {
+                        commands.entity(level).despawn_recursive();
+                    }
+                    // Let the previous level's assets unload, the new level is waited on alone
+                    loading_assets.0.clear();
+
+                    spawn_level(
+                        &mut commands,
+                        &asset_server,
+                        &mut loading_assets,
+                        scene,
+                        *dynamic,
+                    );
+                    next_state.set(AppState::Loading);
+                }
+                TriggerResponse::SpawnWave { kind, count } => {
+                    wave_events.send(SpawnWave {
+                        kind: kind.clone(),
+                        count: *count,
+                    });
+                }
+                TriggerResponse::PlayEffect(kind) => {
+                    vfx_events.send(SpawnVfx::new(*kind, transform.translation()));
+                }
+                TriggerResponse::Checkpoint => {
+                    checkpoint_events.send(CheckpointReached {
+                        checkpoint: event.trigger,
+                        translation: transform.translation(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Spawns the enemies of each [`SpawnWave`] around the matching spawn points.
+fn spawn_waves(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut wave_events: EventReader<SpawnWave>,
+    q_spawns: Query<(&EnemySpawn, &GlobalTransform)>,
+) {
+    for wave in wave_events.read() {
+        for (spawn, transform) in &q_spawns {
+            if spawn.kind != wave.kind {
+                continue;
+            }
+
+            for index in 0..wave.count {
+                // Spread out on a circle so the enemies don't overlap
+                let angle = index as f32 / wave.count as f32 * std::f32::consts::TAU;
+                let offset = if wave.count > 1 {
+                    Vec3::new(angle.cos(), 0.0, angle.sin()) * 1.5
+                } else {
+                    Vec3::ZERO
+                };
+
+                commands.spawn((
+                    Name::new(format!("{} enemy", wave.kind)),
+                    MaterialMeshBundle::<ToonMaterial> {
+                        mesh: meshes.add(Capsule3d::new(0.5, 1.0)),
+                        transform: Transform::from_translation(transform.translation() + offset),
+                        ..default()
+                    },
+                    LibraryMaterial::new("enemy"),
+                    Team::new("red"),
+                    RigidBody::Dynamic,
+                    Collider::capsule(1.0, 0.4),
+                    LockedAxes::ROTATION_LOCKED,
+                    CollisionLayers::new(
+                        GameLayer::Enemy,
+                        [
+                            GameLayer::Player,
+                            GameLayer::Enemy,
+                            GameLayer::Ground,
+                            GameLayer::Default,
+                            GameLayer::Projectile,
+                        ],
+                    ),
+                    Health::default(),
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use bevy::{prelude::*, transform::TransformSystem, utils::HashMap};
+use bevy_hanabi::{prelude::*, EffectSystems};
+use serde::Deserialize;
+
+use crate::{
+    app_state::LoadingAssets,
+    effect_def::{EffectDef, EffectDefLoader},
+};
+
+pub struct VfxPlugin;
+
+impl Plugin for VfxPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SpawnVfx>()
+            .init_asset::<EffectDef>()
+            .init_asset_loader::<EffectDefLoader>()
+            .init_resource::<VfxPool>()
+            .add_systems(Startup, setup_vfx_pool)
+            .add_systems(Update, update_vfx_effects)
+            .add_systems(
+                PostUpdate,
+                spawn_vfx
+                    .before(TransformSystem::TransformPropagate)
+                    .before(EffectSystems::TickSpawners),
+            );
+    }
+}
+
+/// The reusable particle effects of the game.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VfxKind {
+    MuzzleFlash,
+    ProjectileTrail,
+    /// Sparks thrown along the local Y axis, which should be the hit normal.
+    ImpactSparks,
+    LandingDust,
+    DeathBurst,
+}
+
+impl VfxKind {
+    pub const ALL: [VfxKind; 5] = [
+        VfxKind::MuzzleFlash,
+        VfxKind::ProjectileTrail,
+        VfxKind::ImpactSparks,
+        VfxKind::LandingDust,
+        VfxKind::DeathBurst,
+    ];
+
+    /// The number of pooled emitters, i.e. how many bursts can be started on the same frame.
+    fn pool_size(self) -> usize {
+        match self {
+            VfxKind::MuzzleFlash => 4,
+            VfxKind::ProjectileTrail => 16,
+            VfxKind::ImpactSparks => 8,
+            VfxKind::LandingDust => 4,
+            VfxKind::DeathBurst => 4,
+        }
+    }
+
+    /// The path of the [`EffectDef`] of the effect.
+    fn path(self) -> &'static str {
+        match self {
+            VfxKind::MuzzleFlash => "effects/muzzle_flash.effect.ron",
+            VfxKind::ProjectileTrail => "effects/projectile_trail.effect.ron",
+            VfxKind::ImpactSparks => "effects/impact_sparks.effect.ron",
+            VfxKind::LandingDust => "effects/landing_dust.effect.ron",
+            VfxKind::DeathBurst => "effects/death_burst.effect.ron",
+        }
+    }
+}
+
+/// An event that plays a particle effect at the given transform.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SpawnVfx {
+    pub kind: VfxKind,
+    pub transform: Transform,
+}
+
+impl SpawnVfx {
+    pub fn new(kind: VfxKind, translation: Vec3) -> Self {
+        Self {
+            kind,
+            transform: Transform::from_translation(translation),
+        }
+    }
+
+    /// Points the local Y axis of the effect, along which particles are emitted, in `direction`.
+    pub fn with_direction(mut self, direction: Vec3) -> Self {
+        if let Some(direction) = direction.try_normalize() {
+            self.transform.rotation = Quat::from_rotation_arc(Vec3::Y, direction);
+        }
+        self
+    }
+}
+
+/// The pooled emitters of every [`VfxKind`], reused in turn.
+#[derive(Resource, Default)]
+pub struct VfxPool {
+    /// The definition of each effect and the Hanabi effect built from it.
+    effects: HashMap<VfxKind, (Handle<EffectDef>, Handle<EffectAsset>)>,
+    emitters: HashMap<VfxKind, Vec<Entity>>,
+    next: HashMap<VfxKind, usize>,
+}
+
+impl VfxPool {
+    fn next_emitter(&mut self, kind: VfxKind) -> Option<Entity> {
+        let emitters = self.emitters.get(&kind)?;
+        let next = self.next.entry(kind).or_default();
+        let emitter = emitters.get(*next % emitters.len())?;
+        *next = (*next + 1) % emitters.len();
+        Some(*emitter)
+    }
+}
+
+fn setup_vfx_pool(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut loading_assets: ResMut<LoadingAssets>,
+    effects: Res<Assets<EffectAsset>>,
+    mut pool: ResMut<VfxPool>,
+) {
+    for kind in VfxKind::ALL {
+        // Filled in by `update_vfx_effects` once the definition is loaded
+        let effect = effects.reserve_handle();
+        let definition = loading_assets.add(asset_server.load(kind.path()));
+        pool.effects.insert(kind, (definition, effect.clone()));
+
+        let emitters = (0..kind.pool_size())
+            .map(|_| {
+                commands
+                    .spawn((
+                        Name::new(format!("{kind:?} emitter")),
+                        ParticleEffectBundle::new(effect.clone()),
+                    ))
+                    .id()
+            })
+            .collect();
+        pool.emitters.insert(kind, emitters);
+    }
+}
+
+/// (Re)builds the effects whose definition was loaded or modified.
+fn update_vfx_effects(
+    mut asset_events: EventReader<AssetEvent<EffectDef>>,
+    definitions: Res<Assets<EffectDef>>,
+    mut effects: ResMut<Assets<EffectAsset>>,
+    pool: Res<VfxPool>,
+    mut q_emitters: Query<&mut ParticleEffect>,
+) {
+    for event in asset_events.read() {
+        for (kind, (definition, effect)) in &pool.effects {
+            if !event.is_loaded_with_dependencies(definition) && !event.is_modified(definition) {
+                continue;
+            }
+            let Some(definition) = definitions.get(definition) else {
+                continue;
+            };
+
+            effects.insert(effect, definition.effect(&format!("{kind:?}")));
+
+            // Have Hanabi pick up the new effect on the existing emitters
+            for emitter in pool.emitters.get(kind).into_iter().flatten() {
+                if let Ok(mut particle_effect) = q_emitters.get_mut(*emitter) {
+                    particle_effect.set_changed();
+                }
+            }
+        }
+    }
+}
+
+/// Moves the next pooled emitter of each [`SpawnVfx`] kind to its transform and restarts it.
+fn spawn_vfx(
+    mut spawn_events: EventReader<SpawnVfx>,
+    mut pool: ResMut<VfxPool>,
+    mut q_emitters: Query<(&mut Transform, Option<&mut EffectSpawner>)>,
+) {
+    for event in spawn_events.read() {
+        let Some(emitter) = pool.next_emitter(event.kind) else {
+            continue;
+        };
+        let Ok((mut transform, spawner)) = q_emitters.get_mut(emitter) else {
+            continue;
+        };
+
+        *transform = event.transform;
+        // The spawner is added by Hanabi once the effect is ready
+        if let Some(mut spawner) = spawner {
+            spawner.reset();
+        }
+    }
+}